@@ -0,0 +1,192 @@
+use std::sync::Arc;
+
+use axum::{
+    Json, Router,
+    extract::{Path, Query, Request, State},
+    http::{StatusCode, header},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{get, post, put},
+};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::net::TcpListener;
+
+use crate::{
+    config::shared_config::SharedConfig,
+    queue::{
+        manager::{JobDetail, JobFilter, JobSummary, QueueManager},
+        models::JobPriority,
+        queue_error::QueueError,
+        spool::Spool,
+    },
+    shutdown::Shutdown,
+};
+
+// API HTTP/JSON de administração da fila. Todas as rotas exigem o header
+// Authorization: Bearer <admin.token>.
+#[derive(Clone)]
+struct AdminState {
+    manager: Arc<QueueManager>,
+    config: Arc<SharedConfig>,
+}
+
+#[derive(Deserialize)]
+struct FlushParams {
+    domain: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct PriorityRequest {
+    priority: JobPriority,
+}
+
+struct ApiError(QueueError);
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = match &self.0 {
+            QueueError::NotFoundError(_) => StatusCode::NOT_FOUND,
+            QueueError::BusyError(_) => StatusCode::CONFLICT,
+            _ => {
+                tracing::error!("Erro na API de administração: {}", self.0);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        };
+        (status, Json(json!({ "error": self.0.to_string() }))).into_response()
+    }
+}
+
+impl From<QueueError> for ApiError {
+    fn from(err: QueueError) -> Self {
+        ApiError(err)
+    }
+}
+
+type ApiResult<T> = Result<Json<T>, ApiError>;
+
+pub async fn serve(
+    listen: String,
+    config: Arc<SharedConfig>,
+    spool: Arc<Spool>,
+    mut shutdown: Shutdown,
+) {
+    let listener = match TcpListener::bind(&listen).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            tracing::error!("Erro ao abrir a API de administração em {}: {}", listen, e);
+            return;
+        }
+    };
+    tracing::info!("API de administração em http://{}", listen);
+
+    let state = AdminState {
+        manager: Arc::new(QueueManager::new(spool)),
+        config,
+    };
+    let app = Router::new()
+        .route("/queue", get(list))
+        .route("/queue/flush", post(flush))
+        .route("/queue/{id}", get(show).delete(delete))
+        .route("/queue/{id}/retry", post(retry))
+        .route("/queue/{id}/hold", post(hold))
+        .route("/queue/{id}/release", post(release))
+        .route("/queue/{id}/bounce", post(bounce))
+        .route("/queue/{id}/priority", put(set_priority))
+        .layer(middleware::from_fn_with_state(state.clone(), authenticate))
+        .with_state(state);
+
+    let result = axum::serve(listener, app)
+        .with_graceful_shutdown(async move { shutdown.wait().await })
+        .await;
+
+    if let Err(e) = result {
+        tracing::error!("Erro na API de administração: {}", e);
+    }
+}
+
+// O token é lido a cada requisição para acompanhar recargas da configuração
+async fn authenticate(State(state): State<AdminState>, request: Request, next: Next) -> Response {
+    let expected = state.config.load().admin.token.clone();
+    let provided = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+
+    match provided {
+        Some(token) if !expected.is_empty() && constant_time_eq(token, &expected) => {
+            next.run(request).await
+        }
+        _ => (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Bearer")],
+            Json(json!({ "error": "não autorizado" })),
+        )
+            .into_response(),
+    }
+}
+
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+            == 0
+}
+
+async fn list(
+    State(state): State<AdminState>,
+    Query(filter): Query<JobFilter>,
+) -> ApiResult<Vec<JobSummary>> {
+    let jobs = state.manager.list(&filter).await?;
+    Ok(Json(jobs.iter().map(JobSummary::from).collect()))
+}
+
+async fn show(State(state): State<AdminState>, Path(id): Path<String>) -> ApiResult<JobDetail> {
+    let job = state.manager.show(&id).await?;
+    Ok(Json(JobDetail::from(&job)))
+}
+
+async fn retry(State(state): State<AdminState>, Path(id): Path<String>) -> ApiResult<JobSummary> {
+    let job = state.manager.retry(&id).await?;
+    Ok(Json(JobSummary::from(&job)))
+}
+
+async fn hold(State(state): State<AdminState>, Path(id): Path<String>) -> ApiResult<JobSummary> {
+    let job = state.manager.hold(&id).await?;
+    Ok(Json(JobSummary::from(&job)))
+}
+
+async fn release(State(state): State<AdminState>, Path(id): Path<String>) -> ApiResult<JobSummary> {
+    let job = state.manager.release(&id).await?;
+    Ok(Json(JobSummary::from(&job)))
+}
+
+async fn set_priority(
+    State(state): State<AdminState>,
+    Path(id): Path<String>,
+    Json(request): Json<PriorityRequest>,
+) -> ApiResult<JobSummary> {
+    let job = state.manager.set_priority(&id, request.priority).await?;
+    Ok(Json(JobSummary::from(&job)))
+}
+
+async fn delete(State(state): State<AdminState>, Path(id): Path<String>) -> ApiResult<JobSummary> {
+    let job = state.manager.delete(&id).await?;
+    Ok(Json(JobSummary::from(&job)))
+}
+
+async fn bounce(State(state): State<AdminState>, Path(id): Path<String>) -> ApiResult<JobSummary> {
+    let config = state.config.load();
+    let job = state.manager.bounce(&id, &config).await?;
+    Ok(Json(JobSummary::from(&job)))
+}
+
+async fn flush(
+    State(state): State<AdminState>,
+    Query(params): Query<FlushParams>,
+) -> ApiResult<serde_json::Value> {
+    let flushed = state.manager.flush(params.domain.as_deref()).await?;
+    Ok(Json(json!({ "flushed": flushed })))
+}
@@ -0,0 +1,103 @@
+pub mod sealer;
+pub mod validator;
+
+use std::{collections::BTreeMap, fmt};
+
+use crate::dkim::{message::HeaderField, verifier};
+
+// Limite de conjuntos em uma cadeia (RFC 8617 §4.2.1)
+pub const MAX_INSTANCES: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArcResult {
+    None,
+    Pass,
+    Fail,
+}
+
+impl fmt::Display for ArcResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArcResult::None => write!(f, "none"),
+            ArcResult::Pass => write!(f, "pass"),
+            ArcResult::Fail => write!(f, "fail"),
+        }
+    }
+}
+
+pub struct ArcOutcome {
+    pub result: ArcResult,
+    pub reason: Option<String>,
+    // d= de cada ARC-Seal, da instância 1 à última
+    pub sealers: Vec<String>,
+}
+
+// Um conjunto ARC: os três headers de uma instância
+pub struct ArcSet<'a> {
+    pub instance: usize,
+    pub results: &'a HeaderField<'a>,
+    pub signature: &'a HeaderField<'a>,
+    pub seal: &'a HeaderField<'a>,
+}
+
+impl ArcSet<'_> {
+    // cv= do ARC-Seal
+    pub fn cv(&self) -> String {
+        tag(self.seal, "cv").to_ascii_lowercase()
+    }
+}
+
+// Conjuntos da mensagem, ordenados pela instância. Vazio quando não há
+// headers ARC; erro quando falta ou sobra algum (RFC 8617 §5.2).
+pub fn chain<'a>(fields: &'a [HeaderField<'a>]) -> Result<Vec<ArcSet<'a>>, String> {
+    let mut instances: BTreeMap<usize, [Option<&HeaderField>; 3]> = BTreeMap::new();
+    for field in fields {
+        let kind = match field.name.to_ascii_lowercase().as_str() {
+            "arc-authentication-results" => 0,
+            "arc-message-signature" => 1,
+            "arc-seal" => 2,
+            _ => continue,
+        };
+        let instance = instance(field)
+            .filter(|i| (1..=MAX_INSTANCES).contains(i))
+            .ok_or_else(|| format!("invalid instance in {}", field.name))?;
+
+        let slot = &mut instances.entry(instance).or_default()[kind];
+        if slot.is_some() {
+            return Err(format!("duplicate {} for i={}", field.name, instance));
+        }
+        *slot = Some(field);
+    }
+
+    let mut sets = Vec::new();
+    for (expected, (instance, headers)) in (1..).zip(instances) {
+        let [Some(results), Some(signature), Some(seal)] = headers else {
+            return Err(format!("incomplete ARC set i={}", instance));
+        };
+        if instance != expected {
+            return Err(format!("missing ARC set i={}", expected));
+        }
+        sets.push(ArcSet {
+            instance,
+            results,
+            signature,
+            seal,
+        });
+    }
+    Ok(sets)
+}
+
+// i= de qualquer header ARC. No ARC-Authentication-Results é a primeira
+// tag, antes do authserv-id.
+fn instance(field: &HeaderField) -> Option<usize> {
+    verifier::field_value(field.raw)
+        .split(';')
+        .find_map(|item| item.split_once('=').filter(|(name, _)| name.trim() == "i"))
+        .and_then(|(_, value)| value.trim().parse().ok())
+}
+
+fn tag(field: &HeaderField, name: &str) -> String {
+    verifier::parse_tags(verifier::field_value(field.raw))
+        .and_then(|mut tags| tags.remove(name))
+        .unwrap_or_default()
+}
@@ -0,0 +1,100 @@
+use crate::{
+    arc::{self, MAX_INSTANCES},
+    dkim::{
+        DkimSigner,
+        canonicalization::{self, Canonicalization},
+        dkim_error::DkimError,
+        message::{self, HeaderField},
+        verifier,
+    },
+};
+
+// Novo conjunto ARC para a mensagem (RFC 8617 §5.1): ARC-Seal,
+// ARC-Message-Signature e ARC-Authentication-Results, nessa ordem e sem o
+// CRLF final. Só mensagens que recebemos e autenticamos são seladas, com o
+// nosso Authentication-Results copiado para o novo conjunto. Vazio quando
+// a mensagem não deve ser selada.
+pub fn seal(
+    signer: &DkimSigner,
+    domain: &str,
+    authserv_id: &str,
+    raw_message: &str,
+) -> Result<Vec<String>, DkimError> {
+    let (headers, _) = message::split(raw_message);
+    let fields = message::header_fields(headers);
+
+    let Some(results) = fields.iter().find(|f| is_own_results(f, authserv_id)) else {
+        return Ok(Vec::new());
+    };
+    // Cadeias malformadas, cheias ou já com cv=fail não recebem novos conjuntos
+    let Ok(sets) = arc::chain(&fields) else {
+        return Ok(Vec::new());
+    };
+    if sets.len() >= MAX_INSTANCES || sets.last().is_some_and(|set| set.cv() == "fail") {
+        return Ok(Vec::new());
+    }
+
+    // cv= vem da validação feita na recepção (arc= no nosso Authentication-Results)
+    let cv = if sets.is_empty() {
+        "none"
+    } else {
+        match arc_result(results).as_deref() {
+            Some("pass") => "pass",
+            Some("fail") => "fail",
+            _ => return Ok(Vec::new()),
+        }
+    };
+
+    let instance = sets.len() + 1;
+    let aar = format!(
+        "ARC-Authentication-Results: i={};{}",
+        instance,
+        verifier::field_value(results.raw)
+    );
+    let Some(ams) = signer.arc_message_signature(raw_message, domain, instance)? else {
+        return Ok(Vec::new());
+    };
+
+    // Com cv=fail, o selo cobre só o próprio conjunto
+    let relaxed = |raw: &str| canonicalization::header(raw, Canonicalization::Relaxed);
+    let mut covered = String::new();
+    if cv != "fail" {
+        for set in &sets {
+            covered.push_str(&relaxed(set.results.raw));
+            covered.push_str(&relaxed(set.signature.raw));
+            covered.push_str(&relaxed(set.seal.raw));
+        }
+    }
+    covered.push_str(&relaxed(&aar));
+    covered.push_str(&relaxed(&ams));
+
+    let Some(seal) = signer.arc_seal(domain, instance, cv, &covered)? else {
+        return Ok(Vec::new());
+    };
+    Ok(vec![seal, ams, aar])
+}
+
+fn is_own_results(field: &HeaderField, authserv_id: &str) -> bool {
+    field.name.eq_ignore_ascii_case("authentication-results")
+        && verifier::field_value(field.raw)
+            .split(';')
+            .next()
+            .and_then(|id| id.split_whitespace().next())
+            .is_some_and(|id| id.eq_ignore_ascii_case(authserv_id))
+}
+
+// Resultado de arc= em um Authentication-Results
+fn arc_result(field: &HeaderField) -> Option<String> {
+    verifier::field_value(field.raw)
+        .split(';')
+        .skip(1)
+        .filter_map(|result| result.trim().strip_prefix("arc="))
+        .map(|value| {
+            value
+                .split_whitespace()
+                .next()
+                .unwrap_or_default()
+                .to_ascii_lowercase()
+        })
+        .next()
+}
@@ -0,0 +1,204 @@
+use std::sync::Arc;
+
+use base64::{Engine, engine::general_purpose::STANDARD as B64};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    arc::{self, ArcOutcome, ArcResult, ArcSet},
+    dkim::{
+        canonicalization::{self, Canonicalization},
+        message::{self, HeaderField},
+        verifier::{self, DkimVerifier, Failure},
+    },
+    dns::Resolver,
+};
+
+pub struct ArcValidator {
+    verifier: DkimVerifier,
+}
+
+impl ArcValidator {
+    pub fn new(resolver: Arc<dyn Resolver>) -> Self {
+        Self {
+            verifier: DkimVerifier::new(resolver),
+        }
+    }
+
+    // Validação da cadeia (RFC 8617 §5.2): a estrutura, a última
+    // ARC-Message-Signature e todos os ARC-Seal
+    pub async fn validate(&self, raw_message: &str) -> ArcOutcome {
+        let (headers, body) = message::split(raw_message);
+        let fields = message::header_fields(headers);
+
+        let sets = match arc::chain(&fields) {
+            Ok(sets) => sets,
+            Err(reason) => return outcome(ArcResult::Fail, Some(reason), Vec::new()),
+        };
+        let Some(latest) = sets.last() else {
+            return outcome(ArcResult::None, None, Vec::new());
+        };
+        let sealers = sets
+            .iter()
+            .map(|set| arc::tag(set.seal, "d").to_ascii_lowercase())
+            .collect();
+
+        if latest.cv() == "fail" {
+            let reason = format!("i={} sealed with cv=fail", latest.instance);
+            return outcome(ArcResult::Fail, Some(reason), sealers);
+        }
+        for set in &sets {
+            let expected = if set.instance == 1 { "none" } else { "pass" };
+            if set.cv() != expected {
+                let reason = format!("i={} has cv={}", set.instance, set.cv());
+                return outcome(ArcResult::Fail, Some(reason), sealers);
+            }
+        }
+
+        if let Err(Failure(_, reason)) = self
+            .verify_message_signature(latest.signature, &fields, body)
+            .await
+        {
+            let reason = format!("i={} message signature: {}", latest.instance, reason);
+            return outcome(ArcResult::Fail, Some(reason), sealers);
+        }
+
+        for end in (1..=sets.len()).rev() {
+            if let Err(Failure(_, reason)) = self.verify_seal(&sets[..end]).await {
+                let reason = format!("i={} seal: {}", end, reason);
+                return outcome(ArcResult::Fail, Some(reason), sealers);
+            }
+        }
+
+        outcome(ArcResult::Pass, None, sealers)
+    }
+
+    // Como uma DKIM-Signature, mas com i= no lugar de v= (RFC 8617 §4.1.2)
+    async fn verify_message_signature(
+        &self,
+        field: &HeaderField<'_>,
+        fields: &[HeaderField<'_>],
+        body: &str,
+    ) -> Result<(), Failure> {
+        let tags = verifier::parse_tags(verifier::field_value(field.raw))
+            .ok_or_else(|| Failure::perm("malformed signature"))?;
+        for required in ["i", "a", "b", "bh", "d", "h", "s"] {
+            if !tags.contains_key(required) {
+                return Err(Failure::perm(format!("missing {}= tag", required)));
+            }
+        }
+
+        let key_type = key_type(&tags["a"])?;
+        let (header_c, body_c) = match tags.get("c") {
+            Some(c) => Canonicalization::parse_pair(c)
+                .ok_or_else(|| Failure::perm("unknown canonicalization"))?,
+            None => (Canonicalization::Simple, Canonicalization::Simple),
+        };
+
+        let signed_headers: Vec<String> = tags["h"]
+            .split(':')
+            .map(|h| h.trim().to_ascii_lowercase())
+            .collect();
+        if signed_headers.iter().any(|h| h == "arc-seal") {
+            return Err(Failure::perm("ARC-Seal must not be signed"));
+        }
+
+        let signature = B64
+            .decode(&tags["b"])
+            .map_err(|_| Failure::perm("malformed b= tag"))?;
+        let body_hash = B64
+            .decode(&tags["bh"])
+            .map_err(|_| Failure::perm("malformed bh= tag"))?;
+
+        let canonical_body = canonicalization::body(body, body_c);
+        if Sha256::digest(canonical_body.as_bytes()).as_slice() != body_hash.as_slice() {
+            return Err(Failure::fail("body hash did not verify"));
+        }
+
+        let mut data = String::new();
+        for selected in message::select(fields, &signed_headers) {
+            data.push_str(&canonicalization::header(selected.raw, header_c));
+        }
+        let unsigned = canonicalization::header(&verifier::without_signature(field.raw), header_c);
+        data.push_str(unsigned.trim_end_matches("\r\n"));
+
+        self.verify(&tags["s"], &tags["d"], key_type, &data, &signature)
+            .await
+    }
+
+    // O selo cobre os conjuntos de 1 até a sua instância, sempre com
+    // relaxed (RFC 8617 §5.1.1)
+    async fn verify_seal(&self, sets: &[ArcSet<'_>]) -> Result<(), Failure> {
+        let Some((latest, previous)) = sets.split_last() else {
+            return Ok(());
+        };
+        let tags = verifier::parse_tags(verifier::field_value(latest.seal.raw))
+            .ok_or_else(|| Failure::perm("malformed seal"))?;
+        for required in ["i", "a", "b", "cv", "d", "s"] {
+            if !tags.contains_key(required) {
+                return Err(Failure::perm(format!("missing {}= tag", required)));
+            }
+        }
+        if tags.contains_key("h") {
+            return Err(Failure::perm("h= not allowed in ARC-Seal"));
+        }
+
+        let key_type = key_type(&tags["a"])?;
+        let signature = B64
+            .decode(&tags["b"])
+            .map_err(|_| Failure::perm("malformed b= tag"))?;
+
+        let relaxed = |raw: &str| canonicalization::header(raw, Canonicalization::Relaxed);
+        let mut data = String::new();
+        for set in previous {
+            data.push_str(&relaxed(set.results.raw));
+            data.push_str(&relaxed(set.signature.raw));
+            data.push_str(&relaxed(set.seal.raw));
+        }
+        data.push_str(&relaxed(latest.results.raw));
+        data.push_str(&relaxed(latest.signature.raw));
+        let unsigned = relaxed(&verifier::without_signature(latest.seal.raw));
+        data.push_str(unsigned.trim_end_matches("\r\n"));
+
+        self.verify(&tags["s"], &tags["d"], key_type, &data, &signature)
+            .await
+    }
+
+    async fn verify(
+        &self,
+        selector: &str,
+        domain: &str,
+        key_type: &str,
+        data: &str,
+        signature: &[u8],
+    ) -> Result<(), Failure> {
+        let key = self
+            .verifier
+            .fetch_key(selector, &domain.to_ascii_lowercase())
+            .await?;
+        if !key.key_type.eq_ignore_ascii_case(key_type) {
+            return Err(Failure::perm("key type does not match a="));
+        }
+
+        if verifier::verify_data(key_type, &key.public_key, data.as_bytes(), signature)? {
+            Ok(())
+        } else {
+            Err(Failure::fail("signature did not verify"))
+        }
+    }
+}
+
+fn key_type(algorithm: &str) -> Result<&'static str, Failure> {
+    match algorithm.to_ascii_lowercase().as_str() {
+        "rsa-sha256" => Ok("rsa"),
+        "ed25519-sha256" => Ok("ed25519"),
+        _ => Err(Failure::perm("unsupported algorithm")),
+    }
+}
+
+fn outcome(result: ArcResult, reason: Option<String>, sealers: Vec<String>) -> ArcOutcome {
+    ArcOutcome {
+        result,
+        reason,
+        sealers,
+    }
+}
@@ -0,0 +1,27 @@
+use std::{error::Error, fmt};
+
+#[derive(Debug)]
+pub enum AuthError {
+    IoError(std::io::Error),
+    // Linha malformada em auth.users_path, com o número da linha
+    FormatError(usize),
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuthError::IoError(e) => write!(f, "Erro de I/O: {}", e),
+            AuthError::FormatError(line) => {
+                write!(f, "Linha {} inválida no arquivo de usuários", line)
+            }
+        }
+    }
+}
+
+impl Error for AuthError {}
+
+impl From<std::io::Error> for AuthError {
+    fn from(err: std::io::Error) -> Self {
+        AuthError::IoError(err)
+    }
+}
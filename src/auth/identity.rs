@@ -0,0 +1,50 @@
+use crate::auth::User;
+
+// Um usuário pode usar o próprio login, quando ele é um endereço, e as
+// identidades configuradas
+pub fn allowed(user: &User, address: &str) -> bool {
+    user.username.eq_ignore_ascii_case(address)
+        || user
+            .identities
+            .iter()
+            .any(|identity| matches(identity, address))
+}
+
+// Uma identidade é um endereço exato, @domínio para qualquer endereço do
+// domínio ou um padrão com * (por exemplo, *@*.example.com ou só *)
+pub fn matches(identity: &str, address: &str) -> bool {
+    let identity = identity.trim().to_ascii_lowercase();
+    let address = address.to_ascii_lowercase();
+
+    if let Some(domain) = identity.strip_prefix('@') {
+        return address
+            .rsplit_once('@')
+            .is_some_and(|(local, d)| !local.is_empty() && d == domain);
+    }
+    if identity.contains('*') {
+        return wildcard(identity.as_bytes(), address.as_bytes());
+    }
+    identity == address
+}
+
+// * corresponde a qualquer sequência, inclusive vazia
+fn wildcard(pattern: &[u8], text: &[u8]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack = None;
+    while t < text.len() {
+        if p < pattern.len() && pattern[p] == b'*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if p < pattern.len() && pattern[p] == text[t] {
+            p += 1;
+            t += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            t = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == b'*')
+}
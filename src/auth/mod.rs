@@ -0,0 +1,110 @@
+pub mod auth_error;
+pub mod identity;
+pub mod password;
+
+use std::{
+    path::Path,
+    sync::{Arc, OnceLock},
+};
+
+use tokio::sync::Semaphore;
+
+use crate::{
+    auth::auth_error::AuthError,
+    config::{Config, auth_config::AuthConfig},
+};
+
+// Cada verificação de Argon2 ocupa uma CPU e 64 MiB: uma rajada de AUTH não
+// pode passar de uma verificação por CPU
+static VERIFICATIONS: OnceLock<Semaphore> = OnceLock::new();
+
+// Usuário do backend: as credenciais e as identidades vêm do mesmo lugar
+pub struct User {
+    pub username: String,
+    pub password_hash: String,
+    pub identities: Vec<String>,
+}
+
+pub fn lookup(config: &AuthConfig, username: &str) -> Result<Option<User>, AuthError> {
+    match config.backend.as_str() {
+        "file" => lookup_file(&config.users_path, username),
+        _ => Ok(config
+            .users
+            .iter()
+            .find(|user| user.username.eq_ignore_ascii_case(username))
+            .map(|user| User {
+                username: user.username.clone(),
+                password_hash: user.password_hash.clone(),
+                identities: user.identities.clone(),
+            })),
+    }
+}
+
+// Usuários desconhecidos e senhas erradas têm o mesmo resultado
+pub fn authenticate(
+    config: &AuthConfig,
+    username: &str,
+    password: &str,
+) -> Result<bool, AuthError> {
+    Ok(lookup(config, username)?
+        .is_some_and(|user| password::verify(&user.password_hash, password)))
+}
+
+// authenticate() fora das threads do runtime, esperando a vez no limite de
+// verificações simultâneas
+pub async fn authenticate_blocking(
+    config: Arc<Config>,
+    username: String,
+    password: String,
+) -> Result<bool, AuthError> {
+    let semaphore = VERIFICATIONS.get_or_init(|| {
+        Semaphore::new(std::thread::available_parallelism().map_or(1, |n| n.get()))
+    });
+    let _permit = semaphore
+        .acquire()
+        .await
+        .map_err(|e| AuthError::IoError(std::io::Error::other(e)))?;
+
+    tokio::task::spawn_blocking(move || authenticate(&config.auth, &username, &password))
+        .await
+        .unwrap_or_else(|e| Err(AuthError::IoError(e.into())))
+}
+
+pub fn may_send_as(config: &AuthConfig, username: &str, address: &str) -> Result<bool, AuthError> {
+    Ok(lookup(config, username)?.is_some_and(|user| identity::allowed(&user, address)))
+}
+
+// Lido a cada consulta, para que mudanças no arquivo valham sem reload.
+// Formato: login:hash:identidade,identidade,... (# inicia um comentário)
+fn lookup_file(path: &Path, username: &str) -> Result<Option<User>, AuthError> {
+    let content = std::fs::read_to_string(path)?;
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut fields = line.splitn(3, ':');
+        let (Some(login), Some(hash)) = (fields.next(), fields.next()) else {
+            return Err(AuthError::FormatError(i + 1));
+        };
+        if !login.eq_ignore_ascii_case(username) {
+            continue;
+        }
+
+        let identities = fields
+            .next()
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|identity| !identity.is_empty())
+            .map(str::to_string)
+            .collect();
+        return Ok(Some(User {
+            username: login.to_string(),
+            password_hash: hash.to_string(),
+            identities,
+        }));
+    }
+    Ok(None)
+}
@@ -0,0 +1,111 @@
+use argon2::{
+    Algorithm, Argon2, Params, PasswordHash, PasswordHasher, PasswordVerifier, Version,
+    password_hash::SaltString,
+};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use rand::RngCore;
+use sha2::{Digest, Sha256, Sha512};
+
+const SALT_LEN: usize = 16;
+
+// Os mesmos parâmetros do doveadm pw -s ARGON2ID: 64 MiB, 3 passadas
+const ARGON2_MEMORY_KIB: u32 = 65536;
+const ARGON2_ITERATIONS: u32 = 3;
+const ARGON2_PARALLELISM: u32 = 1;
+
+// Hashes no formato do Dovecot, com o esquema entre chaves. {ARGON2ID} e
+// {ARGON2I} levam a string PHC ($argon2id$v=19$...); {SSHA256} e {SSHA512},
+// base64 de digest(senha || salt) || salt, e só são aceitos na verificação.
+pub fn verify(hash: &str, password: &str) -> bool {
+    let Some((scheme, encoded)) = split(hash) else {
+        return false;
+    };
+    if is_argon2(&scheme) {
+        return PasswordHash::new(encoded.trim()).is_ok_and(|parsed| {
+            Argon2::default()
+                .verify_password(password.as_bytes(), &parsed)
+                .is_ok()
+        });
+    }
+
+    let Ok(decoded) = STANDARD.decode(encoded.trim()) else {
+        return false;
+    };
+    let Some(len) = digest_len(&scheme) else {
+        return false;
+    };
+    if decoded.len() <= len {
+        return false;
+    }
+
+    let (expected, salt) = decoded.split_at(len);
+    let actual = digest(&scheme, password.as_bytes(), salt);
+    // Comparação em tempo constante
+    actual.len() == expected.len()
+        && actual
+            .iter()
+            .zip(expected)
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+pub fn is_supported(hash: &str) -> bool {
+    split(hash).is_some_and(|(scheme, encoded)| {
+        if is_argon2(&scheme) {
+            PasswordHash::new(encoded.trim()).is_ok()
+        } else {
+            digest_len(&scheme).is_some()
+        }
+    })
+}
+
+// Novo hash {ARGON2ID} com salt aleatório
+pub fn hash(password: &str) -> String {
+    let mut salt = [0u8; SALT_LEN];
+    rand::thread_rng().fill_bytes(&mut salt);
+    let salt = SaltString::encode_b64(&salt).expect("salt de 16 bytes");
+
+    let params = Params::new(
+        ARGON2_MEMORY_KIB,
+        ARGON2_ITERATIONS,
+        ARGON2_PARALLELISM,
+        None,
+    )
+    .expect("parâmetros do Argon2");
+    let hash = Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password(password.as_bytes(), &salt)
+        .expect("hash Argon2id");
+    format!("{{ARGON2ID}}{}", hash)
+}
+
+fn split(hash: &str) -> Option<(String, &str)> {
+    let (scheme, encoded) = hash.strip_prefix('{')?.split_once('}')?;
+    Some((scheme.to_ascii_uppercase(), encoded))
+}
+
+fn is_argon2(scheme: &str) -> bool {
+    matches!(scheme, "ARGON2ID" | "ARGON2I")
+}
+
+fn digest_len(scheme: &str) -> Option<usize> {
+    match scheme {
+        "SSHA256" => Some(32),
+        "SSHA512" => Some(64),
+        _ => None,
+    }
+}
+
+fn digest(scheme: &str, password: &[u8], salt: &[u8]) -> Vec<u8> {
+    match scheme {
+        "SSHA512" => Sha512::new()
+            .chain_update(password)
+            .chain_update(salt)
+            .finalize()
+            .to_vec(),
+        _ => Sha256::new()
+            .chain_update(password)
+            .chain_update(salt)
+            .finalize()
+            .to_vec(),
+    }
+}
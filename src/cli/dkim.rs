@@ -0,0 +1,118 @@
+use std::{fs::OpenOptions, io::Write, os::unix::fs::OpenOptionsExt};
+
+use chrono::Utc;
+
+use crate::{
+    cli::{DkimCheckArgs, DkimCommand, KeygenArgs},
+    config::Config,
+    dkim::{
+        key::PrivateKey,
+        keygen,
+        rotation::{self, KeyState},
+        verifier::DkimVerifier,
+    },
+    dns,
+};
+
+pub async fn run(config_path: &str, command: DkimCommand) -> i32 {
+    let result = match command {
+        DkimCommand::Keygen(args) => generate(&args),
+        DkimCommand::Check(args) => check(config_path, &args).await,
+    };
+
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("smtp: {}", e);
+            1
+        }
+    }
+}
+
+fn generate(args: &KeygenArgs) -> anyhow::Result<()> {
+    let key = keygen::generate(&args.algorithm, args.bits)?;
+
+    // A chave privada só é legível pelo dono
+    let mut options = OpenOptions::new();
+    options.write(true).mode(0o600);
+    if args.force {
+        options.create(true).truncate(true);
+    } else {
+        options.create_new(true);
+    }
+    let mut file = options
+        .open(&args.out)
+        .map_err(|e| anyhow::anyhow!("{}: {}", args.out.display(), e))?;
+    file.write_all(key.private_key_pem.as_bytes())?;
+
+    println!("Chave privada gravada em {}", args.out.display());
+    println!();
+    println!("Publique o registro TXT:");
+    println!(
+        "{}",
+        keygen::zone_entry(&args.selector, &args.domain, &key.dns_record)
+    );
+    Ok(())
+}
+
+// Para cada chave de [dkim], busca o registro com o resolvedor da
+// configuração (o DNS, com os registros de [dns.txt_records]) e confere se ele verifica
+// uma assinatura feita com a chave privada
+async fn check(config_path: &str, args: &DkimCheckArgs) -> anyhow::Result<()> {
+    let config =
+        Config::load(config_path).map_err(|e| anyhow::anyhow!("{}: {}", config_path, e))?;
+    let verifier = DkimVerifier::new(dns::resolver_with_overrides(&config.dns)?);
+
+    let entries: Vec<_> = config
+        .dkim
+        .key_entries()
+        .into_iter()
+        .filter(|(_, key)| !key.domain.is_empty())
+        .filter(|(_, key)| args.selector.as_ref().is_none_or(|s| *s == key.selector))
+        .filter(|(_, key)| {
+            args.domain
+                .as_ref()
+                .is_none_or(|d| d.eq_ignore_ascii_case(&key.domain))
+        })
+        .collect();
+    if entries.is_empty() {
+        anyhow::bail!("Nenhuma chave DKIM configurada");
+    }
+
+    let now = Utc::now();
+    let mut failures = 0;
+    for (prefix, entry) in entries {
+        let name = format!("{}._domainkey.{}", entry.selector, entry.domain);
+        let state = rotation::state(&entry, config.dkim.retire_grace_secs, now);
+        if state == KeyState::Expired {
+            println!("{}: {}, o registro pode ser removido", name, state);
+            continue;
+        }
+        let key = match PrivateKey::load(&entry) {
+            Ok(key) => key,
+            Err(e) => {
+                println!("{}: {}: {}", name, prefix, e);
+                failures += 1;
+                continue;
+            }
+        };
+
+        match keygen::check_record(&verifier, &entry.selector, &entry.domain, &key).await {
+            Ok(()) => println!("{}: ok ({})", name, state),
+            Err(reason) => {
+                println!("{}: {}", name, reason);
+                let record = keygen::dns_record(&key)?;
+                println!(
+                    "  esperado: {}",
+                    keygen::zone_entry(&entry.selector, &entry.domain, &record)
+                );
+                failures += 1;
+            }
+        }
+    }
+
+    if failures > 0 {
+        anyhow::bail!("{} chave(s) sem registro correspondente", failures);
+    }
+    Ok(())
+}
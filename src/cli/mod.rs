@@ -0,0 +1,205 @@
+pub mod dkim;
+pub mod queue;
+pub mod send;
+pub mod sendmail;
+
+use std::{path::PathBuf, sync::Arc};
+
+use clap::{Args, Parser, Subcommand};
+
+use crate::{
+    config::Config,
+    control::{self, ControlRequest, ControlResponse, client},
+    logging,
+    queue::{models::JobPriority, spool::Spool},
+    recipients::Recipients,
+};
+
+#[derive(Parser)]
+#[command(name = "smtp", version, about = "Servidor SMTP")]
+pub struct Cli {
+    #[arg(
+        short,
+        long,
+        global = true,
+        default_value = "config.toml",
+        help = "Arquivo de configuração"
+    )]
+    pub config: String,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    // Formas anteriores aos subcomandos (smtp --check-config config.toml),
+    // mantidas para as units e scripts existentes
+    #[arg(long, hide = true)]
+    pub check_config: bool,
+    #[arg(long, hide = true)]
+    pub dump_config: bool,
+    #[arg(hide = true, value_name = "CONFIG")]
+    pub legacy_config: Option<String>,
+}
+
+impl Cli {
+    // Arquivo de configuração e comando, com um aviso para as formas antigas
+    pub fn resolve(self) -> (String, Command) {
+        let config = match self.legacy_config {
+            Some(path) => {
+                eprintln!(
+                    "smtp: aviso: o caminho da configuração como argumento está obsoleto; use -c {}",
+                    path
+                );
+                path
+            }
+            None => self.config,
+        };
+
+        let command = if self.check_config {
+            eprintln!("smtp: aviso: --check-config está obsoleto; use check-config");
+            Command::CheckConfig
+        } else if self.dump_config {
+            eprintln!("smtp: aviso: --dump-config está obsoleto; use dump-config");
+            Command::DumpConfig
+        } else {
+            self.command.unwrap_or(Command::Serve)
+        };
+        (config, command)
+    }
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    #[command(about = "Inicia o servidor (padrão)")]
+    Serve,
+    #[command(subcommand, about = "Consulta e gerencia a fila de entrega")]
+    Queue(QueueCommand),
+    #[command(about = "Valida a configuração sem iniciar o servidor")]
+    CheckConfig,
+    #[command(about = "Mostra a configuração efetiva e a origem de cada valor")]
+    DumpConfig,
+    #[command(subcommand, about = "Chaves DKIM")]
+    Dkim(DkimCommand),
+    #[command(about = "Envia uma mensagem lida da entrada padrão ou de um arquivo")]
+    Send(SendArgs),
+    #[command(about = "Gera o hash {ARGON2ID} de uma senha lida da entrada padrão")]
+    HashPassword,
+}
+
+#[derive(Subcommand)]
+pub enum QueueCommand {
+    #[command(about = "Lista os jobs da fila")]
+    List(ListArgs),
+    #[command(about = "Mostra um job, com o histórico de tentativas")]
+    Show {
+        id: String,
+        #[arg(long, help = "Saída em JSON")]
+        json: bool,
+    },
+    #[command(about = "Agenda para agora todos os jobs, ou os de um domínio")]
+    Flush { domain: Option<String> },
+    #[command(about = "Remove um job sem avisar o remetente")]
+    Delete { id: String },
+}
+
+#[derive(Args)]
+pub struct ListArgs {
+    #[arg(long)]
+    pub domain: Option<String>,
+    #[arg(long)]
+    pub sender: Option<String>,
+    #[arg(long, value_name = "SEGUNDOS", help = "Só jobs criados há mais tempo")]
+    pub older_than: Option<u64>,
+    #[arg(long, help = "low, normal ou high")]
+    pub priority: Option<JobPriority>,
+    #[arg(long, help = "Só jobs retidos")]
+    pub held: bool,
+    #[arg(long, help = "Saída em JSON")]
+    pub json: bool,
+}
+
+#[derive(Subcommand)]
+pub enum DkimCommand {
+    #[command(about = "Gera um par de chaves e mostra o registro DNS")]
+    Keygen(KeygenArgs),
+    #[command(about = "Confere os registros publicados para as chaves configuradas")]
+    Check(DkimCheckArgs),
+}
+
+#[derive(Args)]
+pub struct KeygenArgs {
+    #[arg(
+        long,
+        default_value = "rsa-sha256",
+        help = "rsa-sha256 ou ed25519-sha256"
+    )]
+    pub algorithm: String,
+    #[arg(long, default_value_t = 2048, help = "Tamanho da chave RSA")]
+    pub bits: usize,
+    #[arg(long)]
+    pub selector: String,
+    #[arg(long)]
+    pub domain: String,
+    #[arg(long, help = "Arquivo da chave privada (PKCS#8 PEM)")]
+    pub out: PathBuf,
+    #[arg(long, help = "Sobrescreve o arquivo se já existir")]
+    pub force: bool,
+}
+
+#[derive(Args)]
+pub struct DkimCheckArgs {
+    #[arg(long, help = "Só as chaves deste seletor")]
+    pub selector: Option<String>,
+    #[arg(long, help = "Só as chaves deste domínio")]
+    pub domain: Option<String>,
+}
+
+#[derive(Args)]
+pub struct SendArgs {
+    #[arg(short, long, help = "Remetente do envelope")]
+    pub from: String,
+    #[arg(required = true, help = "Destinatários")]
+    pub recipients: Vec<String>,
+    #[arg(long, help = "Lê a mensagem do arquivo em vez da entrada padrão")]
+    pub file: Option<PathBuf>,
+}
+
+// Envia o pedido ao servidor pelo socket de controle ou, se ele não estiver
+// rodando, executa direto no spool
+pub async fn execute(
+    config_path: &str,
+    request: ControlRequest,
+) -> Result<ControlResponse, String> {
+    let config = Config::load(config_path).map_err(|e| format!("{}: {}", config_path, e))?;
+
+    match client::request(&config.server.control_socket, &request).await {
+        Ok(response) => Ok(response),
+        Err(e) if client::is_unavailable(&e) => {
+            logging::init_cli(&config.logging).map_err(|e| e.to_string())?;
+            let spool = Spool::open(&config.queue.spool_dir)
+                .await
+                .map_err(|e| format!("{}: {}", config.queue.spool_dir.display(), e))?;
+            let config = Arc::new(config);
+            let recipients = Arc::new(Recipients::load(config.clone()).map_err(|e| e.to_string())?);
+            Ok(control::handle(&config, &recipients, Arc::new(spool), request).await)
+        }
+        Err(e) => Err(format!("{}: {}", config.server.control_socket.display(), e)),
+    }
+}
+
+// Converte a resposta no código de saída, mostrando o erro se houver
+pub fn finish(
+    result: Result<ControlResponse, String>,
+    on_ok: impl FnOnce(serde_json::Value) -> Result<(), String>,
+) -> i32 {
+    let outcome = match result {
+        Ok(ControlResponse::Ok { data }) => on_ok(data),
+        Ok(ControlResponse::Error { message }) => Err(message),
+        Err(e) => Err(e),
+    };
+
+    match outcome {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("smtp: {}", e);
+            1
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use crate::{
+    cli::{self, ListArgs, QueueCommand},
+    control::ControlRequest,
+    queue::manager::{JobDetail, JobFilter, JobSummary},
+};
+
+pub async fn run(config_path: &str, command: QueueCommand) -> i32 {
+    match command {
+        QueueCommand::List(args) => list(config_path, args).await,
+        QueueCommand::Show { id, json } => {
+            let result = cli::execute(config_path, ControlRequest::Show { id }).await;
+            cli::finish(result, |data| {
+                if json {
+                    return print_json(&data);
+                }
+                let job: JobDetail = serde_json::from_value(data).map_err(|e| e.to_string())?;
+                print_detail(&job);
+                Ok(())
+            })
+        }
+        QueueCommand::Flush { domain } => {
+            let result = cli::execute(config_path, ControlRequest::Flush { domain }).await;
+            cli::finish(result, |data| {
+                println!("{} jobs agendados para agora", data["flushed"]);
+                Ok(())
+            })
+        }
+        QueueCommand::Delete { id } => {
+            let result = cli::execute(config_path, ControlRequest::Delete { id }).await;
+            cli::finish(result, |data| {
+                println!("Job {} removido", data["id"].as_str().unwrap_or_default());
+                Ok(())
+            })
+        }
+    }
+}
+
+async fn list(config_path: &str, args: ListArgs) -> i32 {
+    let filter = JobFilter {
+        domain: args.domain,
+        sender: args.sender,
+        older_than_secs: args.older_than,
+        priority: args.priority,
+        held: args.held.then_some(true),
+    };
+
+    let result = cli::execute(config_path, ControlRequest::List { filter }).await;
+    cli::finish(result, |data| {
+        if args.json {
+            return print_json(&data);
+        }
+
+        let jobs: Vec<JobSummary> = serde_json::from_value(data).map_err(|e| e.to_string())?;
+        if jobs.is_empty() {
+            println!("Fila vazia");
+            return Ok(());
+        }
+        for job in &jobs {
+            print_summary(job);
+        }
+        println!("-- {} jobs", jobs.len());
+        Ok(())
+    })
+}
+
+fn print_json(data: &serde_json::Value) -> Result<(), String> {
+    let out = serde_json::to_string_pretty(data).map_err(|e| e.to_string())?;
+    println!("{}", out);
+    Ok(())
+}
+
+// Formato parecido com o do mailq: uma linha de cabeçalho por job, o envelope
+// e o último erro
+fn print_summary(job: &JobSummary) {
+    let held = if job.held { " retido" } else { "" };
+    println!(
+        "{}  {}  {}{}  tentativa {}/{}",
+        job.id,
+        job.created_at.format("%Y-%m-%d %H:%M:%S"),
+        job.priority,
+        held,
+        job.attempt,
+        job.max_attempts
+    );
+    println!("    <{}> -> <{}>", job.from, job.to);
+    if let Some(error) = &job.last_error {
+        println!("    ({})", error);
+    }
+}
+
+fn print_detail(job: &JobDetail) {
+    let summary = &job.summary;
+    println!("Job:               {}", summary.id);
+    println!("Mensagem:          {}", summary.email_id);
+    println!("Remetente:         <{}>", summary.from);
+    println!("Destinatário:      <{}>", summary.to);
+    println!("Prioridade:        {}", summary.priority);
+    println!(
+        "Retido:            {}",
+        if summary.held { "sim" } else { "não" }
+    );
+    println!("Tamanho:           {} bytes", job.size);
+    println!("Criado em:         {}", summary.created_at);
+    println!("Próxima tentativa: {}", summary.next_attempt_at);
+    println!(
+        "Tentativas:        {}/{}",
+        summary.attempt, summary.max_attempts
+    );
+    if let Some(error) = &summary.last_error {
+        println!("Último erro:       {}", error);
+    }
+
+    if !job.history.is_empty() {
+        println!("Histórico:");
+        for attempt in &job.history {
+            let code = attempt.smtp_code.map(|c| c.to_string()).unwrap_or_default();
+            println!(
+                "  {}  {:<9} {:>3}  {}",
+                attempt.at.format("%Y-%m-%d %H:%M:%S"),
+                attempt.result,
+                code,
+                attempt.detail
+            );
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use std::io::Read;
+
+use crate::{
+    cli::{self, SendArgs},
+    control::ControlRequest,
+    helpers::email_helper,
+};
+
+pub async fn run(config_path: &str, args: SendArgs) -> i32 {
+    let message = match read_message(&args) {
+        Ok(message) => message,
+        Err(e) => {
+            eprintln!("smtp: {}", e);
+            return 1;
+        }
+    };
+
+    let request = ControlRequest::Submit {
+        from: args.from,
+        recipients: args.recipients,
+        message: email_helper::to_crlf(&message),
+    };
+    let result = cli::execute(config_path, request).await;
+    cli::finish(result, |data| {
+        println!(
+            "Mensagem {} na fila",
+            data["id"].as_str().unwrap_or_default()
+        );
+        Ok(())
+    })
+}
+
+fn read_message(args: &SendArgs) -> std::io::Result<String> {
+    match &args.file {
+        Some(path) => std::fs::read_to_string(path),
+        None => {
+            let mut message = String::new();
+            std::io::stdin().read_to_string(&mut message)?;
+            Ok(message)
+        }
+    }
+}
@@ -0,0 +1,325 @@
+use std::{collections::HashSet, ffi::CStr, io::Read, sync::Arc};
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::{
+    cli::{self, ListArgs},
+    config::Config,
+    control::ControlRequest,
+    helpers::email_helper,
+    logging,
+    queue::spool::Spool,
+    recipients::Recipients,
+    shutdown,
+    smtp_server::{SmtpSession, listener::ListenerRole},
+};
+
+// Códigos de saída do sendmail (sysexits.h)
+const EX_USAGE: i32 = 64;
+const EX_DATAERR: i32 = 65;
+const EX_NOINPUT: i32 = 66;
+const EX_TEMPFAIL: i32 = 75;
+const EX_CONFIG: i32 = 78;
+
+#[derive(Default, PartialEq)]
+enum Mode {
+    // -bm (padrão): lê a mensagem da entrada padrão
+    #[default]
+    Deliver,
+    // -bs: sessão SMTP na entrada e na saída padrão
+    Smtp,
+    // -bp: lista a fila, como o mailq
+    Queue,
+}
+
+#[derive(Default)]
+struct Options {
+    mode: Mode,
+    config: Option<String>,
+    from: Option<String>,
+    full_name: Option<String>,
+    recipients_from_headers: bool,
+    ignore_dots: bool,
+    recipients: Vec<String>,
+}
+
+// Ponto de entrada quando o executável é chamado como `sendmail` (por exemplo,
+// por um link em /usr/sbin/sendmail). A configuração vem de -C, da variável
+// SMTP_CONFIG ou de config.toml.
+pub async fn run(args: Vec<String>) -> i32 {
+    let options = match parse_args(args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("sendmail: {}", e);
+            return EX_USAGE;
+        }
+    };
+    let config_path = options
+        .config
+        .clone()
+        .or_else(|| std::env::var("SMTP_CONFIG").ok())
+        .unwrap_or_else(|| "config.toml".to_string());
+
+    match options.mode {
+        Mode::Queue => {
+            let args = ListArgs {
+                domain: None,
+                sender: None,
+                older_than: None,
+                priority: None,
+                held: false,
+                json: false,
+            };
+            cli::queue::run(&config_path, cli::QueueCommand::List(args)).await
+        }
+        Mode::Smtp => smtp_session(&config_path).await,
+        Mode::Deliver => deliver(&config_path, options).await,
+    }
+}
+
+fn parse_args(args: Vec<String>) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            options.recipients.extend(args.by_ref());
+            break;
+        }
+        let Some(flag) = arg.strip_prefix('-').filter(|f| !f.is_empty()) else {
+            options.recipients.push(arg);
+            continue;
+        };
+
+        // Opções com valor aceitam "-fvalor" e "-f valor"
+        let mut value = |name: &str| -> Result<String, String> {
+            let attached = &flag[name.len()..];
+            if !attached.is_empty() {
+                return Ok(attached.to_string());
+            }
+            args.next()
+                .ok_or_else(|| format!("a opção -{} precisa de um valor", name))
+        };
+
+        match flag {
+            "t" => options.recipients_from_headers = true,
+            "i" | "oi" => options.ignore_dots = true,
+            "bm" => options.mode = Mode::Deliver,
+            "bs" => options.mode = Mode::Smtp,
+            "bp" => options.mode = Mode::Queue,
+            f if f.starts_with('f') || f.starts_with('r') => options.from = Some(value(&f[..1])?),
+            f if f.starts_with('F') => options.full_name = Some(value("F")?),
+            f if f.starts_with('C') => options.config = Some(value("C")?),
+            // Opções do sendmail sem efeito aqui (entrega, DSN, fila, etc.)
+            f if f.starts_with('o') || f.starts_with('B') => {}
+            f if f.starts_with('N') || f.starts_with('R') || f.starts_with('V') => {
+                value(&f[..1])?;
+            }
+            "v" | "m" | "U" => {}
+            other => return Err(format!("opção não suportada: -{}", other)),
+        }
+    }
+
+    Ok(options)
+}
+
+async fn deliver(config_path: &str, options: Options) -> i32 {
+    let config = match Config::load(config_path) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("sendmail: {}: {}", config_path, e);
+            return EX_CONFIG;
+        }
+    };
+
+    let mut input = Vec::new();
+    if let Err(e) = std::io::stdin().read_to_end(&mut input) {
+        eprintln!("sendmail: erro ao ler a mensagem: {}", e);
+        return EX_NOINPUT;
+    }
+    let mut message = read_message(&String::from_utf8_lossy(&input), options.ignore_dots);
+
+    let from = options
+        .from
+        .clone()
+        .unwrap_or_else(|| format!("{}@{}", login_name(), config.server.hostname));
+
+    let mut recipients = options.recipients;
+    if options.recipients_from_headers {
+        let (headers, body) = split_message(&message);
+        recipients.extend(header_recipients(headers));
+        message = format!("{}\r\n{}", email_helper::strip_header(headers, "bcc"), body);
+    }
+    let mut seen = HashSet::new();
+    recipients.retain(|r| seen.insert(r.to_ascii_lowercase()));
+    if recipients.is_empty() {
+        eprintln!("sendmail: nenhum destinatário");
+        return EX_DATAERR;
+    }
+
+    let message = add_missing_headers(&message, &from, options.full_name.as_deref(), &config);
+
+    let request = ControlRequest::Submit {
+        from,
+        recipients,
+        message,
+    };
+    let code = cli::finish(cli::execute(config_path, request).await, |_| Ok(()));
+    if code == 0 { 0 } else { EX_TEMPFAIL }
+}
+
+// Sem -i, uma linha com apenas "." encerra a mensagem, como no sendmail
+fn read_message(input: &str, ignore_dots: bool) -> String {
+    let mut message = String::with_capacity(input.len());
+    for line in input.split_inclusive('\n') {
+        if !ignore_dots && line.trim_end_matches(['\r', '\n']) == "." {
+            break;
+        }
+        message.push_str(line);
+    }
+    email_helper::to_crlf(&message)
+}
+
+// Headers (com o CRLF final) e body, sem a linha em branco entre eles
+fn split_message(message: &str) -> (&str, &str) {
+    match message.find("\r\n\r\n") {
+        Some(pos) => (&message[..pos + 2], &message[pos + 4..]),
+        None if looks_like_headers(message) => (message, ""),
+        None => ("", message),
+    }
+}
+
+fn looks_like_headers(message: &str) -> bool {
+    message
+        .lines()
+        .next()
+        .and_then(|line| line.split_once(':'))
+        .is_some_and(|(name, _)| !name.is_empty() && !name.contains(char::is_whitespace))
+}
+
+// Cada header já desdobrado: (nome, valor)
+fn unfolded_headers(headers: &str) -> Vec<(String, String)> {
+    let mut fields: Vec<(String, String)> = Vec::new();
+    for line in headers.split("\r\n") {
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = fields.last_mut() {
+                value.push_str(line);
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            fields.push((name.trim().to_string(), value.to_string()));
+        }
+    }
+    fields
+}
+
+fn header_recipients(headers: &str) -> Vec<String> {
+    unfolded_headers(headers)
+        .iter()
+        .filter(|(name, _)| ["to", "cc", "bcc"].contains(&name.to_ascii_lowercase().as_str()))
+        .flat_map(|(_, value)| email_helper::parse_address_list(value))
+        .collect()
+}
+
+// Mensagens de cron e scripts costumam vir sem From, Date ou Message-ID
+fn add_missing_headers(
+    message: &str,
+    from: &str,
+    full_name: Option<&str>,
+    config: &Config,
+) -> String {
+    let (headers, body) = split_message(message);
+    let present: Vec<String> = unfolded_headers(headers)
+        .into_iter()
+        .map(|(name, _)| name.to_ascii_lowercase())
+        .collect();
+
+    let mut added = String::new();
+    if !present.iter().any(|n| n == "from") {
+        match full_name {
+            Some(name) => added.push_str(&format!(
+                "From: \"{}\" <{}>\r\n",
+                name.replace(['"', '\\'], ""),
+                from
+            )),
+            None => added.push_str(&format!("From: <{}>\r\n", from)),
+        }
+    }
+    if !present.iter().any(|n| n == "date") {
+        added.push_str(&format!("Date: {}\r\n", Utc::now().to_rfc2822()));
+    }
+    if !present.iter().any(|n| n == "message-id") {
+        added.push_str(&format!(
+            "Message-ID: <{}@{}>\r\n",
+            Uuid::new_v4(),
+            config.server.hostname
+        ));
+    }
+
+    format!("{}{}\r\n{}", added, headers, body)
+}
+
+// Sessão SMTP local na entrada e saída padrão, gravando direto no spool
+async fn smtp_session(config_path: &str) -> i32 {
+    let config = match Config::load(config_path) {
+        Ok(config) => Arc::new(config),
+        Err(e) => {
+            eprintln!("sendmail: {}: {}", config_path, e);
+            return EX_CONFIG;
+        }
+    };
+    if let Err(e) = logging::init_cli(&config.logging) {
+        eprintln!("sendmail: {}", e);
+        return EX_CONFIG;
+    }
+    let spool = match Spool::open(&config.queue.spool_dir).await {
+        Ok(spool) => Arc::new(spool),
+        Err(e) => {
+            eprintln!("sendmail: {}: {}", config.queue.spool_dir.display(), e);
+            return EX_TEMPFAIL;
+        }
+    };
+    let recipients = match Recipients::load(config.clone()) {
+        Ok(recipients) => Arc::new(recipients),
+        Err(e) => {
+            eprintln!("sendmail: {}", e);
+            return EX_TEMPFAIL;
+        }
+    };
+
+    // Nunca acionado: a sessão termina com QUIT ou com o fim da entrada
+    let (_trigger, shutdown) = shutdown::channel();
+    let stdio = tokio::io::join(tokio::io::stdin(), tokio::io::stdout());
+    let mut session = SmtpSession::new(
+        config,
+        recipients,
+        "local".to_string(),
+        ListenerRole::Mta,
+        spool,
+        shutdown,
+    );
+
+    match session.run(stdio).await {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("sendmail: {}", e);
+            EX_TEMPFAIL
+        }
+    }
+}
+
+fn login_name() -> String {
+    if let Ok(name) = std::env::var("LOGNAME").or_else(|_| std::env::var("USER")) {
+        return name;
+    }
+
+    // SAFETY: getpwuid devolve um ponteiro para dados estáticos da libc, lidos
+    // imediatamente e sem outras chamadas concorrentes nesse ponto
+    unsafe {
+        let pw = libc::getpwuid(libc::getuid());
+        if pw.is_null() {
+            return "root".to_string();
+        }
+        CStr::from_ptr((*pw).pw_name).to_string_lossy().into_owned()
+    }
+}
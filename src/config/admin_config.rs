@@ -0,0 +1,21 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct AdminConfig {
+    pub enabled: bool,
+    // Endereço da API HTTP de administração da fila
+    pub listen: String,
+    // Enviado pelos clientes no header Authorization: Bearer <token>
+    pub token: String,
+}
+
+impl Default for AdminConfig {
+    fn default() -> Self {
+        AdminConfig {
+            enabled: false,
+            listen: "127.0.0.1:9326".to_string(),
+            token: String::new(),
+        }
+    }
+}
@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(default)]
+pub struct ArcConfig {
+    // Valida as cadeias ARC das mensagens recebidas
+    pub verify: bool,
+    // Sela as mensagens recebidas que retransmitimos
+    pub seal: bool,
+    // d= dos selos; a chave é a primeira de [dkim] para esse domínio
+    pub domain: String,
+    // Domínios cujos selos, em uma cadeia válida, fazem o DMARC aceitar uma
+    // mensagem que falhou (por exemplo, listas que alteram o assunto)
+    pub trusted_sealers: Vec<String>,
+}
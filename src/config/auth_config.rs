@@ -0,0 +1,45 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+pub const AUTH_MECHANISMS: [&str; 2] = ["PLAIN", "LOGIN"];
+pub const AUTH_BACKENDS: [&str; 2] = ["static", "file"];
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct AuthConfig {
+    // Anuncia e aceita AUTH no EHLO
+    pub enabled: bool,
+    // Aceita AUTH fora de TLS, com a senha em texto claro na rede
+    pub allow_insecure: bool,
+    pub mechanisms: Vec<String>,
+    // static usa [[auth.users]]; file lê users_path a cada consulta
+    pub backend: String,
+    pub users: Vec<StaticUser>,
+    // Uma linha por usuário: login:hash:identidade,identidade,...
+    pub users_path: PathBuf,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct StaticUser {
+    pub username: String,
+    // {ARGON2ID}, {SSHA256} ou {SSHA512}, no formato do Dovecot
+    pub password_hash: String,
+    // Remetentes que o usuário pode usar, além do próprio login quando ele é
+    // um endereço: user@domínio, @domínio ou padrões com *
+    #[serde(default)]
+    pub identities: Vec<String>,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        AuthConfig {
+            enabled: false,
+            allow_insecure: false,
+            mechanisms: AUTH_MECHANISMS.iter().map(|m| m.to_string()).collect(),
+            backend: "static".to_string(),
+            users: Vec::new(),
+            users_path: PathBuf::from("users"),
+        }
+    }
+}
@@ -1,16 +1,28 @@
 use std::{error::Error, fmt};
 
+use crate::config::validation::ConfigIssue;
+
 #[derive(Debug)]
 pub enum ConfigError {
     IoError(std::io::Error),
     TomlError(toml::de::Error),
+    ValidationError(Vec<ConfigIssue>),
+    LayerError(String),
 }
 
 impl fmt::Display for ConfigError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             ConfigError::IoError(e) => write!(f, "Erro de I/O: {}", e),
-            ConfigError::TomlError(e) => write!(f, "Erro ao processar TOML: {}", e)
+            ConfigError::TomlError(e) => write!(f, "Erro ao processar TOML: {}", e),
+            ConfigError::ValidationError(issues) => {
+                write!(f, "{} problema(s) encontrado(s)", issues.len())?;
+                for issue in issues {
+                    write!(f, "\n  - {}", issue)?;
+                }
+                Ok(())
+            }
+            ConfigError::LayerError(e) => write!(f, "Erro ao montar a configuração: {}", e),
         }
     }
 }
@@ -28,4 +40,4 @@ impl From<toml::de::Error> for ConfigError {
     fn from(err: toml::de::Error) -> Self {
         ConfigError::TomlError(err)
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+
+use crate::helpers::email_helper;
+
+// Transportes sempre disponíveis: relay pelos MX e Maildir dos local_domains
+pub const BUILTIN_TRANSPORTS: [&str; 2] = ["smtp", "maildir"];
+pub const TRANSPORT_KINDS: [&str; 2] = ["lmtp", "mbox"];
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(default)]
+pub struct DeliveryConfig {
+    pub transports: Vec<TransportConfig>,
+    // A primeira rota que casa com o destinatário vale; as por destinatário
+    // são consultadas antes das por domínio
+    pub routes: Vec<RouteConfig>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct TransportConfig {
+    pub name: String,
+    // lmtp ou mbox
+    pub kind: String,
+    // LMTP: "host:porta" ou o caminho do socket Unix
+    #[serde(default)]
+    pub address: String,
+    // mbox: arquivo de cada usuário; {domain} e {user} são substituídos
+    #[serde(default)]
+    pub path: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct RouteConfig {
+    #[serde(default)]
+    pub domain: Option<String>,
+    #[serde(default)]
+    pub recipient: Option<String>,
+    pub transport: String,
+}
+
+impl DeliveryConfig {
+    // Transporte do destinatário pelas rotas, se alguma casar. As rotas por
+    // destinatário também valem para os subendereços (user+pasta).
+    pub fn route(&self, recipient: &str, delimiter: &str) -> Option<&str> {
+        let (local_part, domain) = recipient.rsplit_once('@').unwrap_or((recipient, ""));
+        let (user, _) = email_helper::split_detail(local_part, delimiter);
+        let base = format!("{}@{}", user, domain);
+        self.routes
+            .iter()
+            .find(|r| {
+                r.recipient.as_deref().is_some_and(|r| {
+                    r.eq_ignore_ascii_case(recipient) || r.eq_ignore_ascii_case(&base)
+                })
+            })
+            .or_else(|| {
+                self.routes.iter().find(|r| {
+                    r.recipient.is_none()
+                        && r.domain
+                            .as_deref()
+                            .is_some_and(|d| d.eq_ignore_ascii_case(domain))
+                })
+            })
+            .map(|r| r.transport.as_str())
+    }
+}
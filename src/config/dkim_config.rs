@@ -1,26 +1,136 @@
 use std::path::PathBuf;
 
-use serde::Deserialize;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize, Clone)]
+// Campos ausentes usam o padrão, para que possam vir de arquivos diferentes
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
 pub struct DkimConfig {
+    // Assina as mensagens enviadas
     pub enabled: bool,
+    // Verifica as assinaturas das mensagens recebidas
+    pub verify: bool,
+    // Chave única (formato antigo); equivale a uma entrada de `keys`
     pub domain: String,
     pub selector: String,
     pub private_key_path: PathBuf,
-    pub alogrithm: String,
+    // PEM inline, normalmente vindo de private_key_file ou de uma variável de ambiente
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub private_key: Option<String>,
+    #[serde(alias = "alogrithm")]
+    pub algorithm: String,
+    // Chaves por domínio. Várias entradas para o mesmo domínio geram uma
+    // assinatura cada (por exemplo, RSA e Ed25519).
+    pub keys: Vec<DkimKeyConfig>,
+    // Vazio usa a lista padrão do DkimSigner
     pub headers: Vec<String>,
+    // c=: "cabeçalho/body", cada um simple ou relaxed
+    pub canonicalization: String,
+    // Lista cada header uma vez a mais do que aparece na mensagem, para que
+    // cópias extras não possam ser acrescentadas depois da assinatura
+    pub oversign: bool,
+    // t=: momento da assinatura
+    pub timestamp: bool,
+    // x=: validade da assinatura, em segundos a partir de t=
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expiration_secs: Option<u64>,
+    // i=: identidade do agente (o domínio precisa ser o de d= ou um subdomínio)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub identity: Option<String>,
+    // l=: assina só o tamanho atual do body
+    pub body_length: bool,
+    // Por quanto tempo o registro DNS de uma chave aposentada (retire_at)
+    // ainda precisa ficar publicado, para as mensagens já enviadas
+    pub retire_grace_secs: u64,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct DkimKeyConfig {
+    pub domain: String,
+    pub selector: String,
+    #[serde(default)]
+    pub private_key_path: PathBuf,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub private_key: Option<String>,
+    #[serde(default = "default_algorithm")]
+    pub algorithm: String,
+    // Rotação: a chave só assina a partir de activate_at e até retire_at
+    // ("2026-11-01", meia-noite UTC, ou RFC 3339)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub activate_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retire_at: Option<String>,
+}
+
+impl DkimKeyConfig {
+    pub fn activation(&self) -> Option<DateTime<Utc>> {
+        self.activate_at.as_deref().and_then(parse_time)
+    }
+
+    pub fn retirement(&self) -> Option<DateTime<Utc>> {
+        self.retire_at.as_deref().and_then(parse_time)
+    }
+}
+
+pub fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Some(date.and_time(Default::default()).and_utc());
+    }
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|time| time.to_utc())
+}
+
+impl DkimConfig {
+    // Todas as chaves configuradas, com o prefixo de cada uma na configuração
+    // (usado nas mensagens de erro)
+    pub fn key_entries(&self) -> Vec<(String, DkimKeyConfig)> {
+        let mut entries = Vec::new();
+        if !self.domain.is_empty() || self.keys.is_empty() {
+            entries.push((
+                "dkim".to_string(),
+                DkimKeyConfig {
+                    domain: self.domain.clone(),
+                    selector: self.selector.clone(),
+                    private_key_path: self.private_key_path.clone(),
+                    private_key: self.private_key.clone(),
+                    algorithm: self.algorithm.clone(),
+                    activate_at: None,
+                    retire_at: None,
+                },
+            ));
+        }
+        for (i, key) in self.keys.iter().enumerate() {
+            entries.push((format!("dkim.keys[{}]", i), key.clone()));
+        }
+        entries
+    }
+}
+
+fn default_algorithm() -> String {
+    "rsa-sha256".to_string()
 }
 
 impl Default for DkimConfig {
     fn default() -> Self {
         DkimConfig {
             enabled: false,
+            verify: false,
             domain: "".to_string(),
             selector: "".to_string(),
             private_key_path: PathBuf::new(),
-            alogrithm: "sha256".to_string(),
+            private_key: None,
+            algorithm: default_algorithm(),
+            keys: Vec::new(),
             headers: Vec::new(),
+            canonicalization: "relaxed/relaxed".to_string(),
+            oversign: false,
+            timestamp: false,
+            expiration_secs: None,
+            identity: None,
+            body_length: false,
+            retire_grace_secs: 7 * 24 * 3600,
         }
     }
 }
@@ -0,0 +1,58 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::dmarc::record::Policy;
+
+// Ações possíveis para as políticas quarantine e reject
+pub const DMARC_ACTIONS: [&str; 3] = ["accept", "hold", "reject"];
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct DmarcConfig {
+    // Avalia o DMARC das mensagens recebidas (exige os resultados de SPF e
+    // DKIM, que são calculados mesmo com [spf] e dkim.verify desligados)
+    pub enabled: bool,
+    // O que fazer quando o domínio pede reject ou quarantine: accept entrega
+    // normalmente, hold retém os jobs na fila e reject recusa o DATA
+    pub on_reject: String,
+    pub on_quarantine: String,
+    // Public Suffix List usada para achar o domínio organizacional
+    pub public_suffix_list: PathBuf,
+    // Sufixos extras, somados aos da lista
+    pub public_suffixes: Vec<String>,
+    // Relatórios agregados (rua=), enviados uma vez por dia pela fila
+    pub reports: bool,
+    // Onde os resultados do dia ficam até o envio
+    pub report_dir: PathBuf,
+    // Remetente dos relatórios; vazio usa postmaster@<hostname>
+    pub report_from: String,
+    // Nome da organização nos relatórios; vazio usa o hostname
+    pub org_name: String,
+}
+
+impl DmarcConfig {
+    pub fn action(&self, disposition: Policy) -> &str {
+        match disposition {
+            Policy::None => "accept",
+            Policy::Quarantine => &self.on_quarantine,
+            Policy::Reject => &self.on_reject,
+        }
+    }
+}
+
+impl Default for DmarcConfig {
+    fn default() -> Self {
+        DmarcConfig {
+            enabled: false,
+            on_reject: "reject".to_string(),
+            on_quarantine: "hold".to_string(),
+            public_suffix_list: PathBuf::from("/usr/share/publicsuffix/public_suffix_list.dat"),
+            public_suffixes: Vec::new(),
+            reports: false,
+            report_dir: PathBuf::from("dmarc"),
+            report_from: String::new(),
+            org_name: String::new(),
+        }
+    }
+}
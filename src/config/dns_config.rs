@@ -0,0 +1,11 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(default)]
+pub struct DnsConfig {
+    // Registros TXT fixos, consultados antes do DNS pelo `dkim check`. Permite
+    // testar chaves antes de publicá-las; o servidor não os usa.
+    pub txt_records: BTreeMap<String, Vec<String>>,
+}
@@ -0,0 +1,346 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use toml::{
+    Table, Value,
+    de::{DeTable, DeValue},
+};
+
+use crate::config::config_error::ConfigError;
+
+// Precedência, da menor para a maior:
+//   1. valores padrão
+//   2. arquivo principal
+//   3. arquivos de `include`, na ordem listada (cada glob em ordem alfabética)
+//   4. variáveis de ambiente SMTP__SECAO__CHAVE
+// Por fim, toda chave `x_file` é substituída por `x` com o conteúdo do arquivo.
+const ENV_PREFIX: &str = "SMTP__";
+const ENV_SEPARATOR: &str = "__";
+const INCLUDE_KEY: &str = "include";
+const SECRET_FILE_SUFFIX: &str = "_file";
+
+#[derive(Debug, Clone)]
+pub enum Origin {
+    File { path: PathBuf, line: usize },
+    Env(String),
+    SecretFile(PathBuf),
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Origin::File { path, line } => write!(f, "{}, linha {}", path.display(), line),
+            Origin::Env(name) => write!(f, "variável {}", name),
+            Origin::SecretFile(path) => write!(f, "arquivo {}", path.display()),
+        }
+    }
+}
+
+pub type Origins = HashMap<String, Origin>;
+
+#[derive(Default)]
+pub struct LayeredConfig {
+    pub table: Table,
+    pub origins: Origins,
+}
+
+pub fn load(path: &str) -> Result<LayeredConfig, ConfigError> {
+    let main_path = Path::new(path);
+    let mut layered = LayeredConfig::default();
+
+    let source = std::fs::read_to_string(main_path)?;
+    let mut table: Table = toml::from_str(&source)?;
+    let includes = take_includes(&mut table)?;
+    layered.merge_file(main_path, &source, table);
+
+    let base_dir = main_path.parent().unwrap_or(Path::new(""));
+    for pattern in includes {
+        for include_path in expand_include(base_dir, &pattern)? {
+            let source = std::fs::read_to_string(&include_path).map_err(|e| {
+                ConfigError::LayerError(format!("{}: {}", include_path.display(), e))
+            })?;
+            let table: Table = toml::from_str(&source).map_err(|e| {
+                ConfigError::LayerError(format!("{}: {}", include_path.display(), e))
+            })?;
+            if table.contains_key(INCLUDE_KEY) {
+                return Err(ConfigError::LayerError(format!(
+                    "{}: include só é permitido no arquivo principal",
+                    include_path.display()
+                )));
+            }
+            layered.merge_file(&include_path, &source, table);
+        }
+    }
+
+    let mut vars: Vec<(String, String)> = std::env::vars()
+        .filter(|(name, _)| name.starts_with(ENV_PREFIX))
+        .collect();
+    vars.sort();
+    for (name, value) in vars {
+        layered.apply_env(&name, &value)?;
+    }
+
+    let mut secret_files = Vec::new();
+    collect_secret_files(&layered.table, "", &mut secret_files);
+    for (key, path) in secret_files {
+        layered.load_secret_file(&key, &path)?;
+    }
+
+    Ok(layered)
+}
+
+// Configuração efetiva em TOML, com segredos ocultos e a origem de cada valor
+pub fn dump(config: &impl serde::Serialize, origins: &Origins) -> Result<String, ConfigError> {
+    let mut value = Value::try_from(config)
+        .map_err(|e| ConfigError::LayerError(format!("Erro ao serializar: {}", e)))?;
+    redact(&mut value);
+
+    let mut out = toml::to_string_pretty(&value)
+        .map_err(|e| ConfigError::LayerError(format!("Erro ao serializar: {}", e)))?;
+
+    out.push_str("\n# Origem dos valores (os demais usam o padrão)\n");
+    let sorted: BTreeMap<_, _> = origins.iter().collect();
+    for (key, origin) in sorted {
+        out.push_str(&format!("# {} = {}\n", key, origin));
+    }
+
+    Ok(out)
+}
+
+impl LayeredConfig {
+    fn merge_file(&mut self, path: &Path, source: &str, table: Table) {
+        if let Ok(document) = DeTable::parse(source) {
+            record_origins(path, source, document.get_ref(), "", &mut self.origins);
+        }
+        merge(&mut self.table, table);
+    }
+
+    fn apply_env(&mut self, name: &str, raw: &str) -> Result<(), ConfigError> {
+        let keys: Vec<String> = name[ENV_PREFIX.len()..]
+            .split(ENV_SEPARATOR)
+            .map(|k| k.to_lowercase())
+            .collect();
+        if keys.iter().any(|k| k.is_empty()) {
+            return Err(ConfigError::LayerError(format!(
+                "{}: nome de variável inválido",
+                name
+            )));
+        }
+
+        let (last, parents) = keys.split_last().expect("split não retorna vazio");
+        let mut table = &mut self.table;
+        for key in parents {
+            let entry = table
+                .entry(key.as_str())
+                .or_insert_with(|| Value::Table(Table::new()));
+            table = match entry {
+                Value::Table(t) => t,
+                _ => {
+                    return Err(ConfigError::LayerError(format!(
+                        "{}: {} não é uma tabela",
+                        name, key
+                    )));
+                }
+            };
+        }
+
+        table.insert(last.clone(), parse_env_value(raw));
+        self.origins
+            .insert(keys.join("."), Origin::Env(name.to_string()));
+        Ok(())
+    }
+
+    fn load_secret_file(&mut self, key: &str, path: &Path) -> Result<(), ConfigError> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| ConfigError::LayerError(format!("{}: {}: {}", key, path.display(), e)))?;
+
+        let target = &key[..key.len() - SECRET_FILE_SUFFIX.len()];
+        let (parents, field) = match target.rsplit_once('.') {
+            Some((parents, field)) => (Some(parents), field),
+            None => (None, target),
+        };
+        let file_field = format!("{}{}", field, SECRET_FILE_SUFFIX);
+
+        // Mesmo caminho gerado por collect_secret_files, com índices nas
+        // listas de tabelas (auth.users[0])
+        let mut table = &mut self.table;
+        for part in parents.into_iter().flat_map(|p| p.split('.')) {
+            let (name, index) = match part.strip_suffix(']').and_then(|p| p.split_once('[')) {
+                Some((name, index)) => (name, index.parse::<usize>().ok()),
+                None => (part, None),
+            };
+            let next = match (table.get_mut(name), index) {
+                (Some(Value::Table(t)), None) => Some(t),
+                (Some(Value::Array(items)), Some(i)) => match items.get_mut(i) {
+                    Some(Value::Table(t)) => Some(t),
+                    _ => None,
+                },
+                _ => None,
+            };
+            table = next.ok_or_else(|| {
+                ConfigError::LayerError(format!("{}: {} não é uma tabela", key, part))
+            })?;
+        }
+
+        if table.contains_key(field) {
+            return Err(ConfigError::LayerError(format!(
+                "defina apenas {} ou {}",
+                target, key
+            )));
+        }
+
+        table.remove(&file_field);
+        table.insert(
+            field.to_string(),
+            Value::String(content.trim_end_matches(['\r', '\n']).to_string()),
+        );
+        self.origins.remove(key);
+        self.origins
+            .insert(target.to_string(), Origin::SecretFile(path.to_path_buf()));
+        Ok(())
+    }
+}
+
+fn take_includes(table: &mut Table) -> Result<Vec<String>, ConfigError> {
+    match table.remove(INCLUDE_KEY) {
+        None => Ok(Vec::new()),
+        Some(Value::Array(items)) => items
+            .into_iter()
+            .map(|item| match item {
+                Value::String(s) => Ok(s),
+                other => Err(ConfigError::LayerError(format!(
+                    "include: esperado texto, encontrado {}",
+                    other.type_str()
+                ))),
+            })
+            .collect(),
+        Some(other) => Err(ConfigError::LayerError(format!(
+            "include: esperada uma lista, encontrado {}",
+            other.type_str()
+        ))),
+    }
+}
+
+fn expand_include(base_dir: &Path, pattern: &str) -> Result<Vec<PathBuf>, ConfigError> {
+    let full = base_dir.join(pattern);
+    let full = full.to_string_lossy();
+
+    let mut paths = glob::glob(&full)
+        .map_err(|e| ConfigError::LayerError(format!("include \"{}\": {}", pattern, e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| ConfigError::LayerError(format!("include \"{}\": {}", pattern, e)))?;
+    paths.sort();
+
+    // Um caminho sem curingas precisa existir; um glob pode não encontrar nada
+    if paths.is_empty() && !pattern.contains(['*', '?', '[']) {
+        return Err(ConfigError::LayerError(format!(
+            "include \"{}\": arquivo não encontrado",
+            pattern
+        )));
+    }
+
+    Ok(paths)
+}
+
+fn merge(dst: &mut Table, src: Table) {
+    for (key, value) in src {
+        match (dst.get_mut(&key), value) {
+            (Some(Value::Table(d)), Value::Table(s)) => merge(d, s),
+            (_, value) => {
+                dst.insert(key, value);
+            }
+        }
+    }
+}
+
+fn record_origins(path: &Path, source: &str, table: &DeTable, prefix: &str, origins: &mut Origins) {
+    for (key, value) in table.iter() {
+        let name: &str = key.get_ref();
+        if prefix.is_empty() && name == INCLUDE_KEY {
+            continue;
+        }
+
+        let full = if prefix.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}.{}", prefix, name)
+        };
+
+        match value.get_ref() {
+            DeValue::Table(t) => record_origins(path, source, t, &full, origins),
+            _ => {
+                let line = source[..key.span().start].matches('\n').count() + 1;
+                origins.insert(
+                    full,
+                    Origin::File {
+                        path: path.to_path_buf(),
+                        line,
+                    },
+                );
+            }
+        }
+    }
+}
+
+// O valor é interpretado como TOML (números, booleanos, listas) e, se não for
+// válido, usado como texto
+fn parse_env_value(raw: &str) -> Value {
+    toml::from_str::<Table>(&format!("v = {}", raw))
+        .ok()
+        .and_then(|mut t| t.remove("v"))
+        .unwrap_or_else(|| Value::String(raw.to_string()))
+}
+
+fn collect_secret_files(table: &Table, prefix: &str, out: &mut Vec<(String, PathBuf)>) {
+    for (key, value) in table {
+        let full = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+
+        match value {
+            Value::Table(t) => collect_secret_files(t, &full, out),
+            // [[auth.users]], [[dkim.keys]]: cada tabela com o seu índice
+            Value::Array(items) => {
+                for (i, item) in items.iter().enumerate() {
+                    if let Value::Table(t) = item {
+                        collect_secret_files(t, &format!("{}[{}]", full, i), out);
+                    }
+                }
+            }
+            Value::String(path) if key.ends_with(SECRET_FILE_SUFFIX) => {
+                out.push((full, PathBuf::from(path)))
+            }
+            _ => {}
+        }
+    }
+}
+
+fn redact(value: &mut Value) {
+    match value {
+        Value::Table(table) => {
+            for (key, value) in table.iter_mut() {
+                if value.is_str() && is_secret(key) {
+                    *value = Value::String("<oculto>".to_string());
+                } else if let Value::Array(items) = value
+                    && is_secret(key)
+                {
+                    // Listas de segredos, como srs.previous_secrets
+                    items.fill(Value::String("<oculto>".to_string()));
+                } else {
+                    redact(value);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+fn is_secret(key: &str) -> bool {
+    key == "private_key" || key == "token" || key.contains("password") || key.contains("secret")
+}
@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct LocalDeliveryConfig {
+    // Domínios cujo destino final é este servidor: os jobs são entregues nas
+    // Maildirs em vez de retransmitidos
+    pub local_domains: Vec<String>,
+    // Maildir++ de cada usuário; {domain} e {user} são substituídos
+    pub maildir: String,
+    // user+pasta entrega na pasta .pasta da Maildir; vazio desativa
+    pub recipient_delimiter: String,
+    // Cria as Maildirs e pastas que não existem. Desligado, um usuário sem
+    // Maildir é desconhecido e uma pasta inexistente vai para a INBOX
+    pub autocreate: bool,
+    // Quota padrão de cada Maildir (0 = sem limite). A definição em um
+    // maildirsize existente tem precedência.
+    pub quota_bytes: u64,
+    pub quota_messages: u64,
+}
+
+impl LocalDeliveryConfig {
+    pub fn is_local(&self, domain: &str) -> bool {
+        self.local_domains
+            .iter()
+            .any(|d| d.eq_ignore_ascii_case(domain))
+    }
+}
+
+impl Default for LocalDeliveryConfig {
+    fn default() -> Self {
+        LocalDeliveryConfig {
+            local_domains: Vec::new(),
+            maildir: "/var/mail/{domain}/{user}".to_string(),
+            recipient_delimiter: "+".to_string(),
+            autocreate: false,
+            quota_bytes: 0,
+            quota_messages: 0,
+        }
+    }
+}
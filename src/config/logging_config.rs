@@ -1,10 +1,20 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize, Clone)]
+// Campos ausentes usam o padrão, para que possam vir de arquivos diferentes
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
 pub struct LoggingConfig {
     pub level: String,
     pub format: String,
-    pub file: Option<String>
+    pub file: Option<String>,
+    // Log estruturado de transações e entregas (uma linha JSON por evento)
+    pub mail_log: Option<String>,
+    // "daily", "hourly" ou "never"
+    pub rotation: String,
+    // Rotaciona também ao atingir o tamanho; 0 desativa
+    pub max_size_mb: u64,
+    // Quantidade de arquivos rotacionados mantidos
+    pub max_files: usize,
 }
 
 impl Default for LoggingConfig {
@@ -13,6 +23,10 @@ impl Default for LoggingConfig {
             level: "debug".to_string(),
             format: "pretty".to_string(),
             file: None,
+            mail_log: None,
+            rotation: "daily".to_string(),
+            max_size_mb: 100,
+            max_files: 7,
         }
     }
-}
\ No newline at end of file
+}
@@ -1,32 +1,75 @@
+pub mod admin_config;
+pub mod arc_config;
+pub mod auth_config;
 pub mod config_error;
+pub mod delivery_config;
 pub mod dkim_config;
+pub mod dmarc_config;
+pub mod dns_config;
+pub mod loader;
+pub mod local_delivery_config;
 pub mod logging_config;
+pub mod metrics_config;
+pub mod queue_config;
+pub mod recipients_config;
+pub mod relay_config;
 pub mod server_config;
+pub mod shared_config;
+pub mod spf_config;
+pub mod srs_config;
+pub mod submission_config;
+pub mod validation;
 
 // use std::path::PathBuf;
 use crate::config::{
-    config_error::ConfigError, dkim_config::DkimConfig, logging_config::LoggingConfig,
-    server_config::ServerConfig,
+    admin_config::AdminConfig, arc_config::ArcConfig, auth_config::AuthConfig,
+    config_error::ConfigError, delivery_config::DeliveryConfig, dkim_config::DkimConfig,
+    dmarc_config::DmarcConfig, dns_config::DnsConfig, local_delivery_config::LocalDeliveryConfig,
+    logging_config::LoggingConfig, metrics_config::MetricsConfig, queue_config::QueueConfig,
+    recipients_config::RecipientsConfig, relay_config::RelayConfig, server_config::ServerConfig,
+    spf_config::SpfConfig, srs_config::SrsConfig, submission_config::SubmissionConfig,
 };
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Config {
     pub server: ServerConfig,
     // #[serde(default)]
     // pub tls: TlsConfig,
     #[serde(default)]
     pub dkim: DkimConfig,
-    // #[serde(default)]
-    // pub auth: AuthConfig,
-    // #[serde(default)]
-    // pub relay: RelayConfig,
+    #[serde(default)]
+    pub auth: AuthConfig,
+    #[serde(default)]
+    pub relay: RelayConfig,
     #[serde(default)]
     pub logging: LoggingConfig,
     // #[serde(default)]
     // pub database: DatabaseConfig,
-    // #[serde(default)]
-    // pub queue: QueueConfig,
+    #[serde(default)]
+    pub queue: QueueConfig,
+    #[serde(default)]
+    pub metrics: MetricsConfig,
+    #[serde(default)]
+    pub admin: AdminConfig,
+    #[serde(default)]
+    pub dns: DnsConfig,
+    #[serde(default)]
+    pub spf: SpfConfig,
+    #[serde(default)]
+    pub dmarc: DmarcConfig,
+    #[serde(default)]
+    pub arc: ArcConfig,
+    #[serde(default)]
+    pub submission: SubmissionConfig,
+    #[serde(default)]
+    pub local_delivery: LocalDeliveryConfig,
+    #[serde(default)]
+    pub delivery: DeliveryConfig,
+    #[serde(default)]
+    pub recipients: RecipientsConfig,
+    #[serde(default)]
+    pub srs: SrsConfig,
     // #[serde(default)]
     // pub plugins: PluginsConfig,
 }
@@ -39,40 +82,12 @@ pub struct Config {
 //     pub min_version: String,
 // }
 
-// #[derive(Debug, Deserialize, Clone)]
-// pub struct AuthConfig {
-//     pub enabled: bool,
-//     pub mechanisms: Vec<String>,
-//     pub backend: String,
-//     pub users: Option<Vec<StaticUser>>,
-// }
-
-// #[derive(Debug, Deserialize, Clone)]
-// pub struct StaticUser {
-//     pub username: String,
-//     pub password_hash: String
-// }
-
-// #[derive(Debug, Deserialize, Clone)]
-// pub struct RelayConfig {
-//     pub local_domains: Vec<String>,
-//     pub allow_authenticated_relay: bool,
-//     pub trusted_ips: Vec<String>
-// }
-
 // #[derive(Debug, Deserialize, Clone)]
 // pub struct DatabaseConfig {
 //     pub driver: String,
 //     pub url: String,
 // }
 
-// #[derive(Debug, Deserialize, Clone)]
-// pub struct QueueConfig {
-//     pub native_dir: PathBuf,
-//     pub js_dir: PathBuf,
-//     pub load_order: Vec<String>
-// }
-
 // #[derive(Debug, Deserialize, Clone)]
 // pub struct PluginsConfig {
 //     pub native_dir: PathBuf,
@@ -82,9 +97,19 @@ pub struct Config {
 
 impl Config {
     pub fn load(path: &str) -> Result<Self, ConfigError> {
-        let content = std::fs::read_to_string(path)?;
-        let config: Config = toml::from_str(&content)?;
+        Self::load_with_origins(path).map(|(config, _)| config)
+    }
+
+    // Arquivo principal + includes + variáveis de ambiente (ver loader.rs)
+    pub fn load_with_origins(path: &str) -> Result<(Self, loader::Origins), ConfigError> {
+        let layered = loader::load(path)?;
+        let config: Config = layered.table.try_into()?;
+
+        let issues = validation::validate(&config, &layered.origins);
+        if !issues.is_empty() {
+            return Err(ConfigError::ValidationError(issues));
+        }
 
-        Ok(config)
+        Ok((config, layered.origins))
     }
 }
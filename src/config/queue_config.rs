@@ -0,0 +1,50 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct QueueConfig {
+    #[serde(default = "default_spool_dir")]
+    pub spool_dir: PathBuf,
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+    #[serde(default = "default_retry_interval_secs")]
+    pub retry_interval_secs: u64,
+    #[serde(default = "default_poll_interval_secs")]
+    pub poll_interval_secs: u64,
+    // Lotes (uma mensagem por transporte) entregues ao mesmo tempo
+    #[serde(default = "default_max_concurrent_deliveries")]
+    pub max_concurrent_deliveries: usize,
+}
+
+impl Default for QueueConfig {
+    fn default() -> Self {
+        QueueConfig {
+            spool_dir: default_spool_dir(),
+            max_attempts: default_max_attempts(),
+            retry_interval_secs: default_retry_interval_secs(),
+            poll_interval_secs: default_poll_interval_secs(),
+            max_concurrent_deliveries: default_max_concurrent_deliveries(),
+        }
+    }
+}
+
+fn default_spool_dir() -> PathBuf {
+    PathBuf::from("spool")
+}
+
+fn default_max_attempts() -> u32 {
+    10
+}
+
+fn default_retry_interval_secs() -> u64 {
+    300
+}
+
+fn default_poll_interval_secs() -> u64 {
+    5
+}
+
+fn default_max_concurrent_deliveries() -> usize {
+    10
+}
@@ -0,0 +1,18 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+// Tabelas em TOML (extensão .toml) ou no formato do Postfix. São relidas
+// no reload e quando o arquivo muda.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(default)]
+pub struct RecipientsConfig {
+    // Como /etc/aliases ("nome: destino, destino"), para os local_domains
+    pub aliases_path: Option<PathBuf>,
+    // Endereço, ou @domínio para catch-all, e seus destinos. Os domínios das
+    // chaves passam a ser locais.
+    pub virtual_alias_path: Option<PathBuf>,
+    // Caixas dos domínios virtuais, com a Maildir opcional (o padrão é
+    // local_delivery.maildir). Os domínios das chaves passam a ser locais.
+    pub virtual_mailbox_path: Option<PathBuf>,
+}
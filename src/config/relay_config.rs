@@ -0,0 +1,58 @@
+use std::net::IpAddr;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct RelayConfig {
+    // Redes (IP ou CIDR) que podem enviar para domínios de fora sem AUTH
+    pub trusted_networks: Vec<String>,
+}
+
+impl RelayConfig {
+    pub fn is_trusted(&self, ip: IpAddr) -> bool {
+        // Endereços IPv4 mapeados (::ffff:a.b.c.d) de um listener IPv6
+        let ip = ip.to_canonical();
+        self.trusted_networks
+            .iter()
+            .filter_map(|network| parse_network(network))
+            .any(|(network, len)| contains(network, len, ip))
+    }
+}
+
+impl Default for RelayConfig {
+    fn default() -> Self {
+        RelayConfig {
+            trusted_networks: vec!["127.0.0.0/8".to_string(), "::1/128".to_string()],
+        }
+    }
+}
+
+// "10.0.0.0/8", "2001:db8::/32" ou um endereço só
+pub fn parse_network(text: &str) -> Option<(IpAddr, u8)> {
+    let (ip, len) = match text.trim().split_once('/') {
+        Some((ip, len)) => (ip, Some(len)),
+        None => (text.trim(), None),
+    };
+    let ip: IpAddr = ip.parse().ok()?;
+    let max = if ip.is_ipv4() { 32 } else { 128 };
+    let len = match len {
+        Some(len) => len.parse().ok().filter(|len| *len <= max)?,
+        None => max,
+    };
+    Some((ip, len))
+}
+
+fn contains(network: IpAddr, len: u8, ip: IpAddr) -> bool {
+    match (network, ip) {
+        (IpAddr::V4(network), IpAddr::V4(ip)) => {
+            let mask = u32::MAX.checked_shl(32 - len as u32).unwrap_or(0);
+            u32::from(ip) & mask == u32::from(network) & mask
+        }
+        (IpAddr::V6(network), IpAddr::V6(ip)) => {
+            let mask = u128::MAX.checked_shl(128 - len as u32).unwrap_or(0);
+            u128::from(ip) & mask == u128::from(network) & mask
+        }
+        _ => false,
+    }
+}
@@ -1,6 +1,8 @@
-use serde::Deserialize;
+use std::path::PathBuf;
 
-#[derive(Debug, Deserialize, Clone)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct ServerConfig {
     #[serde(default = "default_hostname")]
     pub hostname: String,
@@ -10,14 +12,40 @@ pub struct ServerConfig {
     pub port: u16,
     #[serde(default = "default_submission_port")]
     pub submission_port: u16,
+    // Reservada para a submissão com TLS implícito; ainda não é atendida
     #[serde(default = "default_smtps_port")]
     pub smtps_port: u16,
+    // Sessões simultâneas, somando todos os listeners; as excedentes
+    // recebem 421
     #[serde(default = "default_max_connections")]
     pub max_connections: usize,
     #[serde(default = "default_max_message_size_mb")]
     pub max_message_size_mb: usize,
     #[serde(default = "default_banner")]
-    pub banner: String
+    pub banner: String,
+    #[serde(default = "default_shutdown_timeout_secs")]
+    pub shutdown_timeout_secs: u64,
+    // Socket Unix usado pela linha de comando e pelo sendmail local
+    #[serde(default = "default_control_socket")]
+    pub control_socket: PathBuf,
+    // Permissão do socket, em octal; com 0660 o grupo também pode usar a
+    // linha de comando e o sendmail
+    #[serde(default = "default_control_socket_mode")]
+    pub control_socket_mode: String,
+    // Grupo do socket (por exemplo mail); vazio mantém o grupo do processo
+    #[serde(default)]
+    pub control_socket_group: String,
+    // Mensagens com mais headers Received que isso estão em loop
+    #[serde(default = "default_max_hops")]
+    pub max_hops: usize,
+}
+
+impl ServerConfig {
+    pub fn control_socket_mode(&self) -> Option<u32> {
+        u32::from_str_radix(&self.control_socket_mode, 8)
+            .ok()
+            .filter(|mode| *mode <= 0o777)
+    }
 }
 
 fn default_hostname() -> String {
@@ -28,16 +56,16 @@ fn default_ip() -> String {
     "127.0.0.1".to_string()
 }
 
-fn default_port() -> u16 { 
+fn default_port() -> u16 {
     2525
 }
 
 fn default_submission_port() -> u16 {
-    25
+    587
 }
 
 fn default_smtps_port() -> u16 {
-    487
+    465
 }
 
 fn default_max_connections() -> usize {
@@ -50,4 +78,21 @@ fn default_max_message_size_mb() -> usize {
 
 fn default_banner() -> String {
     "smtp server".to_string()
-}
\ No newline at end of file
+}
+
+fn default_shutdown_timeout_secs() -> u64 {
+    30
+}
+
+fn default_control_socket() -> PathBuf {
+    PathBuf::from("smtp.sock")
+}
+
+fn default_control_socket_mode() -> String {
+    "0660".to_string()
+}
+
+// RFC 5321 §6.3 pede um limite de pelo menos 100
+fn default_max_hops() -> usize {
+    100
+}
@@ -0,0 +1,65 @@
+use std::sync::Arc;
+
+use arc_swap::ArcSwap;
+
+use crate::{
+    config::{Config, config_error::ConfigError},
+    recipients::{Recipients, recipient_error::RecipientError},
+};
+
+// Configuração ativa, trocada atomicamente no SIGHUP. Cada sessão guarda o
+// snapshot que recebeu ao ser criada até terminar.
+pub struct SharedConfig {
+    path: String,
+    current: ArcSwap<Config>,
+    // Tabelas de destinatários da configuração ativa, relidas também quando
+    // um arquivo muda
+    recipients: ArcSwap<Recipients>,
+}
+
+impl SharedConfig {
+    pub fn new(path: &str, config: Config) -> Result<Self, RecipientError> {
+        let config = Arc::new(config);
+        let recipients = Recipients::load(config.clone())?;
+        Ok(Self {
+            path: path.to_string(),
+            current: ArcSwap::new(config),
+            recipients: ArcSwap::from_pointee(recipients),
+        })
+    }
+
+    pub fn load(&self) -> Arc<Config> {
+        self.current.load_full()
+    }
+
+    pub fn recipients(&self) -> Arc<Recipients> {
+        self.recipients.load_full()
+    }
+
+    // Lê o arquivo novamente sem alterar a configuração ativa
+    pub fn read(&self) -> Result<Config, ConfigError> {
+        Config::load(&self.path)
+    }
+
+    // Só troca a configuração se as tabelas dela puderem ser lidas
+    pub fn store(&self, config: Config) -> Result<(), RecipientError> {
+        let config = Arc::new(config);
+        let recipients = Recipients::load(config.clone())?;
+        self.current.store(config);
+        self.recipients.store(Arc::new(recipients));
+        Ok(())
+    }
+
+    // Relê as tabelas que mudaram desde a leitura. Um reload no meio prevalece.
+    pub fn refresh_recipients(&self) -> Result<bool, RecipientError> {
+        let current = self.recipients.load_full();
+        if !current.is_stale() {
+            return Ok(false);
+        }
+        let recipients = Recipients::load(current.config().clone())?;
+        let previous = self
+            .recipients
+            .compare_and_swap(&current, Arc::new(recipients));
+        Ok(Arc::ptr_eq(&previous, &current))
+    }
+}
@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+
+use crate::spf::SpfResult;
+
+// Ações possíveis para cada resultado
+pub const SPF_ACTIONS: [&str; 3] = ["accept", "reject", "defer"];
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct SpfConfig {
+    // Verifica o MAIL FROM das mensagens recebidas
+    pub enabled: bool,
+    // Verifica também a identidade do HELO/EHLO (RFC 7208 §2.3)
+    pub helo: bool,
+    // O que fazer com cada resultado: accept, reject ou defer. pass é
+    // sempre aceito.
+    pub on_fail: String,
+    pub on_softfail: String,
+    pub on_neutral: String,
+    pub on_none: String,
+    pub on_temperror: String,
+    pub on_permerror: String,
+}
+
+impl SpfConfig {
+    pub fn action(&self, result: SpfResult) -> &str {
+        match result {
+            SpfResult::Pass => "accept",
+            SpfResult::Fail => &self.on_fail,
+            SpfResult::SoftFail => &self.on_softfail,
+            SpfResult::Neutral => &self.on_neutral,
+            SpfResult::None => &self.on_none,
+            SpfResult::TempError => &self.on_temperror,
+            SpfResult::PermError => &self.on_permerror,
+        }
+    }
+}
+
+impl Default for SpfConfig {
+    fn default() -> Self {
+        SpfConfig {
+            enabled: false,
+            helo: false,
+            on_fail: "reject".to_string(),
+            on_softfail: "accept".to_string(),
+            on_neutral: "accept".to_string(),
+            on_none: "accept".to_string(),
+            on_temperror: "accept".to_string(),
+            on_permerror: "accept".to_string(),
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct SrsConfig {
+    // Reescreve o remetente das mensagens encaminhadas a domínios remotos
+    pub enabled: bool,
+    // Domínio dos endereços SRS; vazio usa server.hostname
+    pub domain: String,
+    // Assina os novos endereços (pode vir de secret_file)
+    pub secret: String,
+    // Segredos anteriores, ainda aceitos nos bounces durante a rotação
+    pub previous_secrets: Vec<String>,
+    // Idade máxima, em dias, de um endereço SRS recebido
+    pub max_age_days: u32,
+}
+
+impl SrsConfig {
+    pub fn domain<'a>(&'a self, hostname: &'a str) -> &'a str {
+        if self.domain.is_empty() {
+            hostname
+        } else {
+            &self.domain
+        }
+    }
+}
+
+impl Default for SrsConfig {
+    fn default() -> Self {
+        SrsConfig {
+            enabled: false,
+            domain: String::new(),
+            secret: String::new(),
+            previous_secrets: Vec::new(),
+            max_age_days: 21,
+        }
+    }
+}
@@ -0,0 +1,11 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(default)]
+pub struct SubmissionConfig {
+    // Escuta em server.submission_port, onde as mensagens passam pelas
+    // correções de MSA (RFC 6409 §8)
+    pub enabled: bool,
+    // Remove os Received internos e omite o IP do cliente no nosso
+    pub hide_client: bool,
+}
@@ -0,0 +1,736 @@
+use std::{
+    fmt,
+    net::{IpAddr, SocketAddr},
+    path::Path,
+};
+
+use crate::{
+    auth::password,
+    config::{
+        Config,
+        auth_config::{AUTH_BACKENDS, AUTH_MECHANISMS},
+        delivery_config::{BUILTIN_TRANSPORTS, TRANSPORT_KINDS},
+        dmarc_config::DMARC_ACTIONS,
+        loader::{Origin, Origins},
+        relay_config,
+        spf_config::SPF_ACTIONS,
+    },
+    dkim::canonicalization::Canonicalization,
+    logging::rotating_file::Rotation,
+};
+
+const DKIM_ALGORITHMS: [&str; 2] = ["rsa-sha256", "ed25519-sha256"];
+const LOG_FORMATS: [&str; 2] = ["pretty", "json"];
+
+#[derive(Debug)]
+pub struct ConfigIssue {
+    pub key: String,
+    pub origin: Option<Origin>,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.origin {
+            Some(origin) => write!(f, "{} ({}): {}", self.key, origin, self.message),
+            None => write!(f, "{} (valor padrão): {}", self.key, self.message),
+        }
+    }
+}
+
+// Verificações que o serde não faz. Todos os problemas são reportados de uma
+// vez, com a chave e o arquivo/linha (ou variável) onde foram definidos.
+pub fn validate(config: &Config, origins: &Origins) -> Vec<ConfigIssue> {
+    let mut validator = Validator {
+        origins,
+        issues: Vec::new(),
+    };
+
+    validator.server(config);
+    validator.dkim(config);
+    validator.spf(config);
+    validator.dmarc(config);
+    validator.arc(config);
+    validator.auth(config);
+    validator.local_delivery(config);
+    validator.recipients(config);
+    validator.srs(config);
+    validator.relay(config);
+    validator.delivery(config);
+    validator.logging(config);
+    validator.queue(config);
+    validator.metrics(config);
+    validator.admin(config);
+
+    validator.issues
+}
+
+struct Validator<'a> {
+    origins: &'a Origins,
+    issues: Vec<ConfigIssue>,
+}
+
+impl Validator<'_> {
+    fn server(&mut self, config: &Config) {
+        let server = &config.server;
+
+        if server.hostname.trim().is_empty() {
+            self.report("server.hostname", "não pode ser vazio");
+        } else if server.hostname.contains(char::is_whitespace) {
+            self.report("server.hostname", "não pode conter espaços");
+        }
+
+        if server.ip.parse::<IpAddr>().is_err() {
+            self.report("server.ip", format!("endereço IP inválido: {}", server.ip));
+        }
+
+        let ports = [
+            ("server.port", server.port),
+            ("server.submission_port", server.submission_port),
+            ("server.smtps_port", server.smtps_port),
+        ];
+        for (i, (key, port)) in ports.iter().enumerate() {
+            if *port == 0 {
+                self.report(key, "a porta não pode ser 0");
+            }
+            if let Some((other, _)) = ports[..i].iter().find(|(_, p)| p == port) {
+                self.report(key, format!("porta {} já usada por {}", port, other));
+            }
+        }
+
+        if server.max_connections == 0 {
+            self.report("server.max_connections", "deve ser maior que 0");
+        }
+        if server.max_message_size_mb == 0 {
+            self.report("server.max_message_size_mb", "deve ser maior que 0");
+        }
+        if server.max_hops == 0 {
+            self.report("server.max_hops", "deve ser maior que 0");
+        }
+
+        if server.control_socket.as_os_str().is_empty() {
+            self.report("server.control_socket", "não pode ser vazio");
+        } else {
+            self.writable_parent("server.control_socket", &server.control_socket);
+        }
+        if server.control_socket_mode().is_none() {
+            self.report(
+                "server.control_socket_mode",
+                format!(
+                    "permissão inválida \"{}\" (use octal, como 0660)",
+                    server.control_socket_mode
+                ),
+            );
+        }
+    }
+
+    fn dkim(&mut self, config: &Config) {
+        let dkim = &config.dkim;
+
+        let entries = dkim.key_entries();
+        for (prefix, key) in &entries {
+            if !DKIM_ALGORITHMS.contains(&key.algorithm.as_str()) {
+                self.report(
+                    &format!("{}.algorithm", prefix),
+                    format!(
+                        "algoritmo desconhecido \"{}\" (use {})",
+                        key.algorithm,
+                        DKIM_ALGORITHMS.join(" ou ")
+                    ),
+                );
+            }
+        }
+
+        if !dkim.enabled {
+            return;
+        }
+
+        for (prefix, key) in &entries {
+            if key.domain.trim().is_empty() {
+                self.report(
+                    &format!("{}.domain", prefix),
+                    "obrigatório quando o DKIM está habilitado",
+                );
+            }
+            if key.selector.trim().is_empty() {
+                self.report(
+                    &format!("{}.selector", prefix),
+                    "obrigatório quando o DKIM está habilitado",
+                );
+            }
+            if key.private_key.is_none() {
+                self.readable_file(
+                    &format!("{}.private_key_path", prefix),
+                    &key.private_key_path,
+                );
+            }
+        }
+        for (prefix, key) in &entries {
+            let times = [
+                ("activate_at", &key.activate_at, key.activation()),
+                ("retire_at", &key.retire_at, key.retirement()),
+            ];
+            for (name, value, parsed) in times {
+                if let Some(value) = value
+                    && parsed.is_none()
+                {
+                    self.report(
+                        &format!("{}.{}", prefix, name),
+                        format!(
+                            "data inválida \"{}\" (use \"2026-11-01\" ou RFC 3339)",
+                            value
+                        ),
+                    );
+                }
+            }
+            if let (Some(activation), Some(retirement)) = (key.activation(), key.retirement())
+                && retirement <= activation
+            {
+                self.report(
+                    &format!("{}.retire_at", prefix),
+                    "deve ser posterior a activate_at",
+                );
+            }
+        }
+        for (i, (prefix, key)) in entries.iter().enumerate() {
+            let duplicate = entries[..i].iter().any(|(_, other)| {
+                other.domain.eq_ignore_ascii_case(&key.domain) && other.selector == key.selector
+            });
+            if duplicate {
+                self.report(
+                    &format!("{}.selector", prefix),
+                    format!("seletor {} repetido para {}", key.selector, key.domain),
+                );
+            }
+        }
+
+        if !dkim.headers.is_empty() && !dkim.headers.iter().any(|h| h.eq_ignore_ascii_case("from"))
+        {
+            self.report("dkim.headers", "deve incluir o header From (RFC 6376 §5.4)");
+        }
+
+        if Canonicalization::parse_pair(&dkim.canonicalization).is_none() {
+            self.report(
+                "dkim.canonicalization",
+                format!(
+                    "canonicalização desconhecida \"{}\" (use simple ou relaxed, como \"relaxed/relaxed\")",
+                    dkim.canonicalization
+                ),
+            );
+        }
+        if dkim.expiration_secs == Some(0) {
+            self.report("dkim.expiration_secs", "deve ser maior que 0");
+        }
+        if let Some(identity) = &dkim.identity {
+            let domain = identity
+                .rsplit_once('@')
+                .map(|(_, d)| d)
+                .unwrap_or_default();
+            let domain = domain.to_ascii_lowercase();
+            let in_domain = entries.iter().any(|(_, key)| {
+                let d = key.domain.to_ascii_lowercase();
+                domain == d || domain.ends_with(&format!(".{}", d))
+            });
+            if !in_domain {
+                self.report(
+                    "dkim.identity",
+                    "o domínio de i= deve ser o de uma das chaves ou um subdomínio",
+                );
+            }
+        }
+    }
+
+    fn spf(&mut self, config: &Config) {
+        let spf = &config.spf;
+
+        let actions = [
+            ("spf.on_fail", &spf.on_fail),
+            ("spf.on_softfail", &spf.on_softfail),
+            ("spf.on_neutral", &spf.on_neutral),
+            ("spf.on_none", &spf.on_none),
+            ("spf.on_temperror", &spf.on_temperror),
+            ("spf.on_permerror", &spf.on_permerror),
+        ];
+        for (key, action) in actions {
+            if !SPF_ACTIONS.contains(&action.as_str()) {
+                self.report(
+                    key,
+                    format!(
+                        "ação desconhecida \"{}\" (use {})",
+                        action,
+                        SPF_ACTIONS.join(", ")
+                    ),
+                );
+            }
+        }
+    }
+
+    fn dmarc(&mut self, config: &Config) {
+        let dmarc = &config.dmarc;
+
+        let actions = [
+            ("dmarc.on_reject", &dmarc.on_reject),
+            ("dmarc.on_quarantine", &dmarc.on_quarantine),
+        ];
+        for (key, action) in actions {
+            if !DMARC_ACTIONS.contains(&action.as_str()) {
+                self.report(
+                    key,
+                    format!(
+                        "ação desconhecida \"{}\" (use {})",
+                        action,
+                        DMARC_ACTIONS.join(", ")
+                    ),
+                );
+            }
+        }
+
+        if dmarc.enabled {
+            self.readable_file("dmarc.public_suffix_list", &dmarc.public_suffix_list);
+        }
+        for (i, suffix) in dmarc.public_suffixes.iter().enumerate() {
+            if suffix.trim().is_empty() || suffix.contains(char::is_whitespace) {
+                self.report(
+                    &format!("dmarc.public_suffixes[{}]", i),
+                    format!("sufixo inválido \"{}\"", suffix),
+                );
+            }
+        }
+
+        if dmarc.enabled && dmarc.reports {
+            if dmarc.report_dir.as_os_str().is_empty() {
+                self.report("dmarc.report_dir", "não pode ser vazio");
+            }
+            if !dmarc.report_from.is_empty() && !dmarc.report_from.contains('@') {
+                self.report(
+                    "dmarc.report_from",
+                    format!("endereço inválido \"{}\"", dmarc.report_from),
+                );
+            }
+        }
+    }
+
+    fn arc(&mut self, config: &Config) {
+        let arc = &config.arc;
+        if !arc.seal {
+            return;
+        }
+
+        let domain = arc.domain.to_ascii_lowercase();
+        if domain.trim().is_empty() {
+            self.report("arc.domain", "obrigatório quando arc.seal está habilitado");
+            return;
+        }
+        let has_key = config.dkim.key_entries().iter().any(|(_, key)| {
+            let d = key.domain.to_ascii_lowercase();
+            domain == d || domain.ends_with(&format!(".{}", d))
+        });
+        if !has_key {
+            self.report(
+                "arc.domain",
+                format!("nenhuma chave em [dkim] para {}", arc.domain),
+            );
+        }
+    }
+
+    fn auth(&mut self, config: &Config) {
+        let auth = &config.auth;
+        // A porta de submissão exige AUTH antes do MAIL FROM, e sem TLS o AUTH
+        // só é aceito com allow_insecure
+        if config.submission.enabled && !auth.enabled {
+            self.report(
+                "submission.enabled",
+                "a porta de submissão exige auth.enabled = true",
+            );
+        } else if config.submission.enabled && !auth.allow_insecure {
+            self.report(
+                "submission.enabled",
+                "sem suporte a TLS, a porta de submissão exige auth.allow_insecure = true",
+            );
+        }
+        if !auth.enabled {
+            return;
+        }
+
+        if auth.mechanisms.is_empty() {
+            self.report("auth.mechanisms", "informe pelo menos um mecanismo");
+        }
+        for mechanism in &auth.mechanisms {
+            if !AUTH_MECHANISMS.contains(&mechanism.to_ascii_uppercase().as_str()) {
+                self.report(
+                    "auth.mechanisms",
+                    format!(
+                        "mecanismo desconhecido \"{}\" (use {})",
+                        mechanism,
+                        AUTH_MECHANISMS.join(" ou ")
+                    ),
+                );
+            }
+        }
+
+        match auth.backend.as_str() {
+            "static" => {
+                if auth.users.is_empty() {
+                    self.report("auth.users", "nenhum usuário configurado");
+                }
+                for (i, user) in auth.users.iter().enumerate() {
+                    let prefix = format!("auth.users[{}]", i);
+                    if user.username.trim().is_empty() || user.username.contains(':') {
+                        self.report(&format!("{}.username", prefix), "login inválido");
+                    } else if auth.users[..i]
+                        .iter()
+                        .any(|other| other.username.eq_ignore_ascii_case(&user.username))
+                    {
+                        self.report(
+                            &format!("{}.username", prefix),
+                            format!("usuário {} repetido", user.username),
+                        );
+                    }
+                    if !password::is_supported(&user.password_hash) {
+                        self.report(
+                            &format!("{}.password_hash", prefix),
+                            "hash inválido (use {ARGON2ID}, {SSHA256} ou {SSHA512})",
+                        );
+                    }
+                    for identity in &user.identities {
+                        if identity.trim().is_empty() || identity.contains(char::is_whitespace) {
+                            self.report(
+                                &format!("{}.identities", prefix),
+                                format!("identidade inválida \"{}\"", identity),
+                            );
+                        }
+                    }
+                }
+            }
+            "file" => self.readable_file("auth.users_path", &auth.users_path),
+            backend => self.report(
+                "auth.backend",
+                format!(
+                    "backend desconhecido \"{}\" (use {})",
+                    backend,
+                    AUTH_BACKENDS.join(" ou ")
+                ),
+            ),
+        }
+    }
+
+    fn local_delivery(&mut self, config: &Config) {
+        let local = &config.local_delivery;
+
+        for (i, domain) in local.local_domains.iter().enumerate() {
+            if domain.trim().is_empty() || domain.contains(['@', '/', ' ']) {
+                self.report(
+                    &format!("local_delivery.local_domains[{}]", i),
+                    format!("domínio inválido \"{}\"", domain),
+                );
+            }
+        }
+        if local.local_domains.is_empty() {
+            return;
+        }
+
+        if !local.maildir.contains("{user}") {
+            self.report("local_delivery.maildir", "deve conter {user}");
+        }
+        if local.recipient_delimiter.chars().count() > 1 {
+            self.report(
+                "local_delivery.recipient_delimiter",
+                "deve ser um único caractere ou vazio",
+            );
+        }
+    }
+
+    fn recipients(&mut self, config: &Config) {
+        let recipients = &config.recipients;
+        let tables = [
+            ("recipients.aliases_path", &recipients.aliases_path),
+            (
+                "recipients.virtual_alias_path",
+                &recipients.virtual_alias_path,
+            ),
+            (
+                "recipients.virtual_mailbox_path",
+                &recipients.virtual_mailbox_path,
+            ),
+        ];
+        for (key, path) in tables {
+            if let Some(path) = path {
+                self.readable_file(key, path);
+            }
+        }
+    }
+
+    fn srs(&mut self, config: &Config) {
+        let srs = &config.srs;
+        if !srs.enabled {
+            return;
+        }
+
+        if srs.secret.is_empty() {
+            self.report("srs.secret", "obrigatório com srs.enabled");
+        }
+        if srs.previous_secrets.iter().any(|secret| secret.is_empty()) {
+            self.report("srs.previous_secrets", "não pode conter segredos vazios");
+        }
+        if srs.domain.contains(['@', '/', ' ']) {
+            self.report("srs.domain", format!("domínio inválido \"{}\"", srs.domain));
+        }
+        // O timestamp dá a volta a cada 1024 dias
+        if !(1..1024).contains(&srs.max_age_days) {
+            self.report("srs.max_age_days", "deve estar entre 1 e 1023");
+        }
+    }
+
+    fn relay(&mut self, config: &Config) {
+        for (i, network) in config.relay.trusted_networks.iter().enumerate() {
+            if relay_config::parse_network(network).is_none() {
+                self.report(
+                    &format!("relay.trusted_networks[{}]", i),
+                    format!("rede inválida \"{}\" (use um IP ou CIDR)", network),
+                );
+            }
+        }
+    }
+
+    fn delivery(&mut self, config: &Config) {
+        let delivery = &config.delivery;
+
+        for (i, transport) in delivery.transports.iter().enumerate() {
+            let prefix = format!("delivery.transports[{}]", i);
+            if transport.name.trim().is_empty() {
+                self.report(&format!("{}.name", prefix), "não pode ser vazio");
+            } else if BUILTIN_TRANSPORTS.contains(&transport.name.as_str()) {
+                self.report(
+                    &format!("{}.name", prefix),
+                    format!("{} é um transporte embutido", transport.name),
+                );
+            } else if delivery.transports[..i]
+                .iter()
+                .any(|other| other.name == transport.name)
+            {
+                self.report(
+                    &format!("{}.name", prefix),
+                    format!("transporte {} repetido", transport.name),
+                );
+            }
+
+            match transport.kind.as_str() {
+                "lmtp" => {
+                    let address = &transport.address;
+                    let valid = if address.starts_with('/') {
+                        true
+                    } else {
+                        address.rsplit_once(':').is_some_and(|(host, port)| {
+                            !host.is_empty() && port.parse::<u16>().is_ok()
+                        })
+                    };
+                    if !valid {
+                        self.report(
+                            &format!("{}.address", prefix),
+                            format!(
+                                "endereço inválido \"{}\" (use host:porta ou o caminho do socket)",
+                                address
+                            ),
+                        );
+                    }
+                }
+                "mbox" => {
+                    if !transport.path.contains("{user}") {
+                        self.report(&format!("{}.path", prefix), "deve conter {user}");
+                    }
+                }
+                kind => self.report(
+                    &format!("{}.kind", prefix),
+                    format!(
+                        "tipo desconhecido \"{}\" (use {})",
+                        kind,
+                        TRANSPORT_KINDS.join(" ou ")
+                    ),
+                ),
+            }
+        }
+
+        for (i, route) in delivery.routes.iter().enumerate() {
+            let prefix = format!("delivery.routes[{}]", i);
+            if route.domain.is_some() == route.recipient.is_some() {
+                self.report(&prefix, "informe domain ou recipient");
+            }
+            let known = BUILTIN_TRANSPORTS.contains(&route.transport.as_str())
+                || delivery
+                    .transports
+                    .iter()
+                    .any(|t| t.name == route.transport);
+            if !known {
+                self.report(
+                    &format!("{}.transport", prefix),
+                    format!("transporte desconhecido \"{}\"", route.transport),
+                );
+            }
+        }
+    }
+
+    fn logging(&mut self, config: &Config) {
+        let logging = &config.logging;
+
+        if tracing_subscriber::EnvFilter::try_new(&logging.level).is_err() {
+            self.report(
+                "logging.level",
+                format!("nível de log inválido: {}", logging.level),
+            );
+        }
+
+        if Rotation::parse(&logging.rotation).is_none() {
+            self.report(
+                "logging.rotation",
+                format!(
+                    "rotação desconhecida \"{}\" (use hourly, daily ou never)",
+                    logging.rotation
+                ),
+            );
+        }
+        if logging.max_files == 0 {
+            self.report("logging.max_files", "deve ser maior que 0");
+        }
+        for (key, path) in [
+            ("logging.file", &logging.file),
+            ("logging.mail_log", &logging.mail_log),
+        ] {
+            if let Some(path) = path {
+                self.writable_parent(key, Path::new(path));
+            }
+        }
+        if logging.file.is_some() && logging.file == logging.mail_log {
+            self.report("logging.mail_log", "deve ser diferente de logging.file");
+        }
+
+        if !LOG_FORMATS.contains(&logging.format.as_str()) {
+            self.report(
+                "logging.format",
+                format!(
+                    "formato desconhecido \"{}\" (use {})",
+                    logging.format,
+                    LOG_FORMATS.join(" ou ")
+                ),
+            );
+        }
+    }
+
+    fn queue(&mut self, config: &Config) {
+        let queue = &config.queue;
+
+        if queue.spool_dir.as_os_str().is_empty() {
+            self.report("queue.spool_dir", "não pode ser vazio");
+        } else if queue.spool_dir.exists() && !queue.spool_dir.is_dir() {
+            self.report(
+                "queue.spool_dir",
+                format!("{} não é um diretório", queue.spool_dir.display()),
+            );
+        }
+
+        if queue.max_attempts == 0 {
+            self.report("queue.max_attempts", "deve ser maior que 0");
+        }
+        if queue.poll_interval_secs == 0 {
+            self.report("queue.poll_interval_secs", "deve ser maior que 0");
+        }
+        if queue.max_concurrent_deliveries == 0 {
+            self.report("queue.max_concurrent_deliveries", "deve ser maior que 0");
+        }
+    }
+
+    fn metrics(&mut self, config: &Config) {
+        let metrics = &config.metrics;
+        if !metrics.enabled {
+            return;
+        }
+
+        match metrics.listen.parse::<SocketAddr>() {
+            Ok(addr) if addr.port() == config.server.port => self.report(
+                "metrics.listen",
+                format!("porta {} já usada por server.port", addr.port()),
+            ),
+            Ok(_) => {}
+            Err(_) => self.report(
+                "metrics.listen",
+                format!("endereço inválido: {}", metrics.listen),
+            ),
+        }
+    }
+
+    fn admin(&mut self, config: &Config) {
+        let admin = &config.admin;
+        if !admin.enabled {
+            return;
+        }
+
+        match admin.listen.parse::<SocketAddr>() {
+            Ok(addr) if addr.port() == config.server.port => self.report(
+                "admin.listen",
+                format!("porta {} já usada por server.port", addr.port()),
+            ),
+            Ok(addr) if config.metrics.enabled && config.metrics.listen == admin.listen => self
+                .report(
+                    "admin.listen",
+                    format!("porta {} já usada por metrics.listen", addr.port()),
+                ),
+            Ok(_) => {}
+            Err(_) => self.report(
+                "admin.listen",
+                format!("endereço inválido: {}", admin.listen),
+            ),
+        }
+
+        if admin.token.trim().is_empty() {
+            self.report(
+                "admin.token",
+                "obrigatório quando a API de administração está habilitada",
+            );
+        }
+    }
+
+    fn readable_file(&mut self, key: &str, path: &Path) {
+        if path.as_os_str().is_empty() {
+            self.report(key, "caminho não informado");
+        } else if let Err(e) = std::fs::File::open(path) {
+            self.report(
+                key,
+                format!("não foi possível ler {}: {}", path.display(), e),
+            );
+        }
+    }
+
+    fn writable_parent(&mut self, key: &str, path: &Path) {
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        if !dir.is_dir() {
+            self.report(key, format!("diretório {} não existe", dir.display()));
+        }
+    }
+
+    fn report(&mut self, key: &str, message: impl Into<String>) {
+        let origin = self
+            .origins
+            .get(key)
+            .or_else(|| aliases(key).iter().find_map(|k| self.origins.get(*k)))
+            // Entradas de listas (dkim.keys[0].domain) têm a origem da lista
+            .or_else(|| {
+                key.split_once('[')
+                    .and_then(|(list, _)| self.origins.get(list))
+            })
+            .cloned();
+        self.issues.push(ConfigIssue {
+            key: key.to_string(),
+            origin,
+            message: message.into(),
+        });
+    }
+}
+
+fn aliases(key: &str) -> &'static [&'static str] {
+    match key {
+        "dkim.algorithm" => &["dkim.alogrithm"],
+        _ => &[],
+    }
+}
@@ -0,0 +1,32 @@
+use std::{io, path::Path};
+
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::UnixStream,
+};
+
+use crate::control::{ControlRequest, ControlResponse};
+
+pub async fn request(path: &Path, request: &ControlRequest) -> io::Result<ControlResponse> {
+    let stream = UnixStream::connect(path).await?;
+    let (reader, mut writer) = stream.into_split();
+
+    let mut out = serde_json::to_vec(request)?;
+    out.push(b'\n');
+    writer.write_all(&out).await?;
+
+    let mut line = String::new();
+    BufReader::new(reader).read_line(&mut line).await?;
+    Ok(serde_json::from_str(&line)?)
+}
+
+// O servidor não está rodando (ou nunca criou o socket), ou o usuário não
+// tem acesso ao socket: nos dois casos o spool é usado diretamente
+pub fn is_unavailable(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::NotFound
+            | io::ErrorKind::ConnectionRefused
+            | io::ErrorKind::PermissionDenied
+    )
+}
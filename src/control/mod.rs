@@ -0,0 +1,134 @@
+pub mod client;
+pub mod server;
+
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    config::Config,
+    plugins::EmailContext,
+    queue::{
+        manager::{JobDetail, JobFilter, JobSummary, QueueManager},
+        spool::Spool,
+    },
+    recipients::Recipients,
+    smtp_server::pipeline,
+};
+
+// Protocolo do socket de controle: um pedido JSON por linha, respondido com
+// uma linha JSON
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum ControlRequest {
+    List {
+        #[serde(default)]
+        filter: JobFilter,
+    },
+    Show {
+        id: String,
+    },
+    Flush {
+        domain: Option<String>,
+    },
+    Delete {
+        id: String,
+    },
+    // Mensagem já com CRLF, como recebida após o DATA
+    Submit {
+        from: String,
+        recipients: Vec<String>,
+        message: String,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ControlResponse {
+    Ok { data: serde_json::Value },
+    Error { message: String },
+}
+
+impl ControlResponse {
+    fn error(message: impl ToString) -> Self {
+        ControlResponse::Error {
+            message: message.to_string(),
+        }
+    }
+}
+
+// Executa um pedido. O servidor usa no socket; a linha de comando usa direto
+// no spool quando o servidor não está rodando.
+pub async fn handle(
+    config: &Config,
+    recipients: &Arc<Recipients>,
+    spool: Arc<Spool>,
+    request: ControlRequest,
+) -> ControlResponse {
+    let manager = QueueManager::new(spool.clone());
+
+    let data = match request {
+        ControlRequest::List { filter } => manager
+            .list(&filter)
+            .await
+            .map(|jobs| json!(jobs.iter().map(JobSummary::from).collect::<Vec<_>>())),
+        ControlRequest::Show { id } => manager
+            .show(&id)
+            .await
+            .map(|job| json!(JobDetail::from(&job))),
+        ControlRequest::Flush { domain } => manager
+            .flush(domain.as_deref())
+            .await
+            .map(|flushed| json!({ "flushed": flushed })),
+        ControlRequest::Delete { id } => manager
+            .delete(&id)
+            .await
+            .map(|job| json!(JobSummary::from(&job))),
+        ControlRequest::Submit {
+            from,
+            recipients: rcpt_to,
+            message,
+        } => return submit(config, recipients, &spool, from, rcpt_to, &message).await,
+    };
+
+    match data {
+        Ok(data) => ControlResponse::Ok { data },
+        Err(e) => ControlResponse::error(e),
+    }
+}
+
+async fn submit(
+    config: &Config,
+    recipients: &Arc<Recipients>,
+    spool: &Spool,
+    from: String,
+    rcpt_to: Vec<String>,
+    message: &str,
+) -> ControlResponse {
+    if rcpt_to.is_empty() {
+        return ControlResponse::error("nenhum destinatário");
+    }
+
+    let mut ctx = EmailContext::new(&from);
+    ctx.rcpt_to = rcpt_to;
+    let id = ctx.id.clone();
+
+    let source = pipeline::Source {
+        peer: "local",
+        helo: None,
+        esmtp: false,
+        tls: false,
+        submission: false,
+    };
+    let response = pipeline::accept_message(config, recipients, spool, &source, ctx, message).await;
+
+    if response.starts_with('2') {
+        spool.wake();
+        ControlResponse::Ok {
+            data: json!({ "id": id }),
+        }
+    } else {
+        ControlResponse::error(response.trim_end())
+    }
+}
@@ -0,0 +1,120 @@
+use std::{
+    ffi::CString,
+    os::unix::fs::{MetadataExt, PermissionsExt},
+    path::Path,
+    sync::Arc,
+};
+
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{UnixListener, UnixStream},
+};
+
+use crate::{
+    config::{server_config::ServerConfig, shared_config::SharedConfig},
+    control::{self, ControlRequest, ControlResponse},
+    queue::spool::Spool,
+    shutdown::Shutdown,
+};
+
+pub async fn serve(shared_config: Arc<SharedConfig>, spool: Arc<Spool>, mut shutdown: Shutdown) {
+    let server = shared_config.load().server.clone();
+    let path = server.control_socket.clone();
+    let (listener, inode) = match bind(&path, &server) {
+        Ok(bound) => bound,
+        Err(e) => {
+            tracing::error!(
+                "Erro ao abrir o socket de controle {}: {}",
+                path.display(),
+                e
+            );
+            return;
+        }
+    };
+    tracing::info!("Socket de controle em {}", path.display());
+
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => {
+                    let config = shared_config.clone();
+                    let spool = spool.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = handle_connection(stream, &config, spool).await {
+                            tracing::warn!("Erro no socket de controle: {}", e);
+                        }
+                    });
+                }
+                Err(e) => tracing::error!("Erro ao aceitar conexão de controle: {}", e),
+            },
+            _ = shutdown.wait() => break,
+        }
+    }
+
+    // Na troca de processo (SIGUSR2) o sucessor já criou o próprio socket no
+    // mesmo caminho, que não pode ser removido
+    if std::fs::metadata(&path).is_ok_and(|m| m.ino() == inode) {
+        let _ = std::fs::remove_file(&path);
+    }
+}
+
+// Um socket que sobrou de uma execução anterior (ou do processo que está
+// sendo substituído) é removido antes do bind. O acesso é dado pela
+// permissão e pelo grupo configurados.
+fn bind(path: &Path, server: &ServerConfig) -> std::io::Result<(UnixListener, u64)> {
+    if path.exists() {
+        std::fs::remove_file(path)?;
+    }
+
+    let listener = UnixListener::bind(path)?;
+    if !server.control_socket_group.is_empty() {
+        let gid = group_id(&server.control_socket_group)?;
+        std::os::unix::fs::chown(path, None, Some(gid))?;
+    }
+    let mode = server.control_socket_mode().unwrap_or(0o600);
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))?;
+    let inode = std::fs::metadata(path)?.ino();
+
+    Ok((listener, inode))
+}
+
+fn group_id(name: &str) -> std::io::Result<u32> {
+    let not_found = || {
+        std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("grupo {} não existe", name),
+        )
+    };
+    let name = CString::new(name).map_err(|_| not_found())?;
+    // Só na inicialização, antes de outras consultas ao banco de grupos
+    let group = unsafe { libc::getgrnam(name.as_ptr()) };
+    if group.is_null() {
+        return Err(not_found());
+    }
+    Ok(unsafe { (*group).gr_gid })
+}
+
+async fn handle_connection(
+    stream: UnixStream,
+    shared_config: &SharedConfig,
+    spool: Arc<Spool>,
+) -> std::io::Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut line = String::new();
+    BufReader::new(reader).read_line(&mut line).await?;
+
+    let response = match serde_json::from_str::<ControlRequest>(&line) {
+        Ok(request) => {
+            let recipients = shared_config.recipients();
+            control::handle(&shared_config.load(), &recipients, spool, request).await
+        }
+        Err(e) => ControlResponse::Error {
+            message: format!("pedido inválido: {}", e),
+        },
+    };
+
+    let mut out = serde_json::to_vec(&response)?;
+    out.push(b'\n');
+    writer.write_all(&out).await?;
+    writer.shutdown().await
+}
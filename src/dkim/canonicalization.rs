@@ -0,0 +1,87 @@
+// Canonicalização de headers e body (RFC 6376 §3.4)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Canonicalization {
+    Simple,
+    Relaxed,
+}
+
+impl Canonicalization {
+    // Valor de c=: "header/body". Sem a barra, o body usa simple (RFC 6376 §3.5)
+    pub fn parse_pair(value: &str) -> Option<(Self, Self)> {
+        let (header, body) = value.split_once('/').unwrap_or((value, "simple"));
+        Some((Self::parse(header)?, Self::parse(body)?))
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value.trim() {
+            "simple" => Some(Canonicalization::Simple),
+            "relaxed" => Some(Canonicalization::Relaxed),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Canonicalization::Simple => "simple",
+            Canonicalization::Relaxed => "relaxed",
+        }
+    }
+}
+
+// Recebe o campo inteiro, com as linhas de continuação e sem o CRLF final
+pub fn header(field: &str, canonicalization: Canonicalization) -> String {
+    match canonicalization {
+        Canonicalization::Simple => format!("{}\r\n", field),
+        Canonicalization::Relaxed => {
+            let (name, value) = field.split_once(':').unwrap_or((field, ""));
+            let value = collapse_whitespace(&value.replace("\r\n", ""));
+            format!(
+                "{}:{}\r\n",
+                name.trim_end_matches([' ', '\t']).to_ascii_lowercase(),
+                value.trim_matches(' ')
+            )
+        }
+    }
+}
+
+pub fn body(body: &str, canonicalization: Canonicalization) -> String {
+    let mut lines: Vec<String> = body
+        .split('\n')
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+        .map(|line| match canonicalization {
+            Canonicalization::Simple => line.to_string(),
+            Canonicalization::Relaxed => {
+                collapse_whitespace(line).trim_end_matches(' ').to_string()
+            }
+        })
+        .collect();
+
+    // Linhas vazias no fim não fazem parte do body canônico
+    while lines.last().is_some_and(|line| line.is_empty()) {
+        lines.pop();
+    }
+
+    // Body vazio: CRLF no simple, nada no relaxed (RFC 6376 §3.4.3 e §3.4.4)
+    if lines.is_empty() && canonicalization == Canonicalization::Simple {
+        return "\r\n".to_string();
+    }
+
+    lines.iter().map(|line| format!("{}\r\n", line)).collect()
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut in_space = false;
+    for c in text.chars() {
+        if c == ' ' || c == '\t' {
+            if !in_space {
+                out.push(' ');
+            }
+            in_space = true;
+        } else {
+            out.push(c);
+            in_space = false;
+        }
+    }
+    out
+}
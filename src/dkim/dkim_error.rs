@@ -4,10 +4,13 @@ use std::{error::Error, fmt};
 pub enum DkimError {
     IoError(std::io::Error),
     UnknownAlgorithmError(),
-    Utf8Error(std::str::Utf8Error),
+    UnknownCanonicalizationError(String),
     PrivateKeyError(rsa::pkcs8::Error),
+    // Também usado pelo ed25519-dalek, inclusive para chaves inválidas
     SignatureError(rsa::signature::Error),
     Base64DecodeError(base64::DecodeError),
+    // Erro ao carregar uma chave do keyring, com o domínio e o seletor
+    KeyError(String, Box<DkimError>),
 }
 
 impl fmt::Display for DkimError {
@@ -15,10 +18,13 @@ impl fmt::Display for DkimError {
         match self {
             DkimError::IoError(e) => write!(f, "Erro de I/O: {}", e),
             DkimError::UnknownAlgorithmError() => write!(f, "Algoritmo desconhecido"),
-            DkimError::Utf8Error(e) => write!(f, "Erro de UTF8: {}", e),
+            DkimError::UnknownCanonicalizationError(c) => {
+                write!(f, "Canonicalização desconhecida: {}", c)
+            }
             DkimError::PrivateKeyError(e) => write!(f, "Erro na chave privada: {}", e),
-            DkimError::SignatureError(e) => write!(f, "Erro ao gerar chave de assinatura: {}", e),
+            DkimError::SignatureError(e) => write!(f, "Erro ao gerar assinatura: {}", e),
             DkimError::Base64DecodeError(e) => write!(f, "Erro ao decodificar base64: {}", e),
+            DkimError::KeyError(key, e) => write!(f, "{}: {}", key, e),
         }
     }
 }
@@ -32,12 +38,6 @@ impl From<std::io::Error> for DkimError {
     }
 }
 
-impl From<std::str::Utf8Error> for DkimError {
-    fn from(err: std::str::Utf8Error) -> Self {
-        DkimError::Utf8Error(err)
-    }
-}
-
 impl From<rsa::pkcs8::Error> for DkimError {
     fn from(err: rsa::pkcs8::Error) -> Self {
         DkimError::PrivateKeyError(err)
@@ -0,0 +1,65 @@
+use base64::{Engine, engine::general_purpose::STANDARD as B64};
+use rsa::pkcs1v15::SigningKey;
+use sha2::{Digest, Sha256};
+
+use crate::{config::dkim_config::DkimKeyConfig, dkim::dkim_error::DkimError};
+
+// Chave privada já decodificada, lida uma vez ao carregar a configuração
+pub enum PrivateKey {
+    Rsa(SigningKey<Sha256>),
+    Ed25519(ed25519_dalek::SigningKey),
+}
+
+impl PrivateKey {
+    pub fn load(config: &DkimKeyConfig) -> Result<Self, DkimError> {
+        let pem = match &config.private_key {
+            Some(pem) => pem.clone(),
+            None => std::fs::read_to_string(&config.private_key_path)?,
+        };
+
+        match config.algorithm.as_str() {
+            "rsa-sha256" => {
+                use rsa::{RsaPrivateKey, pkcs8::DecodePrivateKey};
+                let key = RsaPrivateKey::from_pkcs8_pem(&pem)?;
+                Ok(PrivateKey::Rsa(SigningKey::new(key)))
+            }
+            "ed25519-sha256" => {
+                use ed25519_dalek::pkcs8::DecodePrivateKey;
+
+                // PKCS#8 (gerado por `dkim keygen` e pelo openssl) ou a chave crua
+                if let Ok(key) = ed25519_dalek::SigningKey::from_pkcs8_pem(&pem) {
+                    return Ok(PrivateKey::Ed25519(key));
+                }
+                let b64_body: String = pem.lines().filter(|l| !l.starts_with("----")).collect();
+                let key_bytes = B64.decode(b64_body.trim())?;
+                let key = ed25519_dalek::SigningKey::try_from(key_bytes.as_slice())?;
+                Ok(PrivateKey::Ed25519(key))
+            }
+            _ => Err(DkimError::UnknownAlgorithmError()),
+        }
+    }
+
+    pub fn algorithm(&self) -> &'static str {
+        match self {
+            PrivateKey::Rsa(_) => "rsa-sha256",
+            PrivateKey::Ed25519(_) => "ed25519-sha256",
+        }
+    }
+
+    pub fn sign(&self, data: &[u8]) -> Result<Vec<u8>, DkimError> {
+        match self {
+            PrivateKey::Rsa(key) => {
+                use rsa::signature::{SignatureEncoding, Signer};
+
+                // PKCS#1 v1.5 sobre o SHA-256 dos dados
+                Ok(key.try_sign(data)?.to_vec())
+            }
+            PrivateKey::Ed25519(key) => {
+                use ed25519_dalek::Signer;
+
+                // RFC 8463: o Ed25519 assina o hash SHA-256, não os dados
+                Ok(key.sign(&Sha256::digest(data)).to_bytes().to_vec())
+            }
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use anyhow::Result;
+use base64::{Engine, engine::general_purpose::STANDARD as B64};
+
+use crate::dkim::{
+    dkim_error::DkimError,
+    key::PrivateKey,
+    verifier::{self, DkimVerifier, Failure},
+};
+
+// Tamanho máximo de cada string de um registro TXT (RFC 1035 §3.3)
+const TXT_STRING_MAX: usize = 255;
+// Dados assinados com a chave privada e verificados com a chave publicada
+const CHECK_DATA: &[u8] = b"dkim check";
+
+pub struct GeneratedKey {
+    // PKCS#8 em PEM, no formato que o DkimSigner lê
+    pub private_key_pem: String,
+    // Conteúdo do registro TXT em <selector>._domainkey.<domínio>
+    pub dns_record: String,
+}
+
+pub fn generate(algorithm: &str, bits: usize) -> Result<GeneratedKey> {
+    match algorithm {
+        "rsa-sha256" => generate_rsa(bits),
+        "ed25519-sha256" => generate_ed25519(),
+        other => anyhow::bail!("Algoritmo DKIM desconhecido: {}", other),
+    }
+}
+
+fn generate_rsa(bits: usize) -> Result<GeneratedKey> {
+    use rsa::{
+        RsaPrivateKey,
+        pkcs8::{EncodePrivateKey, LineEnding},
+    };
+
+    // RFC 8301 pede pelo menos 1024 bits, mas 1024 já não é seguro
+    if bits != 2048 && bits != 4096 {
+        anyhow::bail!("Chaves RSA precisam ter 2048 ou 4096 bits");
+    }
+
+    let mut rng = rand::thread_rng();
+    let key = RsaPrivateKey::new(&mut rng, bits)?;
+    let private_key_pem = key.to_pkcs8_pem(LineEnding::LF)?.to_string();
+
+    Ok(GeneratedKey {
+        private_key_pem,
+        dns_record: dns_record(&PrivateKey::Rsa(rsa::pkcs1v15::SigningKey::new(key)))?,
+    })
+}
+
+fn generate_ed25519() -> Result<GeneratedKey> {
+    use ed25519_dalek::{
+        SigningKey,
+        pkcs8::{EncodePrivateKey, spki::der::pem::LineEnding},
+    };
+
+    let key = SigningKey::generate(&mut rand::rngs::OsRng);
+    let private_key_pem = key.to_pkcs8_pem(LineEnding::LF)?.to_string();
+
+    Ok(GeneratedKey {
+        private_key_pem,
+        dns_record: dns_record(&PrivateKey::Ed25519(key))?,
+    })
+}
+
+// Registro TXT da chave pública: SubjectPublicKeyInfo para RSA e, no
+// Ed25519, a chave crua de 32 bytes (RFC 8463)
+pub fn dns_record(key: &PrivateKey) -> Result<String, DkimError> {
+    let (key_type, public_key) = match key {
+        PrivateKey::Rsa(key) => {
+            use rsa::{RsaPublicKey, pkcs8::EncodePublicKey};
+            let public = RsaPublicKey::from(key.as_ref())
+                .to_public_key_der()
+                .map_err(rsa::pkcs8::Error::PublicKey)?;
+            ("rsa", public.as_bytes().to_vec())
+        }
+        PrivateKey::Ed25519(key) => ("ed25519", key.verifying_key().to_bytes().to_vec()),
+    };
+    Ok(format!(
+        "v=DKIM1; k={}; p={}",
+        key_type,
+        B64.encode(public_key)
+    ))
+}
+
+// Linha de zona com o registro dividido em strings de até 255 bytes. Os
+// servidores concatenam as strings na consulta (RFC 6376 §3.6.2.2).
+pub fn zone_entry(selector: &str, domain: &str, record: &str) -> String {
+    let strings: Vec<String> = record
+        .as_bytes()
+        .chunks(TXT_STRING_MAX)
+        .map(|chunk| format!("\"{}\"", String::from_utf8_lossy(chunk)))
+        .collect();
+
+    let name = format!("{}._domainkey.{}.", selector, domain.trim_end_matches('.'));
+    if strings.len() == 1 {
+        format!("{} IN TXT {}", name, strings[0])
+    } else {
+        format!("{} IN TXT ( {} )", name, strings.join(" "))
+    }
+}
+
+// Confere se o registro publicado verifica uma assinatura feita com a chave
+// privada. O erro é o motivo, para mostrar ao usuário.
+pub async fn check_record(
+    verifier: &DkimVerifier,
+    selector: &str,
+    domain: &str,
+    key: &PrivateKey,
+) -> Result<(), String> {
+    let published = verifier
+        .fetch_key(selector, &domain.to_ascii_lowercase())
+        .await
+        .map_err(|Failure(_, reason)| reason)?;
+
+    let key_type = key.algorithm().trim_end_matches("-sha256");
+    if !published.key_type.eq_ignore_ascii_case(key_type) {
+        return Err(format!("registro publicado é k={}", published.key_type));
+    }
+
+    let signature = key.sign(CHECK_DATA).map_err(|e| e.to_string())?;
+    match verifier::verify_data(key_type, &published.public_key, CHECK_DATA, &signature) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err("chave publicada não corresponde à chave privada".to_string()),
+        Err(Failure(_, reason)) => Err(reason),
+    }
+}
@@ -0,0 +1,71 @@
+// Um campo de header como aparece na mensagem, incluindo as linhas de
+// continuação e sem o CRLF final
+pub struct HeaderField<'a> {
+    pub name: &'a str,
+    pub raw: &'a str,
+}
+
+// Headers (com o CRLF da última linha) e body, sem a linha em branco entre eles
+pub fn split(raw: &str) -> (&str, &str) {
+    if let Some(pos) = raw.find("\r\n\r\n") {
+        (&raw[..pos + 2], &raw[pos + 4..])
+    } else if let Some(pos) = raw.find("\n\n") {
+        (&raw[..pos + 1], &raw[pos + 2..])
+    } else {
+        (raw, "")
+    }
+}
+
+pub fn header_fields(headers: &str) -> Vec<HeaderField<'_>> {
+    let mut fields = Vec::new();
+    let mut start: Option<usize> = None;
+    let mut offset = 0;
+
+    for line in headers.split_inclusive('\n') {
+        let is_continuation = line.starts_with([' ', '\t']);
+        if !is_continuation {
+            if let Some(begin) = start.take() {
+                fields.push(field(&headers[begin..offset]));
+            }
+            if !line.trim_end_matches(['\r', '\n']).is_empty() {
+                start = Some(offset);
+            }
+        }
+        offset += line.len();
+    }
+    if let Some(begin) = start {
+        fields.push(field(&headers[begin..offset]));
+    }
+
+    fields
+}
+
+fn field(text: &str) -> HeaderField<'_> {
+    let raw = text.trim_end_matches(['\r', '\n']);
+    let name = raw.split_once(':').map(|(name, _)| name).unwrap_or(raw);
+    HeaderField {
+        name: name.trim_end_matches([' ', '\t']),
+        raw,
+    }
+}
+
+// Para cada nome de h=, usa a última ocorrência ainda não usada
+// (RFC 6376 §5.4.2). Nomes sem ocorrência não contribuem para o hash.
+pub fn select<'a, 'f>(fields: &'a [HeaderField<'f>], names: &[String]) -> Vec<&'a HeaderField<'f>> {
+    let mut used = vec![false; fields.len()];
+    let mut selected = Vec::new();
+
+    for name in names {
+        let found = fields
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(i, f)| !used[*i] && f.name.eq_ignore_ascii_case(name));
+        if let Some((i, field)) = found {
+            used[i] = true;
+            selected.push(field);
+        }
+    }
+
+    selected
+}
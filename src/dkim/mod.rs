@@ -1,144 +1,405 @@
-mod dkim_error;
+pub mod canonicalization;
+pub mod dkim_error;
+pub mod key;
+pub mod keygen;
+pub mod message;
+pub mod rotation;
+pub mod verifier;
 
-use crate::config::dkim_config::DkimConfig;
-use anyhow::Result;
+#[cfg(test)]
+mod tests;
+
+use crate::{config::dkim_config::DkimConfig, helpers::email_helper};
 use base64::{Engine, engine::general_purpose::STANDARD as B64};
-use rsa::pkcs1v15::SigningKey;
+use chrono::{DateTime, Utc};
 use sha2::{Digest, Sha256};
-use std::str::from_utf8;
+
+use canonicalization::Canonicalization;
+use dkim_error::DkimError;
+use key::PrivateKey;
+
+// Usada quando `headers` está vazio (RFC 6376 §5.4.1)
+pub const DEFAULT_HEADERS: [&str; 16] = [
+    "from",
+    "reply-to",
+    "subject",
+    "date",
+    "to",
+    "cc",
+    "message-id",
+    "in-reply-to",
+    "references",
+    "mime-version",
+    "content-type",
+    "content-transfer-encoding",
+    "content-id",
+    "content-description",
+    "list-id",
+    "list-unsubscribe",
+];
+// Comprimento máximo das linhas do header gerado (RFC 5322 recomenda 78)
+const MAX_LINE_LEN: usize = 76;
 
 pub struct DkimSigner {
+    keys: Vec<SigningKey>,
+    headers_to_sign: Vec<String>,
+    header_canonicalization: Canonicalization,
+    body_canonicalization: Canonicalization,
+    oversign: bool,
+    timestamp: bool,
+    expiration_secs: Option<u64>,
+    identity: Option<String>,
+    body_length: bool,
+}
+
+// Uma entrada do keyring, com a chave já carregada
+struct SigningKey {
     domain: String,
     selector: String,
-    algorithm: Algorithm,
-    private_key_pem: Vec<u8>,
-    headers_to_sign: Vec<String>,
+    key: PrivateKey,
+    activate_at: Option<DateTime<Utc>>,
+    retire_at: Option<DateTime<Utc>>,
 }
 
-enum Algorithm {
-    RsaSha256,
-    Ed25519Sha256,
+impl SigningKey {
+    fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.activate_at.is_none_or(|t| t <= now) && self.retire_at.is_none_or(|t| now < t)
+    }
 }
 
 impl DkimSigner {
-    pub fn from_config(config: &DkimConfig) -> Result<Self> {
-        let pem = std::fs::read(&config.private_key_path)?;
+    pub fn from_config(config: &DkimConfig) -> Result<Self, DkimError> {
+        let mut keys = Vec::new();
+        for (prefix, entry) in config.key_entries() {
+            let key = PrivateKey::load(&entry).map_err(|e| {
+                DkimError::KeyError(
+                    format!("{} ({}/{})", prefix, entry.domain, entry.selector),
+                    Box::new(e),
+                )
+            })?;
+            keys.push(SigningKey {
+                domain: entry.domain.to_ascii_lowercase(),
+                activate_at: entry.activation(),
+                retire_at: entry.retirement(),
+                selector: entry.selector,
+                key,
+            });
+        }
+
+        let Some((header_canonicalization, body_canonicalization)) =
+            Canonicalization::parse_pair(&config.canonicalization)
+        else {
+            return Err(DkimError::UnknownCanonicalizationError(
+                config.canonicalization.clone(),
+            ));
+        };
 
-        let algorithm = match config.alogrithm.as_str() {
-            "rsa-sha256" => Algorithm::RsaSha256,
-            "ed25519-sha256" => Algorithm::Ed25519Sha256,
-            other => anyhow::bail!("Algoritmo DKIM desconhecido: {}", other),
+        let headers_to_sign = if config.headers.is_empty() {
+            DEFAULT_HEADERS.iter().map(|h| h.to_string()).collect()
+        } else {
+            config
+                .headers
+                .iter()
+                .map(|h| h.to_ascii_lowercase())
+                .collect()
         };
 
         Ok(Self {
-            domain: config.domain.clone(),
-            selector: config.selector.clone(),
-            algorithm,
-            private_key_pem: pem,
-            headers_to_sign: config.headers.clone(),
+            keys,
+            headers_to_sign,
+            header_canonicalization,
+            body_canonicalization,
+            oversign: config.oversign,
+            timestamp: config.timestamp || config.expiration_secs.is_some(),
+            expiration_secs: config.expiration_secs,
+            identity: config.identity.clone(),
+            body_length: config.body_length,
         })
     }
 
-    pub fn sign(&self, raw_message: &str) -> Result<String> {
-        let (headers_str, body) = split_message(raw_message);
+    // Retorna um header DKIM-Signature (sem o CRLF final) para cada chave do
+    // domínio do From. Sem chave para ele, tenta o domínio do usuário
+    // autenticado; sem nenhuma das duas, a mensagem segue sem assinatura.
+    pub fn sign(
+        &self,
+        raw_message: &str,
+        auth_domain: Option<&str>,
+    ) -> Result<Vec<String>, DkimError> {
+        let (headers, body) = message::split(raw_message);
+        let fields = message::header_fields(headers);
 
-        let body_hash = {
-            let canonical_body = relaxed_body(body);
-            let mut h = Sha256::new();
-            h.update(canonical_body.as_bytes());
-            B64.encode(h.finalize())
+        let from_domain = fields
+            .iter()
+            .find(|f| f.name.eq_ignore_ascii_case("from"))
+            .and_then(|f| f.raw.split_once(':'))
+            .and_then(|(_, value)| email_helper::parse_address_list(value).into_iter().next())
+            .and_then(|addr| addr.rsplit_once('@').map(|(_, d)| d.to_string()));
+
+        let mut keys = from_domain
+            .as_deref()
+            .map(|d| self.keys_for(d))
+            .unwrap_or_default();
+        if keys.is_empty() {
+            keys = auth_domain.map(|d| self.keys_for(d)).unwrap_or_default();
+        }
+        if keys.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let canonical_body = canonicalization::body(body, self.body_canonicalization);
+        let body_hash = B64.encode(Sha256::digest(canonical_body.as_bytes()));
+        let signed_headers = self.signed_headers(&fields);
+        let now = chrono::Utc::now().timestamp();
+
+        let data_to_sign = self.canonical_headers(&fields, &signed_headers);
+
+        let mut signatures = Vec::new();
+        for key in keys {
+            let mut optional = Vec::new();
+            if let Some(identity) = self
+                .identity
+                .as_deref()
+                .filter(|i| identity_matches(i, &key.domain))
+            {
+                optional.push(format!("i={};", identity));
+            }
+            if self.timestamp {
+                optional.push(format!("t={};", now));
+                if let Some(secs) = self.expiration_secs {
+                    optional.push(format!("x={};", now + secs as i64));
+                }
+            }
+            if self.body_length {
+                optional.push(format!("l={};", canonical_body.len()));
+            }
+
+            signatures.push(self.signature(
+                "DKIM-Signature: v=1;",
+                key,
+                &optional,
+                &signed_headers,
+                &body_hash,
+                &data_to_sign,
+            )?);
+        }
+
+        Ok(signatures)
+    }
+
+    // ARC-Message-Signature (RFC 8617 §4.1.2): uma DKIM-Signature com a
+    // instância em i= no lugar de v=, que não cobre os headers ARC. Usa a
+    // primeira chave do domínio; None quando não há chave para ele.
+    pub fn arc_message_signature(
+        &self,
+        raw_message: &str,
+        domain: &str,
+        instance: usize,
+    ) -> Result<Option<String>, DkimError> {
+        let Some(key) = self.keys_for(domain).into_iter().next() else {
+            return Ok(None);
         };
 
-        // Monta hedaer
-        let dkim_header_partial = format!(
-            "DKIM-Signature: v=1; a={}; c=relaxed/relaxed; d={}; s={};\r\n\th={};\r\n\tbh={};\r\r\ttb=",
-            self.algorithm_string(),
-            self.domain,
-            self.selector,
-            self.headers_to_sign.join(":"),
-            body_hash
+        let (headers, body) = message::split(raw_message);
+        let fields = message::header_fields(headers);
+        let signed_headers: Vec<String> = self
+            .signed_headers(&fields)
+            .into_iter()
+            .filter(|name| !name.starts_with("arc-"))
+            .collect();
+
+        let canonical_body = canonicalization::body(body, self.body_canonicalization);
+        let body_hash = B64.encode(Sha256::digest(canonical_body.as_bytes()));
+        let data_to_sign = self.canonical_headers(&fields, &signed_headers);
+        let optional = [format!("t={};", chrono::Utc::now().timestamp())];
+
+        self.signature(
+            &format!("ARC-Message-Signature: i={};", instance),
+            key,
+            &optional,
+            &signed_headers,
+            &body_hash,
+            &data_to_sign,
+        )
+        .map(Some)
+    }
+
+    // ARC-Seal (RFC 8617 §4.1.3). `sets` são os conjuntos ARC cobertos, já
+    // canonicalizados com relaxed; o selo não tem hash do body.
+    pub fn arc_seal(
+        &self,
+        domain: &str,
+        instance: usize,
+        cv: &str,
+        sets: &str,
+    ) -> Result<Option<String>, DkimError> {
+        let Some(key) = self.keys_for(domain).into_iter().next() else {
+            return Ok(None);
+        };
+
+        let mut header = format!(
+            "ARC-Seal: i={}; a={}; cv={}; d={}; s={};\r\n\tt={}; b=",
+            instance,
+            key.key.algorithm(),
+            cv,
+            key.domain,
+            key.selector,
+            chrono::Utc::now().timestamp()
         );
 
-        let signing_heders = collect_headers(&headers_str, &self.headers_to_sign);
-        let data_to_sign = format!("{}{}", signing_heders, dkim_header_partial);
+        let canonical = canonicalization::header(&header, Canonicalization::Relaxed);
+        let mut data = sets.to_string();
+        data.push_str(canonical.trim_end_matches("\r\n"));
 
-        let signature = self.compute_signature(data_to_sign.as_bytes())?;
+        let signature = B64.encode(key.key.sign(data.as_bytes())?);
+        header.push_str(&fold_value(&signature));
+        Ok(Some(header))
+    }
 
-        Ok(format!("{}{}", dkim_header_partial, signature))
+    // Monta o header com b= vazio, assina e acrescenta o valor de b=.
+    // `prefix` é o nome do header com a primeira tag.
+    fn signature(
+        &self,
+        prefix: &str,
+        key: &SigningKey,
+        optional: &[String],
+        signed_headers: &[String],
+        body_hash: &str,
+        data_to_sign: &str,
+    ) -> Result<String, DkimError> {
+        let mut header = format!(
+            "{} a={}; c={}/{}; d={}; s={};",
+            prefix,
+            key.key.algorithm(),
+            self.header_canonicalization.as_str(),
+            self.body_canonicalization.as_str(),
+            key.domain,
+            key.selector,
+        );
+        if !optional.is_empty() {
+            header.push_str(&format!("\r\n\t{}", optional.join(" ")));
+        }
+
+        header.push_str(&format!(
+            "\r\n\th={};\r\n\tbh={};\r\n\tb=",
+            fold_list(signed_headers),
+            body_hash
+        ));
+
+        let canonical = canonicalization::header(&header, self.header_canonicalization);
+        let mut data = data_to_sign.to_string();
+        data.push_str(canonical.trim_end_matches("\r\n"));
+
+        let signature = B64.encode(key.key.sign(data.as_bytes())?);
+        header.push_str(&fold_value(&signature));
+        Ok(header)
     }
 
-    fn algorithm_string(&self) -> &str {
-        match self.algorithm {
-            Algorithm::RsaSha256 => "rsa-sha256",
-            Algorithm::Ed25519Sha256 => "ed25519-sha256",
+    fn canonical_headers(&self, fields: &[message::HeaderField], names: &[String]) -> String {
+        let mut data = String::new();
+        for field in message::select(fields, names) {
+            data.push_str(&canonicalization::header(
+                field.raw,
+                self.header_canonicalization,
+            ));
         }
+        data
     }
 
-    fn compute_signature(&self, data: &[u8]) -> Result<String> {
-        match self.algorithm {
-            Algorithm::RsaSha256 => {
-                use rsa::{RsaPrivateKey, pkcs8::DecodePrivateKey, signature::SignatureEncoding};
-                let key = RsaPrivateKey::from_pkcs8_pem(from_utf8(&self.private_key_pem)?)?;
-
-                let signing_key = SigningKey::<Sha256>::new(key);
-                use rsa::signature::RandomizedSigner;
-                let mut rng = rand::thread_rng();
-                let sig = signing_key.sign_with_rng(&mut rng, data);
-                Ok(B64.encode(sig.to_bytes()))
-            }
-            Algorithm::Ed25519Sha256 => {
-                use ed25519_dalek::{Signer, SigningKey};
+    // Chaves do domínio mais específico que cobre `domain` (o próprio domínio
+    // ou um domínio pai). Todas as chaves desse domínio assinam.
+    fn keys_for(&self, domain: &str) -> Vec<&SigningKey> {
+        let domain = domain.to_ascii_lowercase();
+        let now = Utc::now();
+        let active: Vec<&SigningKey> = self.keys.iter().filter(|k| k.is_active(now)).collect();
+        let Some(best) = active
+            .iter()
+            .filter(|k| within_domain(&domain, &k.domain))
+            .map(|k| k.domain.as_str())
+            .max_by_key(|d| d.len())
+        else {
+            return Vec::new();
+        };
+
+        // Na rotação, a chave ativada por último substitui as anteriores do
+        // mesmo algoritmo; chaves sem activate_at convivem entre si
+        active
+            .iter()
+            .filter(|k| k.domain == best)
+            .filter(|k| {
+                !active.iter().any(|other| {
+                    other.domain == k.domain
+                        && other.key.algorithm() == k.key.algorithm()
+                        && other.activate_at > k.activate_at
+                })
+            })
+            .copied()
+            .collect()
+    }
 
-                let pem_str = from_utf8(&self.private_key_pem)?;
-                let b64_body: String = pem_str.lines().filter(|l| !l.starts_with("----")).collect();
+    // Lista de h=. Com oversigning, cada nome aparece uma vez a mais do que na
+    // mensagem; a entrada extra não corresponde a nenhum header e impede que
+    // um novo seja acrescentado sem invalidar a assinatura (RFC 6376 §8.15).
+    fn signed_headers(&self, fields: &[message::HeaderField]) -> Vec<String> {
+        if !self.oversign {
+            return self.headers_to_sign.clone();
+        }
 
-                let key_bytes = B64.decode(b64_body.trim())?;
-                let signing_key = SigningKey::try_from(key_bytes.as_slice())?;
-                let signature = signing_key.sign(data);
-                Ok(B64.encode(signature.to_bytes()))
+        let mut names: Vec<String> = Vec::new();
+        for name in &self.headers_to_sign {
+            if names.contains(name) {
+                continue;
             }
+            let count = fields
+                .iter()
+                .filter(|f| f.name.eq_ignore_ascii_case(name))
+                .count();
+            names.extend(std::iter::repeat_n(name.clone(), count + 1));
         }
+        names
     }
 }
 
-fn split_message(raw: &str) -> (&str, &str) {
-    if let Some(pos) = raw.find("\r\n\r\n") {
-        (&raw[..pos], &raw[pos + 4..])
-    } else if let Some(pos) = raw.find("\n\n") {
-        (&raw[..pos], &raw[pos + 2..])
-    } else {
-        (raw, "")
+// h= dividido em várias linhas quando é longo
+fn fold_list(names: &[String]) -> String {
+    let mut out = String::new();
+    let mut line_len = "\th=".len();
+    for (i, name) in names.iter().enumerate() {
+        if i > 0 {
+            out.push(':');
+            line_len += 1;
+            if line_len + name.len() > MAX_LINE_LEN {
+                out.push_str("\r\n\t");
+                line_len = 1;
+            }
+        }
+        out.push_str(name);
+        line_len += name.len();
     }
+    out
 }
 
-fn relaxed_body(body: &str) -> String {
-    let mut lines: Vec<String> = body
-        .lines()
-        .map(|l| {
-            let collapsed: String = l.split_whitespace().collect::<Vec<_>>().join(" ");
-            collapsed
-        })
-        .collect();
-
-    while lines.last().map(|l: &String| l.is_empty()).unwrap_or(false) {
-        lines.pop();
+// Valores base64 longos (b=) quebrados em linhas de até MAX_LINE_LEN
+fn fold_value(value: &str) -> String {
+    let first = MAX_LINE_LEN - "\tb=".len();
+    if value.len() <= first {
+        return value.to_string();
     }
 
-    lines.join("\r\n") + "\r\n"
+    let mut out = value[..first].to_string();
+    for chunk in value.as_bytes()[first..].chunks(MAX_LINE_LEN - 1) {
+        out.push_str("\r\n\t");
+        out.push_str(std::str::from_utf8(chunk).expect("base64 é ASCII"));
+    }
+    out
 }
 
-fn collect_headers(headers_section: &str, to_sign: &[String]) -> String {
-    let mut result = String::new();
-    for h in to_sign {
-        let lower = h.to_lowercase();
-        for line in headers_section.lines() {
-            if line.to_lowercase().starts_with(&format!("{}:", lower)) {
-                result.push_str(line);
-                result.push_str("\r\n");
-                break;
-            }
-        }
-    }
+// i= precisa estar no domínio de d= ou em um subdomínio (RFC 6376 §3.5)
+fn identity_matches(identity: &str, domain: &str) -> bool {
+    identity
+        .rsplit_once('@')
+        .is_some_and(|(_, d)| within_domain(&d.to_ascii_lowercase(), domain))
+}
 
-    result
+fn within_domain(domain: &str, parent: &str) -> bool {
+    domain == parent || domain.ends_with(&format!(".{}", parent))
 }
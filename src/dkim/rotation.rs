@@ -0,0 +1,145 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt,
+    sync::Arc,
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    config::{Config, dkim_config::DkimKeyConfig, shared_config::SharedConfig},
+    dkim::{key::PrivateKey, keygen, verifier::DkimVerifier},
+    dns,
+    shutdown::Shutdown,
+};
+
+// Intervalo entre as verificações das chaves com datas de rotação
+const CHECK_INTERVAL: Duration = Duration::from_secs(3600);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyState {
+    // Antes de activate_at: o registro já deve estar publicado
+    Pending,
+    Active,
+    // Depois de retire_at, dentro de retire_grace_secs
+    Retired,
+    // Fora da carência: o registro pode ser removido
+    Expired,
+}
+
+impl fmt::Display for KeyState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyState::Pending => write!(f, "pendente"),
+            KeyState::Active => write!(f, "ativa"),
+            KeyState::Retired => write!(f, "aposentada"),
+            KeyState::Expired => write!(f, "expirada"),
+        }
+    }
+}
+
+pub fn state(key: &DkimKeyConfig, grace_secs: u64, now: DateTime<Utc>) -> KeyState {
+    if key.activation().is_some_and(|t| now < t) {
+        return KeyState::Pending;
+    }
+    match key.retirement() {
+        Some(t) if now < t => KeyState::Active,
+        Some(t) if now < t + chrono::Duration::seconds(grace_secs as i64) => KeyState::Retired,
+        Some(_) => KeyState::Expired,
+        None => KeyState::Active,
+    }
+}
+
+// Acompanha as chaves com activate_at ou retire_at: registra as mudanças de
+// estado e avisa quando a próxima chave ainda não está no DNS
+pub async fn run(shared_config: Arc<SharedConfig>, mut shutdown: Shutdown) {
+    let mut states = HashMap::new();
+    loop {
+        let config = shared_config.load();
+        if config.dkim.enabled || config.arc.seal {
+            check(&config, &mut states).await;
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(CHECK_INTERVAL) => {}
+            _ = shutdown.wait() => break,
+        }
+    }
+}
+
+async fn check(config: &Config, states: &mut HashMap<String, KeyState>) {
+    let dkim = &config.dkim;
+    let now = Utc::now();
+    let entries: Vec<_> = dkim
+        .key_entries()
+        .into_iter()
+        .map(|(_, key)| key)
+        .filter(|key| key.activate_at.is_some() || key.retire_at.is_some())
+        .collect();
+    if entries.is_empty() {
+        return;
+    }
+
+    let verifier = match dns::resolver() {
+        Ok(resolver) => DkimVerifier::new(resolver),
+        Err(e) => {
+            tracing::warn!("Verificação da rotação DKIM adiada: {}", e);
+            return;
+        }
+    };
+
+    for key in &entries {
+        let name = format!("{}._domainkey.{}", key.selector, key.domain);
+        let state = state(key, dkim.retire_grace_secs, now);
+        let previous = states.insert(name.clone(), state);
+
+        if previous.is_some_and(|previous| previous != state) {
+            match state {
+                KeyState::Active => tracing::info!("Chave DKIM {} ativada", name),
+                KeyState::Retired => tracing::info!(
+                    "Chave DKIM {} aposentada; mantenha o registro DNS por {}s",
+                    name,
+                    dkim.retire_grace_secs
+                ),
+                KeyState::Expired => {
+                    tracing::info!("Registro DNS de {} já pode ser removido", name)
+                }
+                KeyState::Pending => {}
+            }
+        }
+
+        if state == KeyState::Pending {
+            let result = match PrivateKey::load(key) {
+                Ok(private_key) => {
+                    keygen::check_record(&verifier, &key.selector, &key.domain, &private_key).await
+                }
+                Err(e) => Err(e.to_string()),
+            };
+            if let Err(reason) = result {
+                tracing::warn!(
+                    "Registro DNS de {} ainda não publicado (ativação em {}): {}",
+                    name,
+                    key.activate_at.as_deref().unwrap_or_default(),
+                    reason
+                );
+            }
+        }
+    }
+
+    // Um domínio sem nenhuma chave ativa deixa de ser assinado
+    let domains: BTreeSet<String> = entries
+        .iter()
+        .map(|key| key.domain.to_ascii_lowercase())
+        .collect();
+    let all = dkim.key_entries();
+    for domain in domains {
+        let any_active = all.iter().any(|(_, key)| {
+            key.domain.eq_ignore_ascii_case(&domain)
+                && state(key, dkim.retire_grace_secs, now) == KeyState::Active
+        });
+        if !any_active {
+            tracing::warn!("Nenhuma chave DKIM ativa para {}", domain);
+        }
+    }
+}
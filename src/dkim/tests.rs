@@ -0,0 +1,264 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use base64::{Engine, engine::general_purpose::STANDARD as B64};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    config::dkim_config::{DkimConfig, DkimKeyConfig},
+    dkim::{
+        DkimSigner,
+        canonicalization::{self, Canonicalization},
+        key::PrivateKey,
+        keygen, message,
+        verifier::{self, DkimResult, DkimVerifier},
+    },
+    dns::static_resolver::StaticResolver,
+};
+
+// RFC 8463, Apêndice A
+const RFC8463_ED25519_KEY: &str = "nWGxne/9WmC6hEr0kuwsxERJxWl7MmkZcDusAxyuf2A=";
+const RFC8463_ED25519_RECORD: &str =
+    "v=DKIM1; k=ed25519; p=11qYAYKxCrfVS/7TyWQHOg7hcvPapiMlrwIaaPcHURo=";
+const RFC8463_RSA_RECORD: &str = concat!(
+    "v=DKIM1; k=rsa; p=MIGfMA0GCSqGSIb3DQEBAQUAA4GNADCBiQKBgQDkHlOQoBTzWRiGs5V6NpP3id",
+    "Y6Wk08a5qhdR6wy5bdOKb2jLQiY/J16JYi0Qvx/byYzCNb3W91y3FutACDfzwQ/BC/e/8uBsCR+yz1Lxj",
+    "+PL6lHvqMKrM3rG4hstT5QjvHO9PzoxZyVYLzBfO2EeC3Ip3G+2kryOTIKT+l/K4w3QIDAQAB"
+);
+const RFC8463_MESSAGE: &str = concat!(
+    "DKIM-Signature: v=1; a=ed25519-sha256; c=relaxed/relaxed;\r\n",
+    " d=football.example.com; i=@football.example.com;\r\n",
+    " q=dns/txt; s=brisbane; t=1528637909; h=from : to :\r\n",
+    " subject : date : message-id : from : subject : date;\r\n",
+    " bh=2jUSOH9NhtVGCQWNr9BrIAPreKQjO6Sn7XIkfJVOzv8=;\r\n",
+    " b=/gCrinpcQOoIfuHNQIbq4pgh9kyIK3AQUdt9OdqQehSwhEIug4D11Bus\r\n",
+    " Fa3bT3FY5OsU7ZbnKELq+eXdp1Q1Dw==\r\n",
+    "DKIM-Signature: v=1; a=rsa-sha256; c=relaxed/relaxed;\r\n",
+    " d=football.example.com; i=@football.example.com;\r\n",
+    " q=dns/txt; s=test; t=1528637909; h=from : to : subject :\r\n",
+    " date : message-id : from : subject : date;\r\n",
+    " bh=2jUSOH9NhtVGCQWNr9BrIAPreKQjO6Sn7XIkfJVOzv8=;\r\n",
+    " b=F45dVWDfMbQDGHJFlXUNB2HKfbCeLRyhDXgFpEL8GwpsRe0IeIixNTe3\r\n",
+    " DhCVlUrSjV4BwcVcOF6+FF3Zo9Rpo1tFOeS9mPYQTnGdaSGsgeefOsk2Jz\r\n",
+    " dA+L10TeYt9BgDfQNZtKdN1WO//KgIqXP7OdEFE4LjFYNcUxZQ4FADY+8=\r\n",
+    "From: Joe SixPack <joe@football.example.com>\r\n",
+    "To: Suzie Q <suzie@shopping.example.net>\r\n",
+    "Subject: Is dinner ready?\r\n",
+    "Date: Fri, 11 Jul 2003 21:00:37 -0700 (PDT)\r\n",
+    "Message-ID: <20030712040037.46341.5F8J@football.example.com>\r\n",
+    "\r\n",
+    "Hi.\r\n",
+    "\r\n",
+    "We lost the game.  Are you hungry yet?\r\n",
+    "\r\n",
+    "Joe.\r\n",
+);
+
+const RSA_TEST_KEY: &str = include_str!("testdata/rsa-2048.pem");
+
+fn verifier(records: &[(&str, &str)]) -> DkimVerifier {
+    let txt: BTreeMap<String, Vec<String>> = records
+        .iter()
+        .map(|(name, record)| (name.to_string(), vec![record.to_string()]))
+        .collect();
+    DkimVerifier::new(Arc::new(StaticResolver::new(&txt)))
+}
+
+fn key_config(algorithm: &str, private_key: &str) -> DkimKeyConfig {
+    DkimKeyConfig {
+        domain: "example.com".to_string(),
+        selector: "test".to_string(),
+        private_key_path: Default::default(),
+        private_key: Some(private_key.to_string()),
+        algorithm: algorithm.to_string(),
+        activate_at: None,
+        retire_at: None,
+    }
+}
+
+fn signer(algorithm: &str, private_key: &str) -> DkimSigner {
+    let config = DkimConfig {
+        enabled: true,
+        keys: vec![key_config(algorithm, private_key)],
+        ..Default::default()
+    };
+    DkimSigner::from_config(&config).unwrap()
+}
+
+const MESSAGE: &str = concat!(
+    "From: Ana <ana@example.com>\r\n",
+    "To: bob@example.net\r\n",
+    "Subject: Teste\r\n",
+    "  com continuação\r\n",
+    "Date: Mon, 19 Oct 2026 09:00:00 +0000\r\n",
+    "\r\n",
+    "Olá,  mundo \r\n",
+    "\r\n",
+    "\r\n",
+);
+
+#[tokio::test]
+async fn rfc8463_signatures_verify() {
+    let verifier = verifier(&[
+        (
+            "brisbane._domainkey.football.example.com",
+            RFC8463_ED25519_RECORD,
+        ),
+        ("test._domainkey.football.example.com", RFC8463_RSA_RECORD),
+    ]);
+
+    let results = verifier.verify(RFC8463_MESSAGE).await;
+    assert_eq!(results.len(), 2);
+    for result in &results {
+        assert_eq!(result.result, DkimResult::Pass, "{:?}", result.reason);
+        assert_eq!(result.domain, "football.example.com");
+    }
+    assert_eq!(results[0].algorithm, "ed25519-sha256");
+    assert_eq!(results[1].algorithm, "rsa-sha256");
+}
+
+// O Ed25519 é determinístico: refazendo os dados assinados a partir da
+// mensagem do RFC, a assinatura precisa ser idêntica à publicada
+#[test]
+fn rfc8463_ed25519_signature_byte_for_byte() {
+    let (headers, body) = message::split(RFC8463_MESSAGE);
+    let fields = message::header_fields(headers);
+    let signature = &fields[0];
+    let tags = verifier::parse_tags(verifier::field_value(signature.raw)).unwrap();
+
+    let canonical_body = canonicalization::body(body, Canonicalization::Relaxed);
+    assert_eq!(
+        B64.encode(Sha256::digest(canonical_body.as_bytes())),
+        tags["bh"]
+    );
+
+    let names: Vec<String> = tags["h"]
+        .split(':')
+        .map(|name| name.trim().to_ascii_lowercase())
+        .collect();
+    let mut data = String::new();
+    for field in message::select(&fields, &names) {
+        data.push_str(&canonicalization::header(
+            field.raw,
+            Canonicalization::Relaxed,
+        ));
+    }
+    let unsigned = canonicalization::header(
+        &verifier::without_signature(signature.raw),
+        Canonicalization::Relaxed,
+    );
+    data.push_str(unsigned.trim_end_matches("\r\n"));
+
+    let key = PrivateKey::load(&key_config("ed25519-sha256", RFC8463_ED25519_KEY)).unwrap();
+    assert_eq!(B64.encode(key.sign(data.as_bytes()).unwrap()), tags["b"]);
+}
+
+#[tokio::test]
+async fn rsa_sign_verify_round_trip() {
+    let signer = signer("rsa-sha256", RSA_TEST_KEY);
+    let key = PrivateKey::load(&key_config("rsa-sha256", RSA_TEST_KEY)).unwrap();
+    let verifier = verifier(&[(
+        "test._domainkey.example.com",
+        &keygen::dns_record(&key).unwrap(),
+    )]);
+
+    let signatures = signer.sign(MESSAGE, None).unwrap();
+    assert_eq!(signatures.len(), 1);
+    let signed = format!("{}\r\n{}", signatures[0], MESSAGE);
+    let results = verifier.verify(&signed).await;
+    assert_eq!(
+        results[0].result,
+        DkimResult::Pass,
+        "{:?}",
+        results[0].reason
+    );
+
+    // Qualquer alteração no body invalida a assinatura
+    let tampered = signed.replace("mundo", "mundo!");
+    let results = verifier.verify(&tampered).await;
+    assert_eq!(results[0].result, DkimResult::Fail);
+}
+
+#[tokio::test]
+async fn ed25519_sign_verify_round_trip() {
+    let signer = signer("ed25519-sha256", RFC8463_ED25519_KEY);
+    let verifier = verifier(&[("test._domainkey.example.com", RFC8463_ED25519_RECORD)]);
+
+    let signatures = signer.sign(MESSAGE, None).unwrap();
+    let signed = format!("{}\r\n{}", signatures[0], MESSAGE);
+    let results = verifier.verify(&signed).await;
+    assert_eq!(
+        results[0].result,
+        DkimResult::Pass,
+        "{:?}",
+        results[0].reason
+    );
+}
+
+// Exemplos do RFC 6376 §3.4.5
+#[test]
+fn header_canonicalization() {
+    let fields = message::header_fields("A: X\r\nB : Y\t\r\n\tZ  \r\n");
+    assert_eq!(fields.len(), 2);
+    assert_eq!(fields[1].name, "B");
+    assert_eq!(fields[1].raw, "B : Y\t\r\n\tZ  ");
+
+    let relaxed: String = fields
+        .iter()
+        .map(|f| canonicalization::header(f.raw, Canonicalization::Relaxed))
+        .collect();
+    assert_eq!(relaxed, "a:X\r\nb:Y Z\r\n");
+
+    let simple: String = fields
+        .iter()
+        .map(|f| canonicalization::header(f.raw, Canonicalization::Simple))
+        .collect();
+    assert_eq!(simple, "A: X\r\nB : Y\t\r\n\tZ  \r\n");
+}
+
+#[test]
+fn body_canonicalization() {
+    let body = " C \r\nD \t E\r\n\r\n\r\n";
+    assert_eq!(
+        canonicalization::body(body, Canonicalization::Relaxed),
+        " C\r\nD E\r\n"
+    );
+    assert_eq!(
+        canonicalization::body(body, Canonicalization::Simple),
+        " C \r\nD \t E\r\n"
+    );
+
+    // Body vazio (RFC 6376 §3.4.3 e §3.4.4)
+    assert_eq!(canonicalization::body("", Canonicalization::Simple), "\r\n");
+    assert_eq!(
+        canonicalization::body("\r\n\r\n", Canonicalization::Relaxed),
+        ""
+    );
+}
+
+// Cada nome de h= usa a última ocorrência ainda não usada (RFC 6376 §5.4.2)
+#[test]
+fn select_uses_last_occurrence_first() {
+    let fields = message::header_fields(concat!(
+        "Subject: primeiro\r\n",
+        "From: ana@example.com\r\n",
+        "Subject: segundo\r\n",
+        "\tcontinuação\r\n",
+    ));
+    let names: Vec<String> = ["subject", "subject", "subject", "from"]
+        .iter()
+        .map(|name| name.to_string())
+        .collect();
+
+    let selected: Vec<&str> = message::select(&fields, &names)
+        .iter()
+        .map(|f| f.raw)
+        .collect();
+    assert_eq!(
+        selected,
+        [
+            "Subject: segundo\r\n\tcontinuação",
+            "Subject: primeiro",
+            "From: ana@example.com",
+        ]
+    );
+}
@@ -0,0 +1,386 @@
+use std::{collections::HashMap, fmt, sync::Arc};
+
+use base64::{Engine, engine::general_purpose::STANDARD as B64};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    dkim::{
+        canonicalization::{self, Canonicalization},
+        message::{self, HeaderField},
+    },
+    dns::{Resolver, dns_error::DnsError},
+};
+
+// Limite de assinaturas verificadas por mensagem, para que uma mensagem com
+// centenas de DKIM-Signature não gere centenas de consultas DNS
+const MAX_SIGNATURES: usize = 8;
+// RFC 8301 §3.2: chaves RSA menores não são aceitas
+const MIN_RSA_BITS: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DkimResult {
+    Pass,
+    Fail,
+    TempError,
+    PermError,
+}
+
+impl fmt::Display for DkimResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DkimResult::Pass => write!(f, "pass"),
+            DkimResult::Fail => write!(f, "fail"),
+            DkimResult::TempError => write!(f, "temperror"),
+            DkimResult::PermError => write!(f, "permerror"),
+        }
+    }
+}
+
+// Resultado de uma DKIM-Signature. Os campos vêm da própria assinatura e
+// ficam vazios quando ela não pôde ser lida.
+pub struct SignatureResult {
+    pub result: DkimResult,
+    pub reason: Option<String>,
+    pub domain: String,
+    pub selector: String,
+    pub identity: String,
+    pub algorithm: String,
+    // Início de b=, para distinguir assinaturas no Authentication-Results (RFC 6008)
+    pub signature_prefix: String,
+}
+
+pub struct DkimVerifier {
+    resolver: Arc<dyn Resolver>,
+}
+
+// Falha ao verificar uma assinatura: o resultado e o motivo
+pub struct Failure(pub DkimResult, pub String);
+
+impl Failure {
+    pub fn perm(reason: impl Into<String>) -> Self {
+        Failure(DkimResult::PermError, reason.into())
+    }
+
+    pub fn fail(reason: impl Into<String>) -> Self {
+        Failure(DkimResult::Fail, reason.into())
+    }
+}
+
+impl DkimVerifier {
+    pub fn new(resolver: Arc<dyn Resolver>) -> Self {
+        Self { resolver }
+    }
+
+    // Um resultado por DKIM-Signature, na ordem da mensagem. Vazio quando a
+    // mensagem não é assinada (dkim=none).
+    pub async fn verify(&self, raw_message: &str) -> Vec<SignatureResult> {
+        let (headers, body) = message::split(raw_message);
+        let fields = message::header_fields(headers);
+
+        let mut results = Vec::new();
+        for field in fields
+            .iter()
+            .filter(|f| f.name.eq_ignore_ascii_case("dkim-signature"))
+            .take(MAX_SIGNATURES)
+        {
+            let tags = parse_tags(field_value(field.raw));
+            let get = |name: &str| {
+                tags.as_ref()
+                    .and_then(|t| t.get(name))
+                    .cloned()
+                    .unwrap_or_default()
+            };
+            let domain = get("d").to_ascii_lowercase();
+            let identity = tags
+                .as_ref()
+                .and_then(|t| t.get("i").cloned())
+                .unwrap_or_else(|| format!("@{}", domain));
+
+            let outcome = match &tags {
+                Some(tags) => self.verify_signature(field, tags, &fields, body).await,
+                None => Err(Failure::perm("malformed signature")),
+            };
+            let (result, reason) = match outcome {
+                Ok(()) => (DkimResult::Pass, None),
+                Err(Failure(result, reason)) => (result, Some(reason)),
+            };
+
+            results.push(SignatureResult {
+                result,
+                reason,
+                domain,
+                selector: get("s"),
+                identity,
+                algorithm: get("a"),
+                signature_prefix: get("b").chars().take(8).collect(),
+            });
+        }
+
+        results
+    }
+
+    // RFC 6376 §6.1
+    async fn verify_signature(
+        &self,
+        field: &HeaderField<'_>,
+        tags: &HashMap<String, String>,
+        fields: &[HeaderField<'_>],
+        body: &str,
+    ) -> Result<(), Failure> {
+        for required in ["v", "a", "b", "bh", "d", "h", "s"] {
+            if !tags.contains_key(required) {
+                return Err(Failure::perm(format!("missing {}= tag", required)));
+            }
+        }
+        if tags["v"] != "1" {
+            return Err(Failure::perm("unsupported version"));
+        }
+
+        let key_type = match tags["a"].to_ascii_lowercase().as_str() {
+            "rsa-sha256" => "rsa",
+            "ed25519-sha256" => "ed25519",
+            // rsa-sha1 não é mais aceito (RFC 8301)
+            _ => return Err(Failure::perm("unsupported algorithm")),
+        };
+
+        let (header_c, body_c) = match tags.get("c") {
+            Some(c) => Canonicalization::parse_pair(c)
+                .ok_or_else(|| Failure::perm("unknown canonicalization"))?,
+            None => (Canonicalization::Simple, Canonicalization::Simple),
+        };
+
+        if let Some(q) = tags.get("q")
+            && !q.split(':').any(|m| m.eq_ignore_ascii_case("dns/txt"))
+        {
+            return Err(Failure::perm("unsupported query method"));
+        }
+
+        let domain = tags["d"].to_ascii_lowercase();
+        let identity_domain = match tags.get("i") {
+            Some(i) => {
+                let Some((_, d)) = i.rsplit_once('@') else {
+                    return Err(Failure::perm("malformed i= tag"));
+                };
+                let d = d.to_ascii_lowercase();
+                if d != domain && !d.ends_with(&format!(".{}", domain)) {
+                    return Err(Failure::perm("i= domain outside of d="));
+                }
+                d
+            }
+            None => domain.clone(),
+        };
+
+        let signed_headers: Vec<String> = tags["h"]
+            .split(':')
+            .map(|h| h.trim().to_ascii_lowercase())
+            .collect();
+        if !signed_headers.iter().any(|h| h == "from") {
+            return Err(Failure::perm("From field not signed"));
+        }
+
+        let timestamp = parse_number(tags.get("t"))?;
+        let expiration = parse_number(tags.get("x"))?;
+        if let (Some(t), Some(x)) = (timestamp, expiration)
+            && x < t
+        {
+            return Err(Failure::perm("x= earlier than t="));
+        }
+        if let Some(x) = expiration
+            && x < chrono::Utc::now().timestamp() as u64
+        {
+            return Err(Failure::fail("signature expired"));
+        }
+
+        let signature = B64
+            .decode(&tags["b"])
+            .map_err(|_| Failure::perm("malformed b= tag"))?;
+        let body_hash = B64
+            .decode(&tags["bh"])
+            .map_err(|_| Failure::perm("malformed bh= tag"))?;
+
+        let key = self.fetch_key(&tags["s"], &domain).await?;
+        if !key.key_type.eq_ignore_ascii_case(key_type) {
+            return Err(Failure::perm("key type does not match a="));
+        }
+        if key.strict_domain && identity_domain != domain {
+            return Err(Failure::perm("key requires i= domain equal to d="));
+        }
+
+        // Hash do body, limitado por l= quando presente
+        let canonical_body = canonicalization::body(body, body_c);
+        let mut body_bytes = canonical_body.as_bytes();
+        if let Some(l) = parse_number(tags.get("l"))? {
+            let l = usize::try_from(l).unwrap_or(usize::MAX);
+            if l > body_bytes.len() {
+                return Err(Failure::perm("l= longer than body"));
+            }
+            body_bytes = &body_bytes[..l];
+        }
+        if Sha256::digest(body_bytes).as_slice() != body_hash.as_slice() {
+            return Err(Failure::fail("body hash did not verify"));
+        }
+
+        // Headers assinados e, por último, a própria assinatura com b= vazio
+        let mut data = String::new();
+        for selected in message::select(fields, &signed_headers) {
+            data.push_str(&canonicalization::header(selected.raw, header_c));
+        }
+        let unsigned = canonicalization::header(&without_signature(field.raw), header_c);
+        data.push_str(unsigned.trim_end_matches("\r\n"));
+
+        if verify_data(key_type, &key.public_key, data.as_bytes(), &signature)? {
+            Ok(())
+        } else {
+            Err(Failure::fail("signature did not verify"))
+        }
+    }
+
+    // Registro selector._domainkey.domain (RFC 6376 §3.6.2)
+    pub async fn fetch_key(&self, selector: &str, domain: &str) -> Result<PublicKey, Failure> {
+        let name = format!("{}._domainkey.{}", selector, domain);
+        let records = match self.resolver.txt(&name).await {
+            Ok(records) => records,
+            Err(DnsError::NotFoundError(_)) => return Err(Failure::perm("no key for signature")),
+            Err(e) => return Err(Failure(DkimResult::TempError, e.to_string())),
+        };
+
+        let Some(tags) = records
+            .iter()
+            .filter_map(|r| parse_tags(r))
+            .find(|t| t.contains_key("p"))
+        else {
+            return Err(Failure::perm("malformed key record"));
+        };
+
+        if tags.get("v").is_some_and(|v| v != "DKIM1") {
+            return Err(Failure::perm("unsupported key record version"));
+        }
+        if tags
+            .get("h")
+            .is_some_and(|h| !h.split(':').any(|a| a.eq_ignore_ascii_case("sha256")))
+        {
+            return Err(Failure::perm("key does not allow sha256"));
+        }
+        if tags
+            .get("s")
+            .is_some_and(|s| !s.split(':').any(|t| t == "*" || t == "email"))
+        {
+            return Err(Failure::perm("key not intended for email"));
+        }
+        if tags["p"].is_empty() {
+            return Err(Failure::perm("key revoked"));
+        }
+
+        let public_key = B64
+            .decode(&tags["p"])
+            .map_err(|_| Failure::perm("malformed p= tag"))?;
+
+        Ok(PublicKey {
+            key_type: tags.get("k").cloned().unwrap_or_else(|| "rsa".to_string()),
+            public_key,
+            strict_domain: tags
+                .get("t")
+                .is_some_and(|t| t.split(':').any(|flag| flag == "s")),
+        })
+    }
+}
+
+pub struct PublicKey {
+    pub key_type: String,
+    pub public_key: Vec<u8>,
+    // t=s: i= não pode ser um subdomínio de d=
+    pub strict_domain: bool,
+}
+
+pub fn verify_data(
+    key_type: &str,
+    public_key: &[u8],
+    data: &[u8],
+    signature: &[u8],
+) -> Result<bool, Failure> {
+    match key_type {
+        "rsa" => {
+            use rsa::{
+                RsaPublicKey, pkcs1::DecodeRsaPublicKey, pkcs1v15::VerifyingKey,
+                pkcs8::DecodePublicKey, signature::Verifier, traits::PublicKeyParts,
+            };
+
+            // p= é normalmente um SubjectPublicKeyInfo, mas alguns publicam o RSAPublicKey
+            let key = RsaPublicKey::from_public_key_der(public_key)
+                .or_else(|_| RsaPublicKey::from_pkcs1_der(public_key))
+                .map_err(|_| Failure::perm("malformed public key"))?;
+            if key.size() * 8 < MIN_RSA_BITS {
+                return Err(Failure::perm("RSA key too small"));
+            }
+
+            let Ok(signature) = rsa::pkcs1v15::Signature::try_from(signature) else {
+                return Ok(false);
+            };
+            Ok(VerifyingKey::<Sha256>::new(key)
+                .verify(data, &signature)
+                .is_ok())
+        }
+        _ => {
+            let key = <[u8; 32]>::try_from(public_key)
+                .ok()
+                .and_then(|bytes| ed25519_dalek::VerifyingKey::from_bytes(&bytes).ok())
+                .ok_or_else(|| Failure::perm("malformed public key"))?;
+            let Ok(signature) = ed25519_dalek::Signature::from_slice(signature) else {
+                return Ok(false);
+            };
+
+            // RFC 8463: a assinatura é do hash SHA-256 dos dados
+            Ok(key.verify_strict(&Sha256::digest(data), &signature).is_ok())
+        }
+    }
+}
+
+// Lista de tags "nome=valor;" (RFC 6376 §3.2). Espaços dentro dos valores
+// são removidos, o que é correto para todas as tags que usamos. Tags
+// repetidas invalidam a lista.
+pub fn parse_tags(value: &str) -> Option<HashMap<String, String>> {
+    let mut tags = HashMap::new();
+    for item in value.split(';') {
+        if item.trim().is_empty() {
+            continue;
+        }
+        let (name, value) = item.split_once('=')?;
+        let value: String = value.split_whitespace().collect();
+        if tags.insert(name.trim().to_string(), value).is_some() {
+            return None;
+        }
+    }
+    Some(tags)
+}
+
+pub fn parse_number(value: Option<&String>) -> Result<Option<u64>, Failure> {
+    value
+        .map(|v| {
+            v.parse()
+                .map_err(|_| Failure::perm("malformed numeric tag"))
+        })
+        .transpose()
+}
+
+pub fn field_value(raw: &str) -> &str {
+    raw.split_once(':')
+        .map(|(_, value)| value)
+        .unwrap_or_default()
+}
+
+// O header DKIM-Signature como foi assinado: igual ao recebido, mas com o
+// valor de b= vazio (RFC 6376 §3.7)
+pub fn without_signature(raw: &str) -> String {
+    let Some((name, value)) = raw.split_once(':') else {
+        return raw.to_string();
+    };
+
+    let items: Vec<String> = value
+        .split(';')
+        .map(|item| match item.split_once('=') {
+            Some((tag, _)) if tag.trim() == "b" => format!("{}=", tag),
+            _ => item.to_string(),
+        })
+        .collect();
+    format!("{}:{}", name, items.join(";"))
+}
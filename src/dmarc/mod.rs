@@ -0,0 +1,286 @@
+pub mod public_suffix;
+pub mod record;
+pub mod report;
+
+use std::{fmt, sync::Arc};
+
+use publicsuffix::{List, Psl};
+use rand::Rng;
+
+use crate::{
+    config::dmarc_config::DmarcConfig,
+    dkim::{
+        message,
+        verifier::{DkimResult, SignatureResult},
+    },
+    dns::{Resolver, dns_error::DnsError},
+    helpers::email_helper,
+    spf::{SpfCheck, SpfResult},
+};
+use record::{Policy, Record};
+use report::{DkimAuth, SpfAuth};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DmarcResult {
+    None,
+    Pass,
+    Fail,
+    TempError,
+    PermError,
+}
+
+impl fmt::Display for DmarcResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DmarcResult::None => write!(f, "none"),
+            DmarcResult::Pass => write!(f, "pass"),
+            DmarcResult::Fail => write!(f, "fail"),
+            DmarcResult::TempError => write!(f, "temperror"),
+            DmarcResult::PermError => write!(f, "permerror"),
+        }
+    }
+}
+
+// Registro encontrado no DNS e o domínio onde foi publicado
+pub struct PublishedPolicy {
+    pub domain: String,
+    pub record: Record,
+}
+
+pub struct DmarcOutcome {
+    pub result: DmarcResult,
+    // Motivo de temperror e permerror
+    pub reason: Option<String>,
+    // Domínio do header From (vazio quando não foi possível extrair)
+    pub header_from: String,
+    pub policy: Option<PublishedPolicy>,
+    // Política pedida pelo domínio para esta mensagem, já considerando pct=
+    pub disposition: Policy,
+    // A mensagem ficou fora de pct= e recebeu uma política mais branda
+    pub sampled_out: bool,
+    // Política desfeita por uma cadeia ARC de um intermediário confiável
+    pub trusted_forwarder: bool,
+    pub dkim_aligned: bool,
+    pub spf_aligned: bool,
+    // Resultados usados na avaliação, para os relatórios agregados
+    pub dkim: Vec<DkimAuth>,
+    pub spf: Option<SpfAuth>,
+}
+
+pub struct DmarcEvaluator {
+    resolver: Arc<dyn Resolver>,
+    // Public Suffix List; sem ela, só o TLD é sufixo
+    suffix_list: Arc<List>,
+    // Sufixos extras de dmarc.public_suffixes
+    public_suffixes: Vec<String>,
+}
+
+impl DmarcEvaluator {
+    pub fn new(resolver: Arc<dyn Resolver>, config: &DmarcConfig) -> Self {
+        let suffix_list = public_suffix::current(&config.public_suffix_list);
+        Self {
+            resolver,
+            suffix_list,
+            public_suffixes: config
+                .public_suffixes
+                .iter()
+                .map(|s| normalize(s.trim_start_matches('.')))
+                .collect(),
+        }
+    }
+
+    // Avalia a mensagem com os resultados de DKIM e do SPF do MAIL FROM
+    // (RFC 7489 §6.6)
+    pub async fn evaluate(
+        &self,
+        raw: &str,
+        signatures: &[SignatureResult],
+        spf: Option<&SpfCheck>,
+    ) -> DmarcOutcome {
+        let mut outcome = DmarcOutcome {
+            result: DmarcResult::None,
+            reason: None,
+            header_from: String::new(),
+            policy: None,
+            disposition: Policy::None,
+            sampled_out: false,
+            trusted_forwarder: false,
+            dkim_aligned: false,
+            spf_aligned: false,
+            dkim: signatures
+                .iter()
+                .map(|s| DkimAuth {
+                    domain: s.domain.clone(),
+                    selector: s.selector.clone(),
+                    result: s.result.to_string(),
+                })
+                .collect(),
+            spf: spf.map(|s| SpfAuth {
+                domain: s.domain.clone(),
+                scope: s.scope.to_string(),
+                result: s.result.to_string(),
+            }),
+        };
+
+        outcome.header_from = match header_from(raw) {
+            Ok(domain) => domain,
+            Err(reason) => {
+                outcome.result = DmarcResult::PermError;
+                outcome.reason = Some(reason);
+                return outcome;
+            }
+        };
+        let domain = outcome.header_from.clone();
+
+        let policy = match self.discover(&domain).await {
+            Ok(Some(policy)) => policy,
+            Ok(None) => return outcome,
+            Err((result, reason)) => {
+                outcome.result = result;
+                outcome.reason = Some(reason);
+                return outcome;
+            }
+        };
+
+        let record = &policy.record;
+        outcome.dkim_aligned = signatures.iter().any(|s| {
+            s.result == DkimResult::Pass && self.aligned(&s.domain, &domain, record.strict_dkim)
+        });
+        outcome.spf_aligned = spf.is_some_and(|s| {
+            s.result == SpfResult::Pass && self.aligned(&s.domain, &domain, record.strict_spf)
+        });
+
+        if outcome.dkim_aligned || outcome.spf_aligned {
+            outcome.result = DmarcResult::Pass;
+        } else {
+            outcome.result = DmarcResult::Fail;
+            // sp= vale para subdomínios cobertos pelo registro do domínio
+            // organizacional
+            let requested = if policy.domain == domain {
+                record.p
+            } else {
+                record.sp.unwrap_or(record.p)
+            };
+            outcome.sampled_out =
+                requested != Policy::None && record.pct <= rand::thread_rng().gen_range(0..100);
+            outcome.disposition = if outcome.sampled_out {
+                requested.weaker()
+            } else {
+                requested
+            };
+        }
+
+        outcome.policy = Some(policy);
+        outcome
+    }
+
+    // Domínio registrado sob o sufixo público (RFC 7489 §3.2)
+    pub fn organizational_domain(&self, domain: &str) -> String {
+        let domain = normalize(domain);
+        let labels: Vec<&str> = domain.split('.').collect();
+
+        let listed = self
+            .suffix_list
+            .suffix(domain.as_bytes())
+            .map(|suffix| suffix.as_bytes().split(|&b| b == b'.').count())
+            .unwrap_or(1);
+        let extra = (2..labels.len())
+            .rev()
+            .find(|n| {
+                self.public_suffixes
+                    .contains(&labels[labels.len() - n..].join("."))
+            })
+            .unwrap_or(1);
+        let suffix_len = listed.max(extra);
+        if labels.len() <= suffix_len {
+            return domain;
+        }
+        labels[labels.len() - suffix_len - 1..].join(".")
+    }
+
+    // Procura o registro no próprio domínio e, sem ele, no domínio
+    // organizacional (RFC 7489 §6.6.3)
+    async fn discover(
+        &self,
+        domain: &str,
+    ) -> Result<Option<PublishedPolicy>, (DmarcResult, String)> {
+        if let Some(record) = self.lookup(domain).await? {
+            return Ok(Some(PublishedPolicy {
+                domain: domain.to_string(),
+                record,
+            }));
+        }
+
+        let organizational = self.organizational_domain(domain);
+        if organizational == domain {
+            return Ok(None);
+        }
+        Ok(self
+            .lookup(&organizational)
+            .await?
+            .map(|record| PublishedPolicy {
+                domain: organizational,
+                record,
+            }))
+    }
+
+    async fn lookup(&self, domain: &str) -> Result<Option<Record>, (DmarcResult, String)> {
+        let name = format!("_dmarc.{}", domain);
+        let records = match self.resolver.txt(&name).await {
+            Ok(records) => records,
+            Err(DnsError::NotFoundError(_)) => return Ok(None),
+            Err(e) => return Err((DmarcResult::TempError, e.to_string())),
+        };
+
+        // Mais de um registro equivale a nenhum
+        let records: Vec<&String> = records.iter().filter(|r| record::is_dmarc(r)).collect();
+        match records[..] {
+            [text] => record::parse(text)
+                .map(Some)
+                .map_err(|e| (DmarcResult::PermError, format!("{}: {}", name, e))),
+            _ => Ok(None),
+        }
+    }
+
+    fn aligned(&self, identifier: &str, domain: &str, strict: bool) -> bool {
+        let identifier = normalize(identifier);
+        if strict {
+            identifier == domain
+        } else {
+            self.organizational_domain(&identifier) == self.organizational_domain(domain)
+        }
+    }
+}
+
+// Domínio do endereço do header From. Mensagens com mais de um From ou
+// mais de um endereço não podem ser avaliadas (RFC 7489 §6.6.1).
+fn header_from(raw: &str) -> Result<String, String> {
+    let (headers, _) = message::split(raw);
+    let fields = message::header_fields(headers);
+    let from: Vec<_> = fields
+        .iter()
+        .filter(|f| f.name.eq_ignore_ascii_case("from"))
+        .collect();
+
+    let [field] = from[..] else {
+        return Err(format!("{} From headers", from.len()));
+    };
+    let value = field
+        .raw
+        .split_once(':')
+        .map(|(_, v)| v)
+        .unwrap_or_default();
+    let addresses = email_helper::parse_address_list(value);
+    let [address] = &addresses[..] else {
+        return Err("From header must have a single address".to_string());
+    };
+
+    match address.rsplit_once('@') {
+        Some((_, domain)) if !domain.is_empty() => Ok(normalize(domain)),
+        _ => Err(format!("invalid From address {}", address)),
+    }
+}
+
+fn normalize(domain: &str) -> String {
+    domain.trim_end_matches('.').to_ascii_lowercase()
+}
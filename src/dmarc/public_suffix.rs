@@ -0,0 +1,43 @@
+use std::{io, path::Path, sync::Arc};
+
+use arc_swap::ArcSwapOption;
+use publicsuffix::List;
+
+static LIST: ArcSwapOption<List> = ArcSwapOption::const_empty();
+
+// Public Suffix List (public_suffix_list.dat). É lida na primeira avaliação e
+// de novo a cada SIGHUP; as mensagens não consultam o arquivo.
+pub fn current(path: &Path) -> Arc<List> {
+    match LIST.load_full() {
+        Some(list) => list,
+        None => reload(path),
+    }
+}
+
+// Relê o arquivo e troca a lista em uso. Com erro, a lista anterior continua
+// valendo; sem nenhuma, só o TLD é sufixo.
+pub fn reload(path: &Path) -> Arc<List> {
+    let list = match read(path) {
+        Ok(list) => Arc::new(list),
+        Err(e) => {
+            tracing::error!("Erro ao ler a lista de sufixos {}: {}", path.display(), e);
+            LIST.load_full().unwrap_or_else(|| Arc::new(List::new()))
+        }
+    };
+    LIST.store(Some(list.clone()));
+    list
+}
+
+fn read(path: &Path) -> io::Result<List> {
+    let content = std::fs::read_to_string(path)?;
+    let list: List = content
+        .parse()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    if list.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "nenhuma regra na lista",
+        ));
+    }
+    Ok(list)
+}
@@ -0,0 +1,168 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+// Política publicada em p=/sp= e disposição aplicada à mensagem
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Policy {
+    None,
+    Quarantine,
+    Reject,
+}
+
+impl Policy {
+    fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "none" => Some(Policy::None),
+            "quarantine" => Some(Policy::Quarantine),
+            "reject" => Some(Policy::Reject),
+            _ => None,
+        }
+    }
+
+    // Política aplicada quando a mensagem fica fora de pct= (RFC 7489 §6.6.4)
+    pub fn weaker(self) -> Self {
+        match self {
+            Policy::Reject => Policy::Quarantine,
+            _ => Policy::None,
+        }
+    }
+}
+
+impl fmt::Display for Policy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Policy::None => write!(f, "none"),
+            Policy::Quarantine => write!(f, "quarantine"),
+            Policy::Reject => write!(f, "reject"),
+        }
+    }
+}
+
+// Um registro DMARC já interpretado (RFC 7489 §6.3)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Record {
+    pub p: Policy,
+    pub sp: Option<Policy>,
+    pub pct: u8,
+    // adkim=/aspf=: alinhamento estrito (s) ou relaxado (r, o padrão)
+    pub strict_dkim: bool,
+    pub strict_spf: bool,
+    // Endereços de rua= (só mailto:) com o tamanho máximo do relatório
+    pub rua: Vec<ReportUri>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportUri {
+    pub address: String,
+    pub max_size: Option<u64>,
+}
+
+// Registros começam com "v=DMARC1", seguido de ";" ou do fim do texto
+pub fn is_dmarc(text: &str) -> bool {
+    text.split(';')
+        .next()
+        .and_then(|tag| tag.split_once('='))
+        .is_some_and(|(name, value)| name.trim() == "v" && value.trim() == "DMARC1")
+}
+
+pub fn parse(text: &str) -> Result<Record, String> {
+    let mut p = None;
+    let mut invalid_p = None;
+    let mut record = Record {
+        p: Policy::None,
+        sp: None,
+        pct: 100,
+        strict_dkim: false,
+        strict_spf: false,
+        rua: Vec::new(),
+    };
+
+    for (i, tag) in text.split(';').enumerate() {
+        let tag = tag.trim();
+        if tag.is_empty() {
+            continue;
+        }
+        let Some((name, value)) = tag.split_once('=') else {
+            return Err(format!("invalid tag {}", tag));
+        };
+        let (name, value) = (name.trim(), value.trim());
+
+        // p= precisa vir logo depois de v=, mas aceitamos em qualquer posição
+        match name.to_ascii_lowercase().as_str() {
+            "v" if i == 0 => {}
+            "p" => match Policy::parse(value) {
+                Some(policy) => p = Some(policy),
+                None => invalid_p = Some(value.to_string()),
+            },
+            "sp" => record.sp = Policy::parse(value),
+            "pct" => {
+                record.pct = value
+                    .parse::<u8>()
+                    .ok()
+                    .filter(|pct| *pct <= 100)
+                    .ok_or_else(|| format!("invalid pct {}", value))?
+            }
+            "adkim" => record.strict_dkim = alignment(value)?,
+            "aspf" => record.strict_spf = alignment(value)?,
+            "rua" => record.rua = value.split(',').filter_map(report_uri).collect(),
+            // ruf=, fo=, rf=, ri= e tags desconhecidas são ignorados
+            _ => {}
+        }
+    }
+
+    // Sem p= válido, um registro com rua= vale como p=none (RFC 7489 §6.6.3)
+    match (p, invalid_p) {
+        (Some(policy), _) => record.p = policy,
+        (None, _) if !record.rua.is_empty() => record.p = Policy::None,
+        (None, Some(value)) => return Err(format!("invalid policy {}", value)),
+        (None, None) => return Err("missing policy".to_string()),
+    }
+
+    Ok(record)
+}
+
+fn alignment(value: &str) -> Result<bool, String> {
+    match value.to_ascii_lowercase().as_str() {
+        "r" => Ok(false),
+        "s" => Ok(true),
+        _ => Err(format!("invalid alignment mode {}", value)),
+    }
+}
+
+// "mailto:endereco[!tamanho]", com o tamanho em bytes ou com k, m, g ou t
+fn report_uri(uri: &str) -> Option<ReportUri> {
+    let uri = uri.trim();
+    let (scheme, rest) = uri.split_once(':')?;
+    if !scheme.eq_ignore_ascii_case("mailto") {
+        return None;
+    }
+
+    let (address, size) = match rest.rsplit_once('!') {
+        Some((address, size)) => (address, Some(size)),
+        None => (rest, None),
+    };
+    let address = address.replace("%2C", ",").replace("%21", "!");
+    if !address.contains('@') {
+        return None;
+    }
+
+    let max_size = size.and_then(|size| {
+        let digits = size.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+        let multiplier = match &size[digits.len()..].to_ascii_lowercase()[..] {
+            "" => 1,
+            "k" => 1 << 10,
+            "m" => 1 << 20,
+            "g" => 1 << 30,
+            "t" => 1 << 40,
+            _ => return None,
+        };
+        digits
+            .parse::<u64>()
+            .ok()
+            .map(|n| n.saturating_mul(multiplier))
+    });
+
+    Some(ReportUri { address, max_size })
+}
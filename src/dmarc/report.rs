@@ -0,0 +1,484 @@
+use std::{
+    collections::BTreeMap,
+    io::Write,
+    net::IpAddr,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use base64::{Engine, engine::general_purpose::STANDARD as B64};
+use chrono::{NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    config::{Config, shared_config::SharedConfig},
+    dmarc::{
+        DmarcEvaluator, DmarcOutcome,
+        record::{Policy, Record},
+    },
+    dns::{self, Resolver},
+    queue::{models::DeliveryJob, spool::Spool},
+    shutdown::Shutdown,
+};
+
+// Intervalo entre as verificações de dias já encerrados
+const CHECK_INTERVAL: Duration = Duration::from_secs(3600);
+const EXTENSION: &str = "jsonl";
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct DkimAuth {
+    pub domain: String,
+    pub selector: String,
+    pub result: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct SpfAuth {
+    pub domain: String,
+    pub scope: String,
+    pub result: String,
+}
+
+// Uma mensagem avaliada, gravada em uma linha do arquivo do dia
+#[derive(Serialize, Deserialize)]
+struct Observation {
+    policy_domain: String,
+    policy: Record,
+    #[serde(flatten)]
+    row: Row,
+}
+
+// Mensagens com os mesmos valores são agregadas em uma linha do relatório
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+struct Row {
+    source_ip: IpAddr,
+    header_from: String,
+    disposition: Policy,
+    dkim_aligned: bool,
+    spf_aligned: bool,
+    // sampled_out, trusted_forwarder, local_policy
+    reasons: Vec<String>,
+    dkim: Vec<DkimAuth>,
+    spf: Option<SpfAuth>,
+}
+
+// Registra o resultado para o relatório do domínio. `applied` é o que de
+// fato fizemos, que pode diferir do pedido pela configuração local.
+pub fn record(config: &Config, source_ip: IpAddr, outcome: &DmarcOutcome, applied: Policy) {
+    let dmarc = &config.dmarc;
+    let Some(policy) = &outcome.policy else {
+        return;
+    };
+    if !dmarc.reports || policy.record.rua.is_empty() {
+        return;
+    }
+
+    let mut reasons = Vec::new();
+    if outcome.sampled_out {
+        reasons.push("sampled_out".to_string());
+    }
+    if outcome.trusted_forwarder {
+        reasons.push("trusted_forwarder".to_string());
+    }
+    if applied != outcome.disposition {
+        reasons.push("local_policy".to_string());
+    }
+
+    let observation = Observation {
+        policy_domain: policy.domain.clone(),
+        policy: policy.record.clone(),
+        row: Row {
+            source_ip,
+            header_from: outcome.header_from.clone(),
+            disposition: applied,
+            dkim_aligned: outcome.dkim_aligned,
+            spf_aligned: outcome.spf_aligned,
+            reasons,
+            dkim: outcome.dkim.clone(),
+            spf: outcome.spf.clone(),
+        },
+    };
+
+    let mut line = match serde_json::to_vec(&observation) {
+        Ok(line) => line,
+        Err(e) => {
+            tracing::error!("Erro ao serializar resultado DMARC: {}", e);
+            return;
+        }
+    };
+    line.push(b'\n');
+
+    // Gravado fora das threads do runtime, sem atrasar a resposta ao DATA
+    let dir = dmarc.report_dir.clone();
+    let path = day_file(&dir, Utc::now().date_naive());
+    tokio::task::spawn_blocking(move || {
+        let result = std::fs::create_dir_all(&dir).and_then(|_| {
+            std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)?
+                .write_all(&line)
+        });
+        if let Err(e) = result {
+            tracing::error!(
+                "Erro ao gravar resultado DMARC em {}: {}",
+                path.display(),
+                e
+            );
+        }
+    });
+}
+
+// Envia os relatórios dos dias encerrados até o desligamento
+pub async fn run(shared_config: Arc<SharedConfig>, spool: Arc<Spool>, mut shutdown: Shutdown) {
+    loop {
+        let config = shared_config.load();
+        if config.dmarc.reports {
+            send_pending(&config, &spool).await;
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(CHECK_INTERVAL) => {}
+            _ = shutdown.wait() => break,
+        }
+    }
+}
+
+// Gera e coloca na fila um relatório por domínio para cada dia anterior ao
+// atual. O arquivo do dia é removido depois.
+pub async fn send_pending(config: &Config, spool: &Spool) {
+    let dir = &config.dmarc.report_dir;
+    let mut entries = match tokio::fs::read_dir(dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return,
+        Err(e) => {
+            tracing::error!("Erro ao ler {}: {}", dir.display(), e);
+            return;
+        }
+    };
+
+    let resolver = match dns::resolver() {
+        Ok(resolver) => resolver,
+        Err(e) => {
+            tracing::warn!("Relatórios DMARC adiados: {}", e);
+            return;
+        }
+    };
+
+    let today = Utc::now().date_naive();
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        let day = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .filter(|_| path.extension().is_some_and(|ext| ext == EXTENSION))
+            .and_then(|stem| NaiveDate::parse_from_str(stem, "%Y-%m-%d").ok());
+        let Some(day) = day.filter(|day| *day < today) else {
+            continue;
+        };
+
+        if let Err(e) = send_day(config, spool, resolver.clone(), &path, day).await {
+            tracing::error!("Erro ao enviar relatórios DMARC de {}: {}", day, e);
+            continue;
+        }
+        if let Err(e) = tokio::fs::remove_file(&path).await {
+            tracing::error!("Erro ao remover {}: {}", path.display(), e);
+        }
+    }
+}
+
+async fn send_day(
+    config: &Config,
+    spool: &Spool,
+    resolver: Arc<dyn Resolver>,
+    path: &Path,
+    day: NaiveDate,
+) -> anyhow::Result<()> {
+    let content = tokio::fs::read_to_string(path).await?;
+
+    // Por domínio: a política vista por último e a contagem de cada linha
+    let mut domains: BTreeMap<String, (Record, BTreeMap<Row, u64>)> = BTreeMap::new();
+    for line in content.lines().filter(|l| !l.trim().is_empty()) {
+        let observation: Observation = match serde_json::from_str(line) {
+            Ok(observation) => observation,
+            Err(e) => {
+                tracing::warn!("Linha inválida em {}: {}", path.display(), e);
+                continue;
+            }
+        };
+        let entry = domains
+            .entry(observation.policy_domain)
+            .or_insert_with(|| (observation.policy.clone(), BTreeMap::new()));
+        entry.0 = observation.policy;
+        *entry.1.entry(observation.row).or_default() += 1;
+    }
+
+    let hostname = &config.server.hostname;
+    let org_name = non_empty(&config.dmarc.org_name).unwrap_or(hostname);
+    let report_from = non_empty(&config.dmarc.report_from)
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("postmaster@{}", hostname));
+    let evaluator = DmarcEvaluator::new(resolver.clone(), &config.dmarc);
+
+    let mut queued = false;
+    for (domain, (policy, rows)) in &domains {
+        let report = Report {
+            org_name,
+            email: &report_from,
+            id: format!("{}.{}", day.format("%Y%m%d"), Uuid::new_v4().simple()),
+            day,
+            domain,
+            policy,
+            rows,
+        };
+        let xml = report.xml();
+
+        for uri in &policy.rua {
+            if uri.max_size.is_some_and(|max| xml.len() as u64 > max) {
+                tracing::warn!(
+                    "Relatório DMARC de {} maior que o limite de {}",
+                    domain,
+                    uri.address
+                );
+                continue;
+            }
+            if !authorized(&evaluator, resolver.as_ref(), domain, &uri.address).await {
+                tracing::warn!(
+                    "{} não autorizou relatórios DMARC de {}",
+                    uri.address,
+                    domain
+                );
+                continue;
+            }
+
+            let message = report.message(hostname, &uri.address, &xml);
+            let job = DeliveryJob::new(
+                &Uuid::new_v4().to_string(),
+                &report_from,
+                &uri.address,
+                &message,
+                config.queue.max_attempts,
+            );
+            spool.store(&job).await?;
+            queued = true;
+            tracing::info!("Relatório DMARC de {} para {}", domain, uri.address);
+        }
+    }
+
+    if queued {
+        spool.wake();
+    }
+    Ok(())
+}
+
+// Destinos fora do domínio da política precisam publicar
+// <domínio>._report._dmarc.<destino> (RFC 7489 §7.1)
+async fn authorized(
+    evaluator: &DmarcEvaluator,
+    resolver: &dyn Resolver,
+    domain: &str,
+    address: &str,
+) -> bool {
+    let target = address
+        .rsplit_once('@')
+        .map(|(_, d)| d.to_ascii_lowercase())
+        .unwrap_or_default();
+    if evaluator.organizational_domain(&target) == evaluator.organizational_domain(domain) {
+        return true;
+    }
+
+    let name = format!("{}._report._dmarc.{}", domain, target);
+    match resolver.txt(&name).await {
+        Ok(records) => records.iter().any(|r| r.starts_with("v=DMARC1")),
+        Err(_) => false,
+    }
+}
+
+struct Report<'a> {
+    org_name: &'a str,
+    email: &'a str,
+    id: String,
+    day: NaiveDate,
+    domain: &'a str,
+    policy: &'a Record,
+    rows: &'a BTreeMap<Row, u64>,
+}
+
+impl Report<'_> {
+    // Início e fim do dia, em segundos desde a época
+    fn range(&self) -> (i64, i64) {
+        let begin = self
+            .day
+            .and_time(chrono::NaiveTime::MIN)
+            .and_utc()
+            .timestamp();
+        (begin, begin + 86_399)
+    }
+
+    // Formato do Apêndice C de RFC 7489
+    fn xml(&self) -> String {
+        let (begin, end) = self.range();
+        let policy = self.policy;
+        let alignment = |strict: bool| if strict { "s" } else { "r" };
+
+        let mut xml = String::new();
+        xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<feedback>\n");
+        xml.push_str("  <report_metadata>\n");
+        xml.push_str(&format!(
+            "    <org_name>{}</org_name>\n",
+            escape(self.org_name)
+        ));
+        xml.push_str(&format!("    <email>{}</email>\n", escape(self.email)));
+        xml.push_str(&format!("    <report_id>{}</report_id>\n", self.id));
+        xml.push_str(&format!(
+            "    <date_range>\n      <begin>{}</begin>\n      <end>{}</end>\n    </date_range>\n",
+            begin, end
+        ));
+        xml.push_str("  </report_metadata>\n");
+
+        xml.push_str("  <policy_published>\n");
+        xml.push_str(&format!("    <domain>{}</domain>\n", escape(self.domain)));
+        xml.push_str(&format!(
+            "    <adkim>{}</adkim>\n",
+            alignment(policy.strict_dkim)
+        ));
+        xml.push_str(&format!(
+            "    <aspf>{}</aspf>\n",
+            alignment(policy.strict_spf)
+        ));
+        xml.push_str(&format!("    <p>{}</p>\n", policy.p));
+        xml.push_str(&format!("    <sp>{}</sp>\n", policy.sp.unwrap_or(policy.p)));
+        xml.push_str(&format!("    <pct>{}</pct>\n", policy.pct));
+        xml.push_str("  </policy_published>\n");
+
+        let pass_fail = |pass: bool| if pass { "pass" } else { "fail" };
+        for (row, count) in self.rows {
+            xml.push_str("  <record>\n    <row>\n");
+            xml.push_str(&format!("      <source_ip>{}</source_ip>\n", row.source_ip));
+            xml.push_str(&format!("      <count>{}</count>\n", count));
+            xml.push_str("      <policy_evaluated>\n");
+            xml.push_str(&format!(
+                "        <disposition>{}</disposition>\n",
+                row.disposition
+            ));
+            xml.push_str(&format!(
+                "        <dkim>{}</dkim>\n",
+                pass_fail(row.dkim_aligned)
+            ));
+            xml.push_str(&format!(
+                "        <spf>{}</spf>\n",
+                pass_fail(row.spf_aligned)
+            ));
+            for reason in &row.reasons {
+                xml.push_str(&format!(
+                    "        <reason>\n          <type>{}</type>\n        </reason>\n",
+                    reason
+                ));
+            }
+            xml.push_str("      </policy_evaluated>\n    </row>\n");
+
+            xml.push_str("    <identifiers>\n");
+            xml.push_str(&format!(
+                "      <header_from>{}</header_from>\n",
+                escape(&row.header_from)
+            ));
+            xml.push_str("    </identifiers>\n");
+
+            xml.push_str("    <auth_results>\n");
+            for dkim in &row.dkim {
+                xml.push_str(&format!(
+                    "      <dkim>\n        <domain>{}</domain>\n        <selector>{}</selector>\n        <result>{}</result>\n      </dkim>\n",
+                    escape(&dkim.domain),
+                    escape(&dkim.selector),
+                    dkim.result
+                ));
+            }
+            // O esquema exige um resultado SPF
+            let (domain, scope, result) = match &row.spf {
+                Some(spf) => (spf.domain.as_str(), spf.scope.as_str(), spf.result.as_str()),
+                None => (row.header_from.as_str(), "mfrom", "none"),
+            };
+            xml.push_str(&format!(
+                "      <spf>\n        <domain>{}</domain>\n        <scope>{}</scope>\n        <result>{}</result>\n      </spf>\n",
+                escape(domain),
+                scope,
+                result
+            ));
+            xml.push_str("    </auth_results>\n  </record>\n");
+        }
+
+        xml.push_str("</feedback>\n");
+        xml
+    }
+
+    // Mensagem com o XML anexado (RFC 7489 §7.2.1.1)
+    fn message(&self, hostname: &str, to: &str, xml: &str) -> String {
+        let (begin, end) = self.range();
+        let boundary = format!("{}/{}", Uuid::new_v4().simple(), hostname);
+        let filename = format!("{}!{}!{}!{}.xml", hostname, self.domain, begin, end);
+
+        let mut msg = String::new();
+        msg.push_str(&format!("From: <{}>\r\n", self.email));
+        msg.push_str(&format!("To: <{}>\r\n", to));
+        msg.push_str(&format!(
+            "Subject: Report Domain: {} Submitter: {} Report-ID: <{}>\r\n",
+            self.domain, self.org_name, self.id
+        ));
+        msg.push_str(&format!("Date: {}\r\n", Utc::now().to_rfc2822()));
+        msg.push_str(&format!(
+            "Message-ID: <{}@{}>\r\n",
+            Uuid::new_v4(),
+            hostname
+        ));
+        msg.push_str("Auto-Submitted: auto-generated\r\n");
+        msg.push_str("MIME-Version: 1.0\r\n");
+        msg.push_str(&format!(
+            "Content-Type: multipart/mixed; boundary=\"{}\"\r\n",
+            boundary
+        ));
+        msg.push_str("\r\n");
+
+        msg.push_str(&format!("--{}\r\n", boundary));
+        msg.push_str("Content-Type: text/plain; charset=utf-8\r\n\r\n");
+        msg.push_str(&format!(
+            "DMARC aggregate report for {} from {}.\r\n\r\n",
+            self.domain, self.org_name
+        ));
+
+        msg.push_str(&format!("--{}\r\n", boundary));
+        msg.push_str(&format!(
+            "Content-Type: application/xml; name=\"{}\"\r\n",
+            filename
+        ));
+        msg.push_str(&format!(
+            "Content-Disposition: attachment; filename=\"{}\"\r\n",
+            filename
+        ));
+        msg.push_str("Content-Transfer-Encoding: base64\r\n\r\n");
+        let encoded = B64.encode(xml);
+        for chunk in encoded.as_bytes().chunks(76) {
+            msg.push_str(std::str::from_utf8(chunk).unwrap_or_default());
+            msg.push_str("\r\n");
+        }
+        msg.push_str(&format!("--{}--\r\n", boundary));
+
+        msg
+    }
+}
+
+fn day_file(dir: &Path, day: NaiveDate) -> PathBuf {
+    dir.join(format!("{}.{}", day.format("%Y-%m-%d"), EXTENSION))
+}
+
+fn non_empty(value: &str) -> Option<&str> {
+    Some(value.trim()).filter(|v| !v.is_empty())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,27 @@
+use std::{error::Error, fmt};
+
+#[derive(Debug)]
+pub enum DnsError {
+    // NXDOMAIN ou nenhum registro do tipo pedido
+    NotFoundError(String),
+    // Falha temporária: timeout, SERVFAIL etc.
+    LookupError(String),
+}
+
+impl fmt::Display for DnsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DnsError::NotFoundError(name) => write!(f, "Nenhum registro para {}", name),
+            DnsError::LookupError(e) => write!(f, "Erro na consulta DNS: {}", e),
+        }
+    }
+}
+
+impl Error for DnsError {}
+
+// Conversões automáticas
+impl From<hickory_resolver::net::NetError> for DnsError {
+    fn from(err: hickory_resolver::net::NetError) -> Self {
+        DnsError::LookupError(err.to_string())
+    }
+}
@@ -0,0 +1,170 @@
+pub mod dns_error;
+pub mod static_resolver;
+
+use std::{
+    future::Future,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    pin::Pin,
+    sync::{Arc, OnceLock},
+};
+
+use hickory_resolver::{
+    TokioResolver,
+    net::NetError,
+    proto::rr::{Name, RData, Record},
+};
+
+use crate::{
+    config::dns_config::DnsConfig,
+    dns::{dns_error::DnsError, static_resolver::StaticResolver},
+};
+
+pub type Lookup<'a, T> = Pin<Box<dyn Future<Output = Result<T, DnsError>> + Send + 'a>>;
+
+// Consultas usadas na autenticação de mensagens (DKIM, SPF, DMARC). Em
+// produção é o DNS; o StaticResolver responde com registros fixos.
+pub trait Resolver: Send + Sync {
+    // Um item por registro TXT, com as strings já concatenadas (RFC 6376 §3.6.2.2)
+    fn txt<'a>(&'a self, name: &'a str) -> Lookup<'a, Vec<String>>;
+    fn a<'a>(&'a self, name: &'a str) -> Lookup<'a, Vec<Ipv4Addr>>;
+    fn aaaa<'a>(&'a self, name: &'a str) -> Lookup<'a, Vec<Ipv6Addr>>;
+    // Servidores MX em ordem de preferência
+    fn mx<'a>(&'a self, name: &'a str) -> Lookup<'a, Vec<String>>;
+    fn ptr<'a>(&'a self, ip: IpAddr) -> Lookup<'a, Vec<String>>;
+}
+
+pub struct DnsResolver {
+    inner: TokioResolver,
+}
+
+impl DnsResolver {
+    pub fn new() -> Result<Self, DnsError> {
+        Ok(Self {
+            inner: TokioResolver::builder_tokio()?.build()?,
+        })
+    }
+}
+
+impl Resolver for DnsResolver {
+    fn txt<'a>(&'a self, name: &'a str) -> Lookup<'a, Vec<String>> {
+        Box::pin(async move {
+            let records = answers(name, self.inner.txt_lookup(fqdn(name)).await)?;
+            Ok(records
+                .iter()
+                .filter_map(|r| match &r.data {
+                    RData::TXT(txt) => Some(
+                        txt.txt_data
+                            .iter()
+                            .map(|s| String::from_utf8_lossy(s))
+                            .collect::<String>(),
+                    ),
+                    _ => None,
+                })
+                .collect())
+        })
+    }
+
+    fn a<'a>(&'a self, name: &'a str) -> Lookup<'a, Vec<Ipv4Addr>> {
+        Box::pin(async move {
+            let records = answers(name, self.inner.ipv4_lookup(fqdn(name)).await)?;
+            Ok(records
+                .iter()
+                .filter_map(|r| match &r.data {
+                    RData::A(a) => Some(a.0),
+                    _ => None,
+                })
+                .collect())
+        })
+    }
+
+    fn aaaa<'a>(&'a self, name: &'a str) -> Lookup<'a, Vec<Ipv6Addr>> {
+        Box::pin(async move {
+            let records = answers(name, self.inner.ipv6_lookup(fqdn(name)).await)?;
+            Ok(records
+                .iter()
+                .filter_map(|r| match &r.data {
+                    RData::AAAA(aaaa) => Some(aaaa.0),
+                    _ => None,
+                })
+                .collect())
+        })
+    }
+
+    fn mx<'a>(&'a self, name: &'a str) -> Lookup<'a, Vec<String>> {
+        Box::pin(async move {
+            let records = answers(name, self.inner.mx_lookup(fqdn(name)).await)?;
+            let mut exchanges: Vec<_> = records
+                .iter()
+                .filter_map(|r| match &r.data {
+                    RData::MX(mx) => Some((mx.preference, host_name(&mx.exchange))),
+                    _ => None,
+                })
+                .collect();
+            exchanges.sort_by_key(|(preference, _)| *preference);
+            Ok(exchanges.into_iter().map(|(_, host)| host).collect())
+        })
+    }
+
+    fn ptr<'a>(&'a self, ip: IpAddr) -> Lookup<'a, Vec<String>> {
+        Box::pin(async move {
+            let name = ip.to_string();
+            let records = answers(&name, self.inner.reverse_lookup(ip).await)?;
+            Ok(records
+                .iter()
+                .filter_map(|r| match &r.data {
+                    RData::PTR(ptr) => Some(host_name(&ptr.0)),
+                    _ => None,
+                })
+                .collect())
+        })
+    }
+}
+
+fn fqdn(name: &str) -> String {
+    format!("{}.", name.trim_end_matches('.'))
+}
+
+fn host_name(name: &Name) -> String {
+    name.to_ascii().trim_end_matches('.').to_ascii_lowercase()
+}
+
+// NXDOMAIN e respostas sem registros viram NotFoundError; o resto é temporário
+fn answers(
+    name: &str,
+    lookup: Result<hickory_resolver::lookup::Lookup, NetError>,
+) -> Result<Vec<Record>, DnsError> {
+    match lookup {
+        Ok(lookup) => Ok(lookup.answers().to_vec()),
+        Err(e) if e.is_nx_domain() || e.is_no_records_found() => {
+            Err(DnsError::NotFoundError(name.to_string()))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+static SYSTEM: OnceLock<Arc<DnsResolver>> = OnceLock::new();
+
+// Resolvedor usado pelo servidor: sempre o DNS do sistema
+pub fn resolver() -> Result<Arc<dyn Resolver>, DnsError> {
+    let system: Arc<dyn Resolver> = match SYSTEM.get() {
+        Some(system) => system.clone(),
+        None => {
+            let system = Arc::new(DnsResolver::new()?);
+            SYSTEM.get_or_init(|| system).clone()
+        }
+    };
+    Ok(system)
+}
+
+// Para o `dkim check`: os registros fixos de [dns] antes do DNS do sistema.
+// O servidor não usa esses registros, para que um TXT esquecido na
+// configuração não mude o resultado do DKIM, SPF e DMARC em produção.
+pub fn resolver_with_overrides(config: &DnsConfig) -> Result<Arc<dyn Resolver>, DnsError> {
+    let system = resolver()?;
+    if config.txt_records.is_empty() {
+        return Ok(system);
+    }
+    Ok(Arc::new(
+        StaticResolver::new(&config.txt_records).with_fallback(system),
+    ))
+}
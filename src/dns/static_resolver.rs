@@ -0,0 +1,83 @@
+use std::{
+    collections::BTreeMap,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    sync::Arc,
+};
+
+use crate::dns::{Lookup, Resolver, dns_error::DnsError};
+
+// Respostas fixas por nome. Nomes sem registro vão para o resolvedor de
+// apoio, quando houver; sem ele, a consulta não encontra nada.
+pub struct StaticResolver {
+    txt: BTreeMap<String, Vec<String>>,
+    fallback: Option<Arc<dyn Resolver>>,
+}
+
+impl StaticResolver {
+    pub fn new(txt: &BTreeMap<String, Vec<String>>) -> Self {
+        Self {
+            txt: txt
+                .iter()
+                .map(|(name, records)| (normalize(name), records.clone()))
+                .collect(),
+            fallback: None,
+        }
+    }
+
+    pub fn with_fallback(mut self, fallback: Arc<dyn Resolver>) -> Self {
+        self.fallback = Some(fallback);
+        self
+    }
+}
+
+impl Resolver for StaticResolver {
+    fn txt<'a>(&'a self, name: &'a str) -> Lookup<'a, Vec<String>> {
+        Box::pin(async move {
+            if let Some(records) = self.txt.get(&normalize(name)) {
+                return Ok(records.clone());
+            }
+            match &self.fallback {
+                Some(fallback) => fallback.txt(name).await,
+                None => Err(DnsError::NotFoundError(name.to_string())),
+            }
+        })
+    }
+
+    // Os demais tipos só existem no resolvedor de apoio
+    fn a<'a>(&'a self, name: &'a str) -> Lookup<'a, Vec<Ipv4Addr>> {
+        match &self.fallback {
+            Some(fallback) => fallback.a(name),
+            None => not_found(name),
+        }
+    }
+
+    fn aaaa<'a>(&'a self, name: &'a str) -> Lookup<'a, Vec<Ipv6Addr>> {
+        match &self.fallback {
+            Some(fallback) => fallback.aaaa(name),
+            None => not_found(name),
+        }
+    }
+
+    fn mx<'a>(&'a self, name: &'a str) -> Lookup<'a, Vec<String>> {
+        match &self.fallback {
+            Some(fallback) => fallback.mx(name),
+            None => not_found(name),
+        }
+    }
+
+    fn ptr<'a>(&'a self, ip: IpAddr) -> Lookup<'a, Vec<String>> {
+        match &self.fallback {
+            Some(fallback) => fallback.ptr(ip),
+            None => not_found(&ip.to_string()),
+        }
+    }
+}
+
+fn not_found<'a, T: Send + 'a>(name: &str) -> Lookup<'a, T> {
+    let name = name.to_string();
+    Box::pin(async move { Err(DnsError::NotFoundError(name)) })
+}
+
+fn normalize(name: &str) -> String {
+    name.trim_end_matches('.').to_ascii_lowercase()
+}
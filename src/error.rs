@@ -1,10 +1,12 @@
-use std::fmt;
 use std::error::Error;
+use std::fmt;
 
 #[derive(Debug)]
 pub enum AppError {
     ConfigError(crate::config::config_error::ConfigError),
     IoError(std::io::Error),
+    QueueError(crate::queue::queue_error::QueueError),
+    StartupError(anyhow::Error),
 }
 
 impl fmt::Display for AppError {
@@ -12,6 +14,8 @@ impl fmt::Display for AppError {
         match self {
             AppError::ConfigError(e) => write!(f, "Erro de configuração: {}", e),
             AppError::IoError(e) => write!(f, "Erro de I/O: {}", e),
+            AppError::QueueError(e) => write!(f, "Erro na fila: {}", e),
+            AppError::StartupError(e) => write!(f, "Erro na inicialização: {}", e),
         }
     }
 }
@@ -29,3 +33,15 @@ impl From<std::io::Error> for AppError {
         AppError::IoError(err)
     }
 }
+
+impl From<crate::queue::queue_error::QueueError> for AppError {
+    fn from(err: crate::queue::queue_error::QueueError) -> Self {
+        AppError::QueueError(err)
+    }
+}
+
+impl From<anyhow::Error> for AppError {
+    fn from(err: anyhow::Error) -> Self {
+        AppError::StartupError(err)
+    }
+}
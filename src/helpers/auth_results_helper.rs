@@ -0,0 +1,70 @@
+// Resultados para o header Authentication-Results (RFC 8601). São acumulados
+// durante a sessão (SPF no MAIL FROM, DKIM depois do DATA).
+#[derive(Default)]
+pub struct AuthResults {
+    results: Vec<String>,
+}
+
+impl AuthResults {
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    // `properties` são pares como ("header.d", "example.com")
+    pub fn add(
+        &mut self,
+        method: &str,
+        result: &str,
+        reason: Option<&str>,
+        properties: &[(&str, &str)],
+    ) {
+        let mut text = format!("{}={}", method, result);
+        if let Some(reason) = reason {
+            text.push_str(&format!(
+                " reason=\"{}\"",
+                reason.replace('\\', "\\\\").replace('"', "\\\"")
+            ));
+        }
+        for (name, value) in properties {
+            text.push_str(&format!(" {}={}", name, value));
+        }
+        self.results.push(text);
+    }
+
+    // Header completo, com um resultado por linha e sem o CRLF final
+    pub fn header(&self, authserv_id: &str) -> String {
+        if self.results.is_empty() {
+            return format!("Authentication-Results: {}; none", authserv_id);
+        }
+        format!(
+            "Authentication-Results: {};\r\n\t{}",
+            authserv_id,
+            self.results.join(";\r\n\t")
+        )
+    }
+}
+
+// Remove os Authentication-Results que se dizem do nosso servidor: só nós
+// podemos ter gerado esses resultados (RFC 8601 §5)
+pub fn strip_forged(raw: &str, authserv_id: &str) -> String {
+    let header_end = raw.find("\r\n\r\n").map(|pos| pos + 2).unwrap_or(raw.len());
+    let (headers, rest) = raw.split_at(header_end);
+
+    let mut out = String::with_capacity(raw.len());
+    let mut skipping = false;
+    for line in headers.split_inclusive('\n') {
+        if !line.starts_with([' ', '\t']) {
+            skipping = line
+                .split_once(':')
+                .filter(|(name, _)| name.trim().eq_ignore_ascii_case("authentication-results"))
+                .and_then(|(_, value)| value.split(';').next())
+                .and_then(|id| id.split_whitespace().next())
+                .is_some_and(|id| id.eq_ignore_ascii_case(authserv_id));
+        }
+        if !skipping {
+            out.push_str(line);
+        }
+    }
+    out.push_str(rest);
+    out
+}
@@ -1,5 +1,96 @@
 pub fn extract_from_angle_brackets(text: &str) -> Option<&str> {
     let start = text.find('<')? + 1;
-    let end = text.find('>')?;
+    let end = start + text[start..].find('>')?;
     Some(&text[start..end])
-}
\ No newline at end of file
+}
+
+// local@domínio, sem espaços nem partes vazias
+pub fn is_valid_address(address: &str) -> bool {
+    address
+        .rsplit_once('@')
+        .is_some_and(|(local, domain)| !local.is_empty() && !domain.is_empty())
+        && !address.contains(|c: char| c.is_whitespace() || c.is_control() || c == '<')
+}
+
+// Normaliza as quebras de linha para CRLF, como chegam pelo DATA
+pub fn to_crlf(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + text.len() / 40);
+    for line in text.split_inclusive('\n') {
+        let content = line.trim_end_matches(['\r', '\n']);
+        out.push_str(content);
+        out.push_str("\r\n");
+    }
+    out
+}
+
+// Remove todas as ocorrências de um header, com as linhas de continuação
+pub fn strip_header(headers: &str, name: &str) -> String {
+    let mut out = String::with_capacity(headers.len());
+    let mut skipping = false;
+    for line in headers.split_inclusive("\r\n") {
+        if !line.starts_with([' ', '\t']) {
+            skipping = line
+                .split_once(':')
+                .is_some_and(|(n, _)| n.trim().eq_ignore_ascii_case(name));
+        }
+        if !skipping {
+            out.push_str(line);
+        }
+    }
+    out
+}
+
+// Endereços de um header como To/Cc/Bcc (RFC 5322 §3.4), sem os nomes.
+// Vírgulas dentro de aspas, comentários ou <> não separam endereços.
+pub fn parse_address_list(value: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut current = String::new();
+    let (mut quoted, mut comment, mut angle) = (false, 0, false);
+
+    for c in value.chars() {
+        match c {
+            '"' if comment == 0 => quoted = !quoted,
+            '(' if !quoted => comment += 1,
+            ')' if !quoted && comment > 0 => comment -= 1,
+            '<' if !quoted && comment == 0 => angle = true,
+            '>' if !quoted && comment == 0 => angle = false,
+            ',' if !quoted && comment == 0 && !angle => {
+                items.push(std::mem::take(&mut current));
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    items.push(current);
+
+    items
+        .iter()
+        .filter_map(|item| {
+            let angle = item
+                .split_once('<')
+                .and_then(|(_, rest)| rest.split_once('>'))
+                .map(|(addr, _)| addr);
+            let addr = match angle {
+                Some(addr) => addr.trim(),
+                // Sem <>: remove um eventual comentário, como em "a@b (Nome)"
+                None => item.split('(').next().unwrap_or_default().trim(),
+            };
+            // Grupos ("lista: a@b;") não são expandidos além do primeiro nível
+            let addr = addr.rsplit(':').next().unwrap_or_default();
+            let addr = addr.trim_end_matches(';').trim();
+            (!addr.is_empty()).then(|| addr.to_string())
+        })
+        .collect()
+}
+
+// Separa o subendereço (RFC 5233): "ana+trabalho" -> ("ana", Some("trabalho"))
+pub fn split_detail<'a>(local_part: &'a str, delimiter: &str) -> (&'a str, Option<&'a str>) {
+    if delimiter.is_empty() {
+        return (local_part, None);
+    }
+    match local_part.split_once(delimiter) {
+        Some((user, detail)) if !user.is_empty() => (user, Some(detail)),
+        _ => (local_part, None),
+    }
+}
@@ -1 +1,3 @@
-pub mod email_helper;
\ No newline at end of file
+pub mod auth_results_helper;
+pub mod email_helper;
+pub mod trace_helper;
@@ -0,0 +1,101 @@
+use std::net::IpAddr;
+
+use chrono::Utc;
+
+use crate::helpers::email_helper;
+
+// Dados de um header Received (RFC 5321 §4.4)
+pub struct Received<'a> {
+    // Domínio do HELO/EHLO, como informado pelo cliente
+    pub helo: Option<&'a str>,
+    pub ip: Option<IpAddr>,
+    pub by: &'a str,
+    // Tipo do protocolo (RFC 3848); None na injeção local sem SMTP
+    pub protocol: Option<&'static str>,
+    pub id: &'a str,
+    // Só com um destinatário, para não expor os demais
+    pub recipient: Option<&'a str>,
+}
+
+impl Received<'_> {
+    // Header completo, sem o CRLF final
+    pub fn header(&self) -> String {
+        let mut header = String::from("Received:");
+        let literal = self.ip.map(address_literal);
+        match (self.helo.map(sanitize).filter(|h| !h.is_empty()), &literal) {
+            (Some(helo), Some(literal)) => {
+                header.push_str(&format!(" from {} ({})\r\n\t", helo, literal))
+            }
+            (None, Some(literal)) => {
+                header.push_str(&format!(" from {} ({})\r\n\t", literal, literal))
+            }
+            (Some(helo), None) => header.push_str(&format!(" from {}\r\n\t", helo)),
+            (None, None) => header.push(' '),
+        }
+
+        header.push_str(&format!("by {}", self.by));
+        if let Some(protocol) = self.protocol {
+            header.push_str(&format!(" with {}", protocol));
+        }
+        header.push_str(&format!(" id {}", self.id));
+        if let Some(recipient) = self.recipient {
+            header.push_str(&format!("\r\n\tfor <{}>", recipient));
+        }
+        header.push_str(&format!(";\r\n\t{}", Utc::now().to_rfc2822()));
+        header
+    }
+}
+
+// RFC 3848: SMTP para HELO; ESMTP com S para TLS e A para AUTH
+pub fn protocol(esmtp: bool, tls: bool, authenticated: bool) -> &'static str {
+    match (esmtp, tls, authenticated) {
+        (false, _, _) => "SMTP",
+        (true, false, false) => "ESMTP",
+        (true, true, false) => "ESMTPS",
+        (true, false, true) => "ESMTPA",
+        (true, true, true) => "ESMTPSA",
+    }
+}
+
+// Quantidade de headers Received, usada para detectar loops (RFC 5321 §6.3)
+pub fn count_received(raw: &str) -> usize {
+    raw.split("\r\n")
+        .take_while(|line| !line.is_empty())
+        .filter(|line| {
+            line.split_once(':')
+                .is_some_and(|(name, _)| name.trim().eq_ignore_ascii_case("received"))
+        })
+        .count()
+}
+
+// Return-Path com o remetente do envelope, acrescentado na entrega final.
+// Um Return-Path que já esteja na mensagem é descartado (RFC 5321 §4.4).
+pub fn with_return_path(raw: &str, sender: &str) -> String {
+    let header_end = raw.find("\r\n\r\n").map(|pos| pos + 2).unwrap_or(raw.len());
+    let (headers, rest) = raw.split_at(header_end);
+
+    format!(
+        "Return-Path: <{}>\r\n{}{}",
+        sender,
+        email_helper::strip_header(headers, "return-path"),
+        rest
+    )
+}
+
+fn address_literal(ip: IpAddr) -> String {
+    match ip {
+        IpAddr::V4(ip) => format!("[{}]", ip),
+        IpAddr::V6(ip) => format!("[IPv6:{}]", ip),
+    }
+}
+
+// O HELO vem do cliente: só a primeira palavra, sem caracteres que
+// quebrariam o header
+fn sanitize(helo: &str) -> String {
+    helo.split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| c.is_ascii_graphic() && !matches!(c, '(' | ')' | ';'))
+        .collect()
+}
@@ -0,0 +1,68 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const SUBDIRS: [&str; 3] = ["tmp", "new", "cur"];
+
+// Entregas feitas por este processo, para os nomes únicos
+static DELIVERIES: AtomicU64 = AtomicU64::new(0);
+
+pub fn exists(dir: &Path) -> bool {
+    SUBDIRS.iter().all(|sub| dir.join(sub).is_dir())
+}
+
+// Pastas do Maildir++ levam o arquivo maildirfolder
+pub fn create(dir: &Path, folder: bool) -> io::Result<()> {
+    for sub in SUBDIRS {
+        fs::create_dir_all(dir.join(sub))?;
+    }
+    if folder {
+        fs::File::create(dir.join("maildirfolder"))?;
+    }
+    Ok(())
+}
+
+// Nome único (cr.yp.to/proto/maildir.html), com o tamanho em S= como no
+// Maildir++ para que a quota possa ser recalculada sem abrir os arquivos
+pub fn unique_name(hostname: &str, size: usize) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let host = hostname.replace('/', "\\057").replace(':', "\\072");
+    format!(
+        "{}.M{}P{}Q{}.{},S={}",
+        now.as_secs(),
+        now.subsec_micros(),
+        std::process::id(),
+        DELIVERIES.fetch_add(1, Ordering::Relaxed),
+        host,
+        size
+    )
+}
+
+// Grava em tmp/ e só move para new/ depois que os dados estão no disco, para
+// que um leitor nunca veja uma mensagem pela metade
+pub fn deliver(dir: &Path, hostname: &str, message: &[u8]) -> io::Result<PathBuf> {
+    let name = unique_name(hostname, message.len());
+    let tmp_path = dir.join("tmp").join(&name);
+
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&tmp_path)?;
+    if let Err(e) = file.write_all(message).and_then(|_| file.sync_all()) {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(e);
+    }
+    drop(file);
+
+    let new_path = dir.join("new").join(&name);
+    fs::rename(&tmp_path, &new_path)?;
+    fs::File::open(dir.join("new"))?.sync_all()?;
+
+    Ok(new_path)
+}
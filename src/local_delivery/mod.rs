@@ -0,0 +1,168 @@
+pub mod maildir;
+pub mod quota;
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{Context, Result};
+
+use crate::{
+    config::{Config, local_delivery_config::LocalDeliveryConfig},
+    helpers::{email_helper, trace_helper},
+    local_delivery::quota::{Quota, QuotaCheck},
+    queue::models::DeliveryJob,
+    recipients::Recipients,
+    smtp_client::delivery_result::DeliveryResult,
+};
+
+// Entrega final nas Maildirs dos local_domains, com o mesmo resultado por
+// destinatário que o SmtpClient
+pub struct LocalDelivery {
+    config: Arc<Config>,
+    recipients: Arc<Recipients>,
+}
+
+impl LocalDelivery {
+    pub fn new(config: Arc<Config>, recipients: Arc<Recipients>) -> Self {
+        Self { config, recipients }
+    }
+
+    pub async fn deliver(&self, job: &DeliveryJob) -> Result<DeliveryResult> {
+        let config = self.config.clone();
+        let recipients = self.recipients.clone();
+        let job = job.clone();
+        // Só I/O de arquivos, bloqueante
+        tokio::task::spawn_blocking(move || deliver(&config, &recipients, &job)).await?
+    }
+}
+
+fn deliver(config: &Config, recipients: &Recipients, job: &DeliveryJob) -> Result<DeliveryResult> {
+    let local = &config.local_delivery;
+    let (local_part, domain) = job.to_addr.rsplit_once('@').unwrap_or((&job.to_addr, ""));
+    let (user, detail) = email_helper::split_detail(local_part, &local.recipient_delimiter);
+    let user = user.to_ascii_lowercase();
+
+    if !is_safe_name(&user) || !is_safe_name(domain) {
+        return Ok(DeliveryResult::Permanent {
+            smtp_code: 553,
+            message: format!("Endereço {} inválido para entrega local", job.to_addr),
+        });
+    }
+
+    // Caixas do mapa virtual podem ter a própria Maildir e são criadas na
+    // primeira entrega
+    let listed = recipients.mailbox_path(&job.to_addr);
+    let template = listed
+        .as_deref()
+        .filter(|path| !path.is_empty())
+        .unwrap_or(&local.maildir);
+    let maildir = expand_path(template, &user, domain);
+    if !maildir::exists(&maildir) {
+        if !local.autocreate && listed.is_none() {
+            return Ok(DeliveryResult::Permanent {
+                smtp_code: 550,
+                message: format!("Usuário {} desconhecido", job.to_addr),
+            });
+        }
+        maildir::create(&maildir, false)
+            .with_context(|| format!("Erro ao criar {}", maildir.display()))?;
+    }
+
+    if already_delivered(&job.raw_message, &job.to_addr) {
+        return Ok(DeliveryResult::Permanent {
+            smtp_code: 554,
+            message: format!("Loop detectado: {} já recebeu esta mensagem", job.to_addr),
+        });
+    }
+
+    let message = local_copy(job);
+    let size = message.len() as u64;
+    let default = Quota {
+        bytes: local.quota_bytes,
+        messages: local.quota_messages,
+    };
+    match quota::check(&maildir, default, size)
+        .with_context(|| format!("Erro ao calcular a quota de {}", maildir.display()))?
+    {
+        QuotaCheck::Ok => {}
+        QuotaCheck::Full => {
+            return Ok(DeliveryResult::Transient {
+                smtp_code: 452,
+                message: format!("Caixa de {} cheia", job.to_addr),
+            });
+        }
+        QuotaCheck::TooLarge => {
+            return Ok(DeliveryResult::Permanent {
+                smtp_code: 552,
+                message: format!("Mensagem maior que a quota de {}", job.to_addr),
+            });
+        }
+    }
+
+    let target = folder(local, &maildir, detail)?;
+    let path = maildir::deliver(&target, &config.server.hostname, message.as_bytes())
+        .with_context(|| format!("Erro ao gravar em {}", target.display()))?;
+    if let Err(e) = quota::record(&maildir, size) {
+        tracing::warn!("[{}] Erro ao atualizar a quota: {}", job.id, e);
+    }
+
+    Ok(DeliveryResult::Delivered {
+        smtp_code: 250,
+        message: format!("Entregue em {}", path.display()),
+    })
+}
+
+// Caminho de um usuário em um modelo com {domain} e {user}
+pub fn expand_path(template: &str, user: &str, domain: &str) -> PathBuf {
+    PathBuf::from(
+        template
+            .replace("{domain}", &domain.to_ascii_lowercase())
+            .replace("{user}", user),
+    )
+}
+
+// Pasta do subendereço (user+pasta), ou a INBOX quando não há pasta, o nome
+// não é aceitável ou a pasta não existe e autocreate está desligado
+fn folder(local: &LocalDeliveryConfig, maildir: &Path, detail: Option<&str>) -> Result<PathBuf> {
+    let Some(detail) = detail.filter(|d| is_safe_name(d)) else {
+        return Ok(maildir.to_path_buf());
+    };
+
+    let dir = maildir.join(format!(".{}", detail));
+    if maildir::exists(&dir) {
+        return Ok(dir);
+    }
+    if !local.autocreate {
+        return Ok(maildir.to_path_buf());
+    }
+    maildir::create(&dir, true).with_context(|| format!("Erro ao criar {}", dir.display()))?;
+    Ok(dir)
+}
+
+// Nomes que vão para o caminho: sem separadores nem ponto inicial
+pub fn is_safe_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && !name.contains(['/', '\\', '\0'])
+        && !name.chars().any(|c| c.is_control())
+}
+
+// Um Delivered-To igual ao destinatário indica que a mensagem já passou por
+// aqui (por exemplo, um encaminhamento que volta para a mesma caixa)
+pub fn already_delivered(raw: &str, recipient: &str) -> bool {
+    raw.split("\r\n")
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| line.split_once(':'))
+        .any(|(name, value)| {
+            name.trim().eq_ignore_ascii_case("delivered-to")
+                && value.trim().eq_ignore_ascii_case(recipient)
+        })
+}
+
+// Return-Path e Delivered-To no topo e quebras de linha LF, como nas Maildirs
+pub fn local_copy(job: &DeliveryJob) -> String {
+    let raw = format!("Delivered-To: {}\r\n{}", job.to_addr, job.raw_message);
+    trace_helper::with_return_path(&raw, &job.from_addr).replace("\r\n", "\n")
+}
@@ -0,0 +1,165 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+const MAILDIRSIZE: &str = "maildirsize";
+// Como no Maildir++, um maildirsize maior que isso é recalculado
+const MAX_MAILDIRSIZE: u64 = 5120;
+
+// Limites de uma Maildir; 0 é sem limite
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Quota {
+    pub bytes: u64,
+    pub messages: u64,
+}
+
+impl Quota {
+    fn is_unlimited(&self) -> bool {
+        self.bytes == 0 && self.messages == 0
+    }
+
+    // Definição do Maildir++: "1000000S,1000C"
+    fn parse(line: &str) -> Quota {
+        let mut quota = Quota::default();
+        for part in line.split(',') {
+            let part = part.trim();
+            if let Some(bytes) = part.strip_suffix('S') {
+                quota.bytes = bytes.parse().unwrap_or(0);
+            } else if let Some(messages) = part.strip_suffix('C') {
+                quota.messages = messages.parse().unwrap_or(0);
+            }
+        }
+        quota
+    }
+
+    fn definition(&self) -> String {
+        let mut parts = Vec::new();
+        if self.bytes > 0 {
+            parts.push(format!("{}S", self.bytes));
+        }
+        if self.messages > 0 {
+            parts.push(format!("{}C", self.messages));
+        }
+        parts.join(",")
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum QuotaCheck {
+    Ok,
+    // Cabe depois que o usuário liberar espaço
+    Full,
+    // Maior que a própria quota: nunca vai caber
+    TooLarge,
+}
+
+pub fn check(maildir: &Path, default: Quota, size: u64) -> io::Result<QuotaCheck> {
+    let Some((quota, bytes, messages)) = usage(maildir, default)? else {
+        return Ok(QuotaCheck::Ok);
+    };
+
+    if quota.bytes > 0 && size > quota.bytes {
+        return Ok(QuotaCheck::TooLarge);
+    }
+    let over_bytes = quota.bytes > 0 && bytes + size > quota.bytes;
+    let over_messages = quota.messages > 0 && messages + 1 > quota.messages;
+    if over_bytes || over_messages {
+        return Ok(QuotaCheck::Full);
+    }
+    Ok(QuotaCheck::Ok)
+}
+
+// Soma a mensagem entregue ao maildirsize, quando há quota
+pub fn record(maildir: &Path, size: u64) -> io::Result<()> {
+    let path = maildir.join(MAILDIRSIZE);
+    if !path.exists() {
+        return Ok(());
+    }
+    let mut file = fs::OpenOptions::new().append(true).open(path)?;
+    file.write_all(format!("{} 1\n", size).as_bytes())
+}
+
+// Quota e uso (bytes, mensagens) pelo maildirsize, recalculado quando falta
+// ou está grande demais. None quando não há quota.
+fn usage(maildir: &Path, default: Quota) -> io::Result<Option<(Quota, u64, u64)>> {
+    let path = maildir.join(MAILDIRSIZE);
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => Some(content),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e),
+    };
+
+    // A definição gravada no maildirsize (por exemplo, pelo Dovecot) vale
+    // mais que a padrão
+    let quota = content
+        .as_deref()
+        .and_then(|c| c.lines().next())
+        .map(Quota::parse)
+        .filter(|q| !q.is_unlimited())
+        .unwrap_or(default);
+    if quota.is_unlimited() {
+        return Ok(None);
+    }
+
+    if let Some(content) = content
+        && content.len() as u64 <= MAX_MAILDIRSIZE
+    {
+        let (mut bytes, mut messages) = (0i64, 0i64);
+        for line in content.lines().skip(1) {
+            let mut fields = line.split_whitespace();
+            bytes += fields.next().and_then(|f| f.parse().ok()).unwrap_or(0);
+            messages += fields.next().and_then(|f| f.parse().ok()).unwrap_or(0);
+        }
+        return Ok(Some((quota, bytes.max(0) as u64, messages.max(0) as u64)));
+    }
+
+    let (bytes, messages) = scan(maildir)?;
+    let tmp_path = maildir.join(format!("tmp/{}.{}", MAILDIRSIZE, std::process::id()));
+    fs::write(
+        &tmp_path,
+        format!("{}\n{} {}\n", quota.definition(), bytes, messages),
+    )?;
+    fs::rename(&tmp_path, &path)?;
+
+    Ok(Some((quota, bytes, messages)))
+}
+
+// Uso real: new/ e cur/ da INBOX e de todas as pastas
+fn scan(maildir: &Path) -> io::Result<(u64, u64)> {
+    let mut dirs = vec![maildir.to_path_buf()];
+    for entry in fs::read_dir(maildir)? {
+        let entry = entry?;
+        if entry.file_name().to_string_lossy().starts_with('.') && entry.path().is_dir() {
+            dirs.push(entry.path());
+        }
+    }
+
+    let (mut bytes, mut messages) = (0, 0);
+    for dir in dirs {
+        for sub in ["new", "cur"] {
+            let entries = match fs::read_dir(dir.join(sub)) {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            };
+            for entry in entries {
+                let entry = entry?;
+                bytes += size_from_name(&entry.file_name().to_string_lossy())
+                    .map(Ok)
+                    .unwrap_or_else(|| entry.metadata().map(|m| m.len()))?;
+                messages += 1;
+            }
+        }
+    }
+    Ok((bytes, messages))
+}
+
+// ",S=<tamanho>" no nome, antes das flags (":2,...")
+fn size_from_name(name: &str) -> Option<u64> {
+    let name = name.split(':').next().unwrap_or_default();
+    name.split(',')
+        .find_map(|part| part.strip_prefix("S="))
+        .and_then(|size| size.parse().ok())
+}
@@ -0,0 +1,86 @@
+use std::{
+    collections::HashMap,
+    io::Write,
+    sync::{Mutex, OnceLock},
+};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::logging::rotating_file::RotatingFile;
+
+static MAIL_LOG: OnceLock<Mutex<RotatingFile>> = OnceLock::new();
+
+pub fn init(file: RotatingFile) {
+    let _ = MAIL_LOG.set(Mutex::new(file));
+}
+
+// Uma transação SMTP concluída (aceita ou recusada após o DATA)
+#[derive(Serialize)]
+pub struct TransactionRecord<'a> {
+    pub message_id: &'a str,
+    pub peer: &'a str,
+    pub helo: Option<&'a str>,
+    pub from: &'a str,
+    pub recipients: &'a [String],
+    pub size: usize,
+    pub tls: Option<&'a str>,
+    pub verdicts: &'a HashMap<String, String>,
+    pub result: &'a str,
+    pub smtp_code: u16,
+}
+
+// Uma tentativa de entrega feita pela fila
+#[derive(Serialize)]
+pub struct DeliveryRecord<'a> {
+    pub message_id: &'a str,
+    pub job_id: &'a str,
+    pub from: &'a str,
+    pub recipient: &'a str,
+    pub attempt: u32,
+    pub result: &'a str,
+    pub smtp_code: Option<u16>,
+    pub detail: &'a str,
+    pub next_attempt_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize)]
+struct Entry<'a, T: Serialize> {
+    timestamp: DateTime<Utc>,
+    event: &'a str,
+    #[serde(flatten)]
+    record: &'a T,
+}
+
+pub fn transaction(record: &TransactionRecord) {
+    write("transaction", record);
+}
+
+pub fn delivery(record: &DeliveryRecord) {
+    write("delivery", record);
+}
+
+fn write<T: Serialize>(event: &str, record: &T) {
+    let Some(log) = MAIL_LOG.get() else {
+        return;
+    };
+
+    let entry = Entry {
+        timestamp: Utc::now(),
+        event,
+        record,
+    };
+    let mut line = match serde_json::to_vec(&entry) {
+        Ok(line) => line,
+        Err(e) => {
+            tracing::error!("Erro ao serializar registro do log de emails: {}", e);
+            return;
+        }
+    };
+    line.push(b'\n');
+
+    let mut file = log.lock().unwrap_or_else(|e| e.into_inner());
+    if let Err(e) = file.write_all(&line).and_then(|_| file.flush()) {
+        tracing::error!("Erro ao gravar no log de emails: {}", e);
+    }
+}
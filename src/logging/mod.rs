@@ -0,0 +1,65 @@
+pub mod mail_log;
+pub mod rotating_file;
+
+use std::{path::Path, sync::Mutex};
+
+use tracing_subscriber::{EnvFilter, fmt::writer::BoxMakeWriter};
+
+use crate::{
+    config::logging_config::LoggingConfig,
+    logging::rotating_file::{RotatingFile, Rotation},
+};
+
+pub fn init_tracing(config: &LoggingConfig) -> std::io::Result<()> {
+    let filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(&config.level));
+
+    let (writer, ansi) = match &config.file {
+        Some(path) => (
+            BoxMakeWriter::new(Mutex::new(open_rotating(config, path)?)),
+            false,
+        ),
+        None => (BoxMakeWriter::new(std::io::stdout), true),
+    };
+
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(writer)
+        .with_ansi(ansi);
+
+    match config.format.as_str() {
+        "json" => builder.json().init(),
+        _ => builder.pretty().init(),
+    }
+
+    if let Some(path) = &config.mail_log {
+        mail_log::init(open_rotating(config, path)?);
+    }
+
+    Ok(())
+}
+
+// Linha de comando: só avisos e erros, na saída de erro, para não misturar com
+// a saída do comando. O log de emails continua valendo quando configurado.
+pub fn init_cli(config: &LoggingConfig) -> std::io::Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::new("warn"))
+        .with_writer(std::io::stderr)
+        .without_time()
+        .init();
+
+    if let Some(path) = &config.mail_log {
+        mail_log::init(open_rotating(config, path)?);
+    }
+
+    Ok(())
+}
+
+fn open_rotating(config: &LoggingConfig, path: &str) -> std::io::Result<RotatingFile> {
+    RotatingFile::open(
+        Path::new(path),
+        Rotation::parse(&config.rotation).unwrap_or(Rotation::Daily),
+        config.max_size_mb * 1024 * 1024,
+        config.max_files,
+    )
+}
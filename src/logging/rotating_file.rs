@@ -0,0 +1,160 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Local};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rotation {
+    Hourly,
+    Daily,
+    Never,
+}
+
+impl Rotation {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "hourly" => Some(Rotation::Hourly),
+            "daily" => Some(Rotation::Daily),
+            "never" => Some(Rotation::Never),
+            _ => None,
+        }
+    }
+
+    fn period(&self, time: DateTime<Local>) -> String {
+        match self {
+            Rotation::Hourly => time.format("%Y%m%d%H").to_string(),
+            Rotation::Daily => time.format("%Y%m%d").to_string(),
+            Rotation::Never => String::new(),
+        }
+    }
+}
+
+// Arquivo de log que rotaciona por tempo e/ou tamanho. O arquivo ativo mantém
+// sempre o mesmo nome; os anteriores recebem o sufixo .AAAAMMDD-HHMMSS e só os
+// `max_files` mais recentes são mantidos.
+pub struct RotatingFile {
+    path: PathBuf,
+    rotation: Rotation,
+    max_size: u64,
+    max_files: usize,
+    file: File,
+    size: u64,
+    period: String,
+}
+
+impl RotatingFile {
+    pub fn open(
+        path: &Path,
+        rotation: Rotation,
+        max_size: u64,
+        max_files: usize,
+    ) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let metadata = file.metadata()?;
+        let modified: DateTime<Local> = metadata.modified()?.into();
+
+        let mut rotating = Self {
+            path: path.to_path_buf(),
+            rotation,
+            max_size,
+            max_files,
+            file,
+            size: metadata.len(),
+            period: rotation.period(modified),
+        };
+
+        // Arquivo de um período anterior que ficou de uma execução passada
+        if rotating.size > 0 && rotating.period != rotation.period(Local::now()) {
+            rotating.rotate()?;
+        }
+
+        Ok(rotating)
+    }
+
+    fn rotate_if_needed(&mut self, incoming: usize) -> io::Result<()> {
+        let period = self.rotation.period(Local::now());
+        let too_big = self.max_size > 0 && self.size + incoming as u64 > self.max_size;
+
+        if self.size > 0 && (period != self.period || too_big) {
+            self.rotate()?;
+        }
+        self.period = period;
+
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+
+        let stamp = Local::now().format("%Y%m%d-%H%M%S").to_string();
+        let mut target = self.rotated_path(&stamp);
+        let mut n = 1;
+        while target.exists() {
+            target = self.rotated_path(&format!("{}-{}", stamp, n));
+            n += 1;
+        }
+
+        fs::rename(&self.path, &target)?;
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.size = 0;
+
+        self.prune()
+    }
+
+    fn rotated_path(&self, suffix: &str) -> PathBuf {
+        let mut name = self.path.as_os_str().to_owned();
+        name.push(".");
+        name.push(suffix);
+        PathBuf::from(name)
+    }
+
+    fn prune(&self) -> io::Result<()> {
+        let dir = match self.path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let Some(name) = self.path.file_name().and_then(|n| n.to_str()) else {
+            return Ok(());
+        };
+        let prefix = format!("{}.", name);
+
+        let mut rotated: Vec<PathBuf> = fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                entry
+                    .file_name()
+                    .to_str()
+                    .is_some_and(|n| n.starts_with(&prefix))
+            })
+            .map(|entry| entry.path())
+            .collect();
+
+        // O sufixo de data faz a ordem alfabética coincidir com a cronológica
+        rotated.sort();
+        let excess = rotated.len().saturating_sub(self.max_files);
+        for old in &rotated[..excess] {
+            fs::remove_file(old)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.rotate_if_needed(buf.len())?;
+        let n = self.file.write(buf)?;
+        self.size += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
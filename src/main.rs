@@ -1,67 +1,418 @@
+#![allow(clippy::enum_variant_names)]
+
+mod admin;
+mod arc;
+mod auth;
+mod cli;
 mod config;
+mod control;
 mod dkim;
+mod dmarc;
+mod dns;
 mod error;
 mod helpers;
+mod local_delivery;
+mod logging;
+mod metrics;
 mod plugins;
 mod queue;
+mod recipients;
+mod shutdown;
 mod smtp_client;
 mod smtp_server;
+mod spf;
+mod srs;
+mod transport;
 
 use crate::{
-    config::{Config, logging_config::LoggingConfig},
+    cli::{Cli, Command},
+    config::{Config, shared_config::SharedConfig},
+    dkim::DkimSigner,
     error::AppError,
-    smtp_server::SmtpSession,
+    queue::{runner::QueueRunner, spool::Spool},
+    shutdown::{ServerSignal, Shutdown, Signals},
+    smtp_server::{
+        SmtpSession,
+        listener::{self, ListenerRole},
+        response_builder,
+    },
+};
+use clap::Parser;
+use std::{net::SocketAddr, path::Path, sync::Arc, time::Duration};
+use tokio::{
+    io::AsyncWriteExt,
+    net::{TcpListener, TcpStream},
+    task::JoinSet,
 };
-use std::sync::Arc;
-use tokio::net::TcpListener;
+
+// Tempo extra, além do prazo de DATA, antes de abortar sessões restantes
+const SHUTDOWN_MARGIN: Duration = Duration::from_secs(5);
+// Espera depois de um erro no accept (por exemplo, EMFILE) antes de tentar de novo
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(200);
 
 #[tokio::main]
 async fn main() -> Result<(), AppError> {
-    let config_path = std::env::args()
-        .nth(1)
-        .unwrap_or_else(|| "config.toml".to_string());
+    // Chamado como `sendmail` (link simbólico): interface compatível com o sendmail
+    let mut args = std::env::args();
+    let program = args.next().unwrap_or_default();
+    if Path::new(&program)
+        .file_name()
+        .is_some_and(|n| n == "sendmail")
+    {
+        std::process::exit(cli::sendmail::run(args.collect()).await);
+    }
 
-    let config = Arc::new(Config::load(&config_path)?);
-    init_tracing(&config.logging);
+    let (config_path, command) = Cli::parse().resolve();
+
+    let code = match command {
+        Command::Serve => return serve(&config_path).await,
+        Command::Queue(command) => cli::queue::run(&config_path, command).await,
+        Command::CheckConfig => check_config(&config_path),
+        Command::DumpConfig => dump_config(&config_path),
+        Command::Dkim(command) => cli::dkim::run(&config_path, command).await,
+        Command::Send(args) => cli::send::run(&config_path, args).await,
+        Command::HashPassword => hash_password(),
+    };
+    std::process::exit(code);
+}
+
+async fn serve(config_path: &str) -> Result<(), AppError> {
+    let shared_config =
+        SharedConfig::new(config_path, Config::load(config_path)?).map_err(anyhow::Error::from)?;
+    let shared_config = Arc::new(shared_config);
+    let config = shared_config.load();
+    logging::init_tracing(&config.logging)?;
 
     tracing::info!("Iniciando servidor v{}", env!("CARGO_PKG_VERSION"));
     tracing::info!("Hostname: {}", config.server.hostname);
+    if !config.dns.txt_records.is_empty() {
+        tracing::warn!("[dns.txt_records] só vale para o `dkim check`; o servidor consulta o DNS");
+    }
+
+    let (shutdown_trigger, shutdown) = shutdown::channel();
+    let mut signals = Signals::new()?;
 
-    // Listener SMTP
+    // Fila de entrega
+    let spool = Arc::new(Spool::open(&config.queue.spool_dir).await?);
+    let runner = QueueRunner::new(shared_config.clone(), spool.clone())?;
+    let runner = tokio::spawn(runner.run(shutdown.clone()));
+
+    if config.dmarc.enabled {
+        dmarc::public_suffix::reload(&config.dmarc.public_suffix_list);
+    }
+
+    // Relatórios DMARC agregados, enviados pela própria fila
+    tokio::spawn(dmarc::report::run(
+        shared_config.clone(),
+        spool.clone(),
+        shutdown.clone(),
+    ));
+
+    // Avisos da rotação de chaves DKIM
+    tokio::spawn(dkim::rotation::run(shared_config.clone(), shutdown.clone()));
+
+    // Tabelas de destinatários alteradas sem reload
+    tokio::spawn(recipients::watch(shared_config.clone(), shutdown.clone()));
+
+    let control = tokio::spawn(control::server::serve(
+        shared_config.clone(),
+        spool.clone(),
+        shutdown.clone(),
+    ));
+
+    if config.metrics.enabled {
+        tokio::spawn(metrics::server::serve(
+            config.metrics.listen.clone(),
+            shutdown.clone(),
+        ));
+    }
+
+    if config.admin.enabled {
+        tokio::spawn(admin::serve(
+            config.admin.listen.clone(),
+            shared_config.clone(),
+            spool.clone(),
+            shutdown.clone(),
+        ));
+    }
+
+    // Listener SMTP e, com [submission], o da porta de submissão
     let addr = format!("{}:{}", config.server.ip, config.server.port);
-    let listener = TcpListener::bind(&addr).await?;
+    let listener = listener::bind_or_inherit(&addr, listener::LISTEN_FD_ENV)?;
     tracing::info!("Escutando em {}", addr);
 
+    let submission = if config.submission.enabled {
+        let addr = format!("{}:{}", config.server.ip, config.server.submission_port);
+        let listener = listener::bind_or_inherit(&addr, listener::SUBMISSION_FD_ENV)?;
+        tracing::info!("Submissão em {}", addr);
+        Some(listener)
+    } else {
+        None
+    };
+
+    let mut sessions = JoinSet::new();
+
     loop {
-        let (stream, peer_addr) = listener.accept().await?;
+        tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, peer_addr)) => {
+                    if let Some(stream) = admit(&shared_config, &sessions, stream, peer_addr) {
+                        sessions.spawn(session(&shared_config, &spool, &shutdown, ListenerRole::Mta, stream, peer_addr));
+                    }
+                }
+                Err(e) => accept_failed(e).await,
+            },
+            accepted = accept_submission(submission.as_ref()) => match accepted {
+                Ok((stream, peer_addr)) => {
+                    if let Some(stream) = admit(&shared_config, &sessions, stream, peer_addr) {
+                        sessions.spawn(session(&shared_config, &spool, &shutdown, ListenerRole::Submission, stream, peer_addr));
+                    }
+                }
+                Err(e) => accept_failed(e).await,
+            },
+            Some(_) = sessions.join_next(), if !sessions.is_empty() => {}
+            signal = signals.recv() => match signal {
+                ServerSignal::Terminate => {
+                    tracing::info!("Sinal de encerramento recebido");
+                    break;
+                }
+                ServerSignal::Upgrade => {
+                    if hand_over_listeners(&listener, submission.as_ref()).await {
+                        break;
+                    }
+                }
+                ServerSignal::Reload => reload_config(&shared_config),
+            },
+        }
+    }
+
+    // Para de aceitar conexões e avisa as sessões ativas
+    drop(listener);
+    drop(submission);
+    shutdown_trigger.trigger();
+    tracing::info!("Aguardando {} sessões ativas", sessions.len());
+
+    let grace =
+        Duration::from_secs(shared_config.load().server.shutdown_timeout_secs) + SHUTDOWN_MARGIN;
+    let drained = tokio::time::timeout(grace, async {
+        while sessions.join_next().await.is_some() {}
+    })
+    .await;
+    if drained.is_err() {
+        tracing::warn!(
+            "Abortando {} sessões após o prazo de desligamento",
+            sessions.len()
+        );
+        sessions.shutdown().await;
+    }
+
+    // A fila conclui a entrega em andamento e grava o estado no spool
+    if let Err(e) = runner.await {
+        tracing::error!("Erro ao encerrar a fila de entrega: {}", e);
+    }
+    let _ = control.await;
+
+    tracing::info!("Servidor encerrado");
+    Ok(())
+}
+
+// Novas sessões recebem a configuração ativa no momento da conexão
+fn session(
+    shared_config: &SharedConfig,
+    spool: &Arc<Spool>,
+    shutdown: &Shutdown,
+    role: ListenerRole,
+    stream: TcpStream,
+    peer_addr: SocketAddr,
+) -> impl Future<Output = ()> + use<> {
+    let config = shared_config.load();
+    let recipients = shared_config.recipients();
+    let peer = peer_addr.to_string();
+    let spool = spool.clone();
+    let shutdown = shutdown.clone();
+
+    async move {
+        tracing::debug!("Nova conexão de {}", peer);
+        let mut session = SmtpSession::new(config, recipients, peer.clone(), role, spool, shutdown);
+        if let Err(e) = session.run(stream).await {
+            tracing::error!("[{}] Erro na sessão: {}", peer, e);
+        }
+    }
+}
 
-        let config = config.clone();
-        let peer = peer_addr.to_string();
+// Acima de server.max_connections, a conexão recebe 421 e é fechada
+fn admit(
+    shared_config: &SharedConfig,
+    sessions: &JoinSet<()>,
+    mut stream: TcpStream,
+    peer_addr: SocketAddr,
+) -> Option<TcpStream> {
+    let config = shared_config.load();
+    if sessions.len() < config.server.max_connections {
+        return Some(stream);
+    }
 
-        tokio::spawn(async move {
-            tracing::debug!("Nova conexão de {}", peer);
-            let mut session = SmtpSession::new(config, peer.clone());
-            if let Err(e) = session.run(stream).await {
-                tracing::error!("[{}] Erro na sessão: {}", peer, e);
-            }
-        });
+    tracing::warn!(
+        "[{}] Conexão recusada: limite de {} sessões atingido",
+        peer_addr,
+        config.server.max_connections
+    );
+    let response = response_builder::too_many_connections_response(&config.server.hostname);
+    tokio::spawn(async move {
+        let _ = stream.write_all(response.as_bytes()).await;
+        let _ = stream.shutdown().await;
+    });
+    None
+}
+
+// Sem o listener de submissão, nunca retorna
+// Erros do accept são passageiros (descritores esgotados, conexão abortada
+// pelo cliente): o servidor continua aceitando depois de uma pausa
+async fn accept_failed(e: std::io::Error) {
+    tracing::error!("Erro ao aceitar conexão: {}", e);
+    tokio::time::sleep(ACCEPT_BACKOFF).await;
+}
+
+async fn accept_submission(
+    submission: Option<&TcpListener>,
+) -> std::io::Result<(TcpStream, SocketAddr)> {
+    match submission {
+        Some(listener) => listener.accept().await,
+        None => std::future::pending().await,
     }
 }
 
-fn init_tracing(config: &LoggingConfig) {
-    use tracing_subscriber::EnvFilter;
-
-    let filter =
-        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(&config.level));
-
-    match config.format.as_str() {
-        "json" => tracing_subscriber::fmt()
-            .with_env_filter(filter)
-            .json()
-            .init(),
-        _ => tracing_subscriber::fmt()
-            .with_env_filter(filter)
-            .pretty()
-            .init(),
+// check-config: valida o arquivo e as chaves sem iniciar o servidor
+fn check_config(path: &str) -> i32 {
+    let config = match Config::load(path) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}: {}", path, e);
+            return 1;
+        }
+    };
+
+    if (config.dkim.enabled || config.arc.seal)
+        && let Err(e) = DkimSigner::from_config(&config.dkim)
+    {
+        eprintln!("{}: {}", path, e);
+        return 1;
+    }
+
+    println!("{}: configuração válida", path);
+    0
+}
+
+// hash-password: a senha vem da primeira linha, sem o fim de linha
+fn hash_password() -> i32 {
+    let mut password = String::new();
+    if let Err(e) = std::io::stdin().read_line(&mut password) {
+        eprintln!("smtp: {}", e);
+        return 1;
+    }
+    let password = password.trim_end_matches(['\r', '\n']);
+    if password.is_empty() {
+        eprintln!("smtp: senha vazia");
+        return 1;
+    }
+
+    println!("{}", auth::password::hash(password));
+    0
+}
+
+// dump-config: mostra a configuração efetiva após includes e variáveis
+fn dump_config(path: &str) -> i32 {
+    let dump = Config::load_with_origins(path)
+        .and_then(|(config, origins)| config::loader::dump(&config, &origins));
+
+    match dump {
+        Ok(dump) => {
+            print!("{}", dump);
+            0
+        }
+        Err(e) => {
+            eprintln!("{}: {}", path, e);
+            1
+        }
+    }
+}
+
+// SIGHUP: troca a configuração inteira, incluindo as redes de [relay], as
+// tabelas de destinatários, as chaves DKIM e a lista de sufixos do DMARC. Não
+// há certificados TLS para recarregar, porque o servidor ainda não tem
+// suporte a TLS. Os listeners, o spool e o socket de controle são abertos uma
+// vez e só mudam ao reiniciar.
+fn reload_config(shared_config: &SharedConfig) {
+    tracing::info!("Recarregando configuração");
+
+    let candidate = match shared_config.read() {
+        Ok(config) => config,
+        Err(e) => {
+            tracing::error!("Configuração rejeitada, mantendo a atual: {}", e);
+            return;
+        }
+    };
+
+    // Garante que as chaves DKIM podem ser lidas antes de trocar
+    if (candidate.dkim.enabled || candidate.arc.seal)
+        && let Err(e) = DkimSigner::from_config(&candidate.dkim)
+    {
+        tracing::error!("Configuração DKIM rejeitada, mantendo a atual: {}", e);
+        return;
+    }
+
+    let current = shared_config.load();
+    if candidate.server.ip != current.server.ip
+        || candidate.server.port != current.server.port
+        || candidate.server.submission_port != current.server.submission_port
+        || candidate.submission.enabled != current.submission.enabled
+        || candidate.queue.spool_dir != current.queue.spool_dir
+        || candidate.server.control_socket != current.server.control_socket
+    {
+        tracing::warn!(
+            "Alterações de endereço, portas, submissão, spool e socket de controle só valem após reiniciar"
+        );
+    }
+
+    if let Err(e) = shared_config.store(candidate) {
+        tracing::error!("Configuração rejeitada, mantendo a atual: {}", e);
+        return;
+    }
+    let config = shared_config.load();
+    if config.dmarc.enabled {
+        dmarc::public_suffix::reload(&config.dmarc.public_suffix_list);
+    }
+    tracing::info!("Configuração recarregada");
+}
+
+async fn hand_over_listeners(listener: &TcpListener, submission: Option<&TcpListener>) -> bool {
+    let mut listeners = vec![(listener::LISTEN_FD_ENV, listener)];
+    if let Some(submission) = submission {
+        listeners.push((listener::SUBMISSION_FD_ENV, submission));
+    }
+
+    let mut child = match listener::spawn_successor(&listeners) {
+        Ok(child) => child,
+        Err(e) => {
+            tracing::error!("Erro ao iniciar o novo processo: {}", e);
+            return false;
+        }
+    };
+
+    // Só encerra se o novo processo sobreviver à inicialização
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    match child.try_wait() {
+        Ok(None) => {
+            tracing::info!("Listener repassado ao processo {}", child.id());
+            true
+        }
+        Ok(Some(status)) => {
+            tracing::error!("Novo processo terminou durante a inicialização: {}", status);
+            false
+        }
+        Err(e) => {
+            tracing::error!("Erro ao verificar o novo processo: {}", e);
+            false
+        }
     }
 }
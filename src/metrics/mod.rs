@@ -0,0 +1,195 @@
+pub mod server;
+
+use std::sync::LazyLock;
+
+use prometheus::{
+    Histogram, HistogramOpts, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+
+static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+pub fn metrics() -> &'static Metrics {
+    &METRICS
+}
+
+pub struct Metrics {
+    registry: Registry,
+    pub connections: IntCounter,
+    pub active_sessions: IntGauge,
+    pub commands: IntCounterVec,
+    pub replies: IntCounterVec,
+    pub messages: IntCounterVec,
+    pub bytes_received: IntCounter,
+    pub tls_handshakes: IntCounterVec,
+    pub auth_attempts: IntCounterVec,
+    pub queue_depth: IntGaugeVec,
+    pub delivery_attempts: IntCounterVec,
+    pub dkim_sign_seconds: Histogram,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new_custom(Some("smtp".to_string()), None)
+            .expect("prefixo de métricas válido");
+
+        let metrics = Self {
+            connections: IntCounter::new("connections_total", "Conexões SMTP aceitas").unwrap(),
+            active_sessions: IntGauge::new("active_sessions", "Sessões SMTP abertas").unwrap(),
+            commands: IntCounterVec::new(
+                Opts::new("commands_total", "Comandos SMTP recebidos"),
+                &["command"],
+            )
+            .unwrap(),
+            replies: IntCounterVec::new(
+                Opts::new("replies_total", "Respostas SMTP enviadas, por código"),
+                &["code"],
+            )
+            .unwrap(),
+            messages: IntCounterVec::new(
+                Opts::new(
+                    "messages_total",
+                    "Mensagens aceitas e recusadas após o DATA",
+                ),
+                &["result"],
+            )
+            .unwrap(),
+            bytes_received: IntCounter::new("received_bytes_total", "Bytes recebidos dos clientes")
+                .unwrap(),
+            tls_handshakes: IntCounterVec::new(
+                Opts::new("tls_handshakes_total", "Negociações TLS"),
+                &["result"],
+            )
+            .unwrap(),
+            auth_attempts: IntCounterVec::new(
+                Opts::new("auth_attempts_total", "Tentativas de AUTH"),
+                &["mechanism", "result"],
+            )
+            .unwrap(),
+            queue_depth: IntGaugeVec::new(
+                Opts::new(
+                    "queue_depth",
+                    "Jobs no spool, por prioridade e destino (local ou remote)",
+                ),
+                &["priority", "destination"],
+            )
+            .unwrap(),
+            delivery_attempts: IntCounterVec::new(
+                Opts::new(
+                    "delivery_attempts_total",
+                    "Tentativas de entrega, pela resposta do destino",
+                ),
+                &["result"],
+            )
+            .unwrap(),
+            dkim_sign_seconds: Histogram::with_opts(
+                HistogramOpts::new("dkim_sign_seconds", "Tempo de assinatura DKIM").buckets(vec![
+                    0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25,
+                ]),
+            )
+            .unwrap(),
+            registry,
+        };
+
+        metrics.register();
+        metrics
+    }
+
+    fn register(&self) {
+        let collectors: [Box<dyn prometheus::core::Collector>; 11] = [
+            Box::new(self.connections.clone()),
+            Box::new(self.active_sessions.clone()),
+            Box::new(self.commands.clone()),
+            Box::new(self.replies.clone()),
+            Box::new(self.messages.clone()),
+            Box::new(self.bytes_received.clone()),
+            Box::new(self.tls_handshakes.clone()),
+            Box::new(self.auth_attempts.clone()),
+            Box::new(self.queue_depth.clone()),
+            Box::new(self.delivery_attempts.clone()),
+            Box::new(self.dkim_sign_seconds.clone()),
+        ];
+
+        for collector in collectors {
+            self.registry
+                .register(collector)
+                .expect("métricas registradas uma única vez");
+        }
+    }
+
+    pub fn render(&self) -> String {
+        TextEncoder::new()
+            .encode_to_string(&self.registry.gather())
+            .unwrap_or_else(|e| {
+                tracing::error!("Erro ao gerar métricas: {}", e);
+                String::new()
+            })
+    }
+}
+
+// Mantém o gauge de sessões ativas correto mesmo quando a sessão termina com erro
+pub struct ActiveSession;
+
+impl ActiveSession {
+    pub fn open() -> Self {
+        metrics().connections.inc();
+        metrics().active_sessions.inc();
+        ActiveSession
+    }
+}
+
+impl Drop for ActiveSession {
+    fn drop(&mut self) {
+        metrics().active_sessions.dec();
+    }
+}
+
+const KNOWN_COMMANDS: [&str; 13] = [
+    "EHLO", "HELO", "MAIL", "RCPT", "DATA", "RSET", "NOOP", "QUIT", "VRFY", "EXPN", "HELP", "AUTH",
+    "STARTTLS",
+];
+const KNOWN_AUTH_MECHANISMS: [&str; 4] = ["PLAIN", "LOGIN", "CRAM-MD5", "XOAUTH2"];
+
+// Recebe o comando já em maiúsculas. Comandos desconhecidos são agrupados para
+// não criar uma série por texto enviado pelo cliente.
+pub fn record_command(command: &str) {
+    let verb = command.split_whitespace().next().unwrap_or_default();
+    let verb = KNOWN_COMMANDS
+        .iter()
+        .find(|c| **c == verb)
+        .copied()
+        .unwrap_or("OTHER");
+    metrics().commands.with_label_values(&[verb]).inc();
+
+    // STARTTLS ainda não é suportado: as tentativas são contadas como tal
+    // até que seja implementado
+    if verb == "STARTTLS" {
+        metrics()
+            .tls_handshakes
+            .with_label_values(&["unsupported"])
+            .inc();
+    }
+}
+
+// Resultado de um AUTH: success, failure ou error (backend indisponível)
+pub fn record_auth(mechanism: &str, result: &str) {
+    let mechanism = KNOWN_AUTH_MECHANISMS
+        .iter()
+        .find(|m| **m == mechanism)
+        .copied()
+        .unwrap_or("OTHER");
+    metrics()
+        .auth_attempts
+        .with_label_values(&[mechanism, result])
+        .inc();
+}
+
+pub fn record_reply(response: &str) {
+    // Em respostas com várias linhas, o código é o mesmo em todas
+    if let Some(code) = response
+        .get(..3)
+        .filter(|c| c.bytes().all(|b| b.is_ascii_digit()))
+    {
+        metrics().replies.with_label_values(&[code]).inc();
+    }
+}
@@ -0,0 +1,31 @@
+use axum::{Router, http::header, response::IntoResponse, routing::get};
+use tokio::net::TcpListener;
+
+use crate::{metrics::metrics, shutdown::Shutdown};
+
+pub async fn serve(listen: String, mut shutdown: Shutdown) {
+    let listener = match TcpListener::bind(&listen).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            tracing::error!("Erro ao abrir o endpoint de métricas em {}: {}", listen, e);
+            return;
+        }
+    };
+    tracing::info!("Métricas em http://{}/metrics", listen);
+
+    let app = Router::new().route("/metrics", get(render));
+    let result = axum::serve(listener, app)
+        .with_graceful_shutdown(async move { shutdown.wait().await })
+        .await;
+
+    if let Err(e) = result {
+        tracing::error!("Erro no endpoint de métricas: {}", e);
+    }
+}
+
+async fn render() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        metrics().render(),
+    )
+}
@@ -1,8 +1,31 @@
+use crate::{helpers::auth_results_helper::AuthResults, spf::SpfCheck};
+
 pub struct EmailContext {
     pub id: String,
     pub from: String,
     pub rcpt_to: Vec<String>,
+    // Usuário autenticado (AUTH) da sessão
+    pub auth_user: Option<String>,
     pub raw_headers: String,
     pub raw_body: String,
-    pub metadata: std::collections::HashMap<String, String>
-}
\ No newline at end of file
+    pub metadata: std::collections::HashMap<String, String>,
+    pub auth_results: AuthResults,
+    // Resultado SPF do MAIL FROM, usado pelo DMARC
+    pub spf: Option<SpfCheck>,
+}
+
+impl EmailContext {
+    pub fn new(from: &str) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            from: from.to_string(),
+            rcpt_to: vec![],
+            auth_user: None,
+            raw_headers: String::new(),
+            raw_body: String::new(),
+            metadata: Default::default(),
+            auth_results: AuthResults::default(),
+            spf: None,
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::{config::Config, queue::models::DeliveryJob};
+
+// Job que leva a notificação ao remetente, com o remetente nulo para que um
+// bounce nunca gere outro
+pub fn job(job: &DeliveryJob, reason: &str, config: &Config) -> Option<DeliveryJob> {
+    let raw = notification(job, reason, &config.server.hostname)?;
+    Some(DeliveryJob::new(
+        &Uuid::new_v4().to_string(),
+        "",
+        &job.from_addr,
+        &raw,
+        config.queue.max_attempts,
+    ))
+}
+
+// Mensagem de não-entrega (RFC 3464) para o remetente do job. Retorna None
+// para o remetente nulo, que nunca recebe bounces (RFC 5321 §4.5.5).
+pub fn notification(job: &DeliveryJob, reason: &str, hostname: &str) -> Option<String> {
+    if job.from_addr.is_empty() {
+        return None;
+    }
+
+    let boundary = format!("{}/{}", Uuid::new_v4().simple(), hostname);
+    let headers = job
+        .raw_message
+        .split_once("\r\n\r\n")
+        .map(|(headers, _)| headers)
+        .unwrap_or(&job.raw_message);
+    let diagnostic = job.last_error.as_deref().unwrap_or(reason);
+
+    let mut msg = String::new();
+    msg.push_str(&format!(
+        "From: Mail Delivery System <MAILER-DAEMON@{}>\r\n",
+        hostname
+    ));
+    msg.push_str(&format!("To: <{}>\r\n", job.from_addr));
+    msg.push_str("Subject: Undelivered Mail Returned to Sender\r\n");
+    msg.push_str(&format!("Date: {}\r\n", Utc::now().to_rfc2822()));
+    msg.push_str(&format!(
+        "Message-ID: <{}@{}>\r\n",
+        Uuid::new_v4(),
+        hostname
+    ));
+    msg.push_str("Auto-Submitted: auto-replied\r\n");
+    msg.push_str("MIME-Version: 1.0\r\n");
+    msg.push_str(&format!(
+        "Content-Type: multipart/report; report-type=delivery-status; boundary=\"{}\"\r\n",
+        boundary
+    ));
+    msg.push_str("\r\n");
+
+    msg.push_str(&format!("--{}\r\n", boundary));
+    msg.push_str("Content-Type: text/plain; charset=utf-8\r\n\r\n");
+    msg.push_str(&format!(
+        "Your message could not be delivered to <{}>.\r\n\r\n{}\r\n\r\n",
+        job.to_addr, reason
+    ));
+
+    msg.push_str(&format!("--{}\r\n", boundary));
+    msg.push_str("Content-Type: message/delivery-status\r\n\r\n");
+    msg.push_str(&format!("Reporting-MTA: dns; {}\r\n", hostname));
+    msg.push_str(&format!(
+        "Arrival-Date: {}\r\n\r\n",
+        job.created_at.to_rfc2822()
+    ));
+    msg.push_str(&format!("Final-Recipient: rfc822; {}\r\n", job.to_addr));
+    msg.push_str("Action: failed\r\n");
+    msg.push_str("Status: 5.0.0\r\n");
+    msg.push_str(&format!(
+        "Diagnostic-Code: smtp; {}\r\n\r\n",
+        diagnostic.replace(['\r', '\n'], " ")
+    ));
+
+    msg.push_str(&format!("--{}\r\n", boundary));
+    msg.push_str("Content-Type: text/rfc822-headers\r\n\r\n");
+    msg.push_str(headers);
+    msg.push_str("\r\n");
+    msg.push_str(&format!("--{}--\r\n", boundary));
+
+    Some(msg)
+}
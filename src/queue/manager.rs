@@ -0,0 +1,243 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::Config,
+    logging::mail_log,
+    queue::{
+        bounce,
+        models::{AttemptRecord, DeliveryJob, JobPriority},
+        queue_error::QueueError,
+        spool::Spool,
+    },
+};
+
+// Operações de administração da fila, usadas pela API HTTP e pela linha de
+// comando. Cada alteração reserva o job no spool, então um job em entrega no
+// momento retorna BusyError em vez de ser sobrescrito.
+pub struct QueueManager {
+    spool: Arc<Spool>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JobFilter {
+    pub domain: Option<String>,
+    pub sender: Option<String>,
+    // Só jobs criados há pelo menos esse tempo
+    pub older_than_secs: Option<u64>,
+    pub priority: Option<JobPriority>,
+    pub held: Option<bool>,
+}
+
+impl JobFilter {
+    fn matches(&self, job: &DeliveryJob, now: DateTime<Utc>) -> bool {
+        if let Some(domain) = &self.domain
+            && !job.domain.eq_ignore_ascii_case(domain)
+        {
+            return false;
+        }
+        if let Some(sender) = &self.sender
+            && !job.from_addr.eq_ignore_ascii_case(sender)
+        {
+            return false;
+        }
+        if let Some(secs) = self.older_than_secs
+            && (now - job.created_at).num_seconds() < secs as i64
+        {
+            return false;
+        }
+        if self.priority.as_ref().is_some_and(|p| *p != job.priority) {
+            return false;
+        }
+        if self.held.is_some_and(|held| held != job.held) {
+            return false;
+        }
+        true
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JobSummary {
+    pub id: String,
+    pub email_id: String,
+    pub from: String,
+    pub to: String,
+    pub domain: String,
+    pub priority: String,
+    pub held: bool,
+    pub attempt: u32,
+    pub max_attempts: u32,
+    pub created_at: DateTime<Utc>,
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_error: Option<String>,
+}
+
+impl From<&DeliveryJob> for JobSummary {
+    fn from(job: &DeliveryJob) -> Self {
+        Self {
+            id: job.id.clone(),
+            email_id: job.email_id.clone(),
+            from: job.from_addr.clone(),
+            to: job.to_addr.clone(),
+            domain: job.domain.clone(),
+            priority: job.priority.to_string(),
+            held: job.held,
+            attempt: job.attempt,
+            max_attempts: job.max_attemps,
+            created_at: job.created_at,
+            next_attempt_at: job.next_attempt_at,
+            last_error: job.last_error.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JobDetail {
+    #[serde(flatten)]
+    pub summary: JobSummary,
+    pub size: usize,
+    pub history: Vec<AttemptRecord>,
+}
+
+impl From<&DeliveryJob> for JobDetail {
+    fn from(job: &DeliveryJob) -> Self {
+        Self {
+            summary: job.into(),
+            size: job.raw_message.len(),
+            history: job.history.clone(),
+        }
+    }
+}
+
+impl QueueManager {
+    pub fn new(spool: Arc<Spool>) -> Self {
+        Self { spool }
+    }
+
+    pub async fn list(&self, filter: &JobFilter) -> Result<Vec<DeliveryJob>, QueueError> {
+        let now = Utc::now();
+        let mut jobs: Vec<DeliveryJob> = self
+            .spool
+            .load_all()
+            .await?
+            .into_iter()
+            .filter(|job| filter.matches(job, now))
+            .collect();
+        jobs.sort_by_key(|job| job.created_at);
+        Ok(jobs)
+    }
+
+    pub async fn show(&self, id: &str) -> Result<DeliveryJob, QueueError> {
+        self.spool.load(id).await
+    }
+
+    // Agenda para agora e libera se estiver retido
+    pub async fn retry(&self, id: &str) -> Result<DeliveryJob, QueueError> {
+        let job = self
+            .update(id, |job| {
+                job.held = false;
+                job.next_attempt_at = Utc::now();
+            })
+            .await?;
+        self.spool.wake();
+        Ok(job)
+    }
+
+    pub async fn hold(&self, id: &str) -> Result<DeliveryJob, QueueError> {
+        self.update(id, |job| job.held = true).await
+    }
+
+    pub async fn release(&self, id: &str) -> Result<DeliveryJob, QueueError> {
+        let job = self.update(id, |job| job.held = false).await?;
+        self.spool.wake();
+        Ok(job)
+    }
+
+    pub async fn set_priority(
+        &self,
+        id: &str,
+        priority: JobPriority,
+    ) -> Result<DeliveryJob, QueueError> {
+        self.update(id, |job| job.priority = priority).await
+    }
+
+    // Remove sem avisar o remetente
+    pub async fn delete(&self, id: &str) -> Result<DeliveryJob, QueueError> {
+        let _claim = self.spool.claim(id)?;
+        let job = self.spool.load(id).await?;
+        self.spool.remove(id).await?;
+        log_removal(&job, "deleted", "removido pela administração");
+        Ok(job)
+    }
+
+    // Remove e envia ao remetente uma mensagem de não-entrega, pela própria fila
+    pub async fn bounce(&self, id: &str, config: &Config) -> Result<DeliveryJob, QueueError> {
+        let _claim = self.spool.claim(id)?;
+        let job = self.spool.load(id).await?;
+
+        let reason = "Delivery was cancelled by the administrator.";
+        if let Some(notice) = bounce::job(&job, reason, config) {
+            self.spool.store(&notice).await?;
+        }
+
+        self.spool.remove(id).await?;
+        log_removal(&job, "bounced", "devolvido pela administração");
+        self.spool.wake();
+        Ok(job)
+    }
+
+    // Agenda para agora todos os jobs não retidos (de um domínio, se informado).
+    // Jobs em entrega no momento são ignorados.
+    pub async fn flush(&self, domain: Option<&str>) -> Result<usize, QueueError> {
+        let filter = JobFilter {
+            domain: domain.map(str::to_string),
+            held: Some(false),
+            ..Default::default()
+        };
+
+        let mut flushed = 0;
+        for job in self.list(&filter).await? {
+            match self
+                .update(&job.id, |job| job.next_attempt_at = Utc::now())
+                .await
+            {
+                Ok(_) => flushed += 1,
+                Err(QueueError::BusyError(_)) | Err(QueueError::NotFoundError(_)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        self.spool.wake();
+        Ok(flushed)
+    }
+
+    async fn update(
+        &self,
+        id: &str,
+        change: impl FnOnce(&mut DeliveryJob),
+    ) -> Result<DeliveryJob, QueueError> {
+        let _claim = self.spool.claim(id)?;
+        let mut job = self.spool.load(id).await?;
+        change(&mut job);
+        self.spool.store(&job).await?;
+        Ok(job)
+    }
+}
+
+fn log_removal(job: &DeliveryJob, result: &str, detail: &str) {
+    tracing::info!("[{}] Job {}: {}", job.id, result, detail);
+    mail_log::delivery(&mail_log::DeliveryRecord {
+        message_id: &job.email_id,
+        job_id: &job.id,
+        from: &job.from_addr,
+        recipient: &job.to_addr,
+        attempt: job.attempt,
+        result,
+        smtp_code: None,
+        detail,
+        next_attempt_at: None,
+    });
+}
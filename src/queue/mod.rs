@@ -1 +1,6 @@
-mod models;
+pub mod bounce;
+pub mod manager;
+pub mod models;
+pub mod queue_error;
+pub mod runner;
+pub mod spool;
@@ -1,14 +1,54 @@
+use std::{fmt, str::FromStr};
+
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
 pub enum JobPriority {
+    #[serde(alias = "low")]
     Low = 0,
+    #[serde(alias = "normal")]
     Normal = 1,
+    #[serde(alias = "high")]
     High = 2,
 }
 
+impl fmt::Display for JobPriority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JobPriority::Low => write!(f, "low"),
+            JobPriority::Normal => write!(f, "normal"),
+            JobPriority::High => write!(f, "high"),
+        }
+    }
+}
+
+impl FromStr for JobPriority {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "low" => Ok(JobPriority::Low),
+            "normal" => Ok(JobPriority::Normal),
+            "high" => Ok(JobPriority::High),
+            _ => Err(format!(
+                "prioridade desconhecida \"{}\" (use low, normal ou high)",
+                s
+            )),
+        }
+    }
+}
+
+// Uma tentativa de entrega, guardada no próprio job para consulta
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttemptRecord {
+    pub at: DateTime<Utc>,
+    pub result: String,
+    pub smtp_code: Option<u16>,
+    pub detail: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeliveryJob {
     pub id: String,
@@ -23,6 +63,31 @@ pub struct DeliveryJob {
     pub next_attempt_at: DateTime<Utc>,
     pub last_error: Option<String>,
     pub priority: JobPriority,
+    // Jobs retidos não são entregues até serem liberados
+    #[serde(default)]
+    pub held: bool,
+    #[serde(default)]
+    pub history: Vec<AttemptRecord>,
+    // Usuário autenticado que enviou a mensagem, quando houver
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth_user: Option<String>,
+    // Destinatário do RCPT quando o job veio da expansão de um alias
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original_recipient: Option<String>,
+}
+
+// Só os campos usados para agendar e agrupar as entregas: a fila lê o spool
+// inteiro a cada ciclo, e as mensagens ficam no disco até a entrega
+#[derive(Debug, Clone, Deserialize)]
+pub struct JobMetadata {
+    pub id: String,
+    pub email_id: String,
+    pub to_addr: String,
+    pub domain: String,
+    pub next_attempt_at: DateTime<Utc>,
+    pub priority: JobPriority,
+    #[serde(default)]
+    pub held: bool,
 }
 
 impl DeliveryJob {
@@ -47,10 +112,17 @@ impl DeliveryJob {
             next_attempt_at: Utc::now(),
             last_error: None,
             priority: JobPriority::Normal,
+            held: false,
+            history: Vec::new(),
+            auth_user: None,
+            original_recipient: None,
         }
     }
 }
 
 fn extract_domain_from_email(email: &str) -> &str {
-    email.splitn(2, '@').nth(1).unwrap_or("unknown")
+    email
+        .split_once('@')
+        .map(|(_, domain)| domain)
+        .unwrap_or("unknown")
 }
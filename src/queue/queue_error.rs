@@ -0,0 +1,35 @@
+use std::{error::Error, fmt};
+
+#[derive(Debug)]
+pub enum QueueError {
+    IoError(std::io::Error),
+    JsonError(serde_json::Error),
+    NotFoundError(String),
+    BusyError(String),
+}
+
+impl fmt::Display for QueueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueueError::IoError(e) => write!(f, "Erro de I/O: {}", e),
+            QueueError::JsonError(e) => write!(f, "Erro ao processar JSON: {}", e),
+            QueueError::NotFoundError(id) => write!(f, "Job não encontrado: {}", id),
+            QueueError::BusyError(id) => write!(f, "Job em entrega no momento: {}", id),
+        }
+    }
+}
+
+impl Error for QueueError {}
+
+// Conversões automáticas
+impl From<std::io::Error> for QueueError {
+    fn from(err: std::io::Error) -> Self {
+        QueueError::IoError(err)
+    }
+}
+
+impl From<serde_json::Error> for QueueError {
+    fn from(err: serde_json::Error) -> Self {
+        QueueError::JsonError(err)
+    }
+}
@@ -0,0 +1,314 @@
+use std::{sync::Arc, time::Duration};
+
+use chrono::{DateTime, Utc};
+use futures_util::stream::{self, StreamExt};
+
+use crate::{
+    config::{Config, shared_config::SharedConfig},
+    logging::mail_log,
+    metrics::metrics,
+    queue::{
+        bounce,
+        models::{AttemptRecord, DeliveryJob, JobMetadata, JobPriority},
+        queue_error::QueueError,
+        spool::Spool,
+    },
+    recipients::Recipients,
+    shutdown::Shutdown,
+    smtp_client::delivery_result::DeliveryResult,
+    transport::Router,
+};
+
+pub struct QueueRunner {
+    shared_config: Arc<SharedConfig>,
+    // Configuração e tabelas usadas para construir os transportes atuais
+    config: Arc<Config>,
+    recipients: Arc<Recipients>,
+    spool: Arc<Spool>,
+    router: Router,
+}
+
+impl QueueRunner {
+    pub fn new(shared_config: Arc<SharedConfig>, spool: Arc<Spool>) -> anyhow::Result<Self> {
+        let config = shared_config.load();
+        let recipients = shared_config.recipients();
+        let router = Router::new(config.clone(), recipients.clone())?;
+        Ok(Self {
+            shared_config,
+            config,
+            recipients,
+            spool,
+            router,
+        })
+    }
+
+    // Processa a fila até o encerramento. Uma entrega em andamento sempre é
+    // concluída e o estado do job gravado no spool antes de sair.
+    pub async fn run(mut self, mut shutdown: Shutdown) {
+        loop {
+            self.refresh_router();
+            self.process_due(&shutdown).await;
+
+            let poll_interval = Duration::from_secs(self.config.queue.poll_interval_secs);
+            tokio::select! {
+                _ = tokio::time::sleep(poll_interval) => {}
+                _ = self.spool.woken() => {}
+                _ = shutdown.wait() => break,
+            }
+        }
+
+        tracing::info!("Fila de entrega encerrada");
+    }
+
+    // Reconstrói os transportes (e as chaves DKIM) quando a configuração foi
+    // recarregada ou as tabelas de destinatários mudaram. Se a construção
+    // falhar, os transportes anteriores continuam e ela é tentada de novo no
+    // próximo ciclo.
+    fn refresh_router(&mut self) {
+        let current = self.shared_config.load();
+        let recipients = self.shared_config.recipients();
+        if Arc::ptr_eq(&current, &self.config) && Arc::ptr_eq(&recipients, &self.recipients) {
+            return;
+        }
+
+        match Router::new(current.clone(), recipients.clone()) {
+            Ok(router) => {
+                self.router = router;
+                self.config = current;
+                self.recipients = recipients;
+                tracing::info!("Fila de entrega usando a nova configuração");
+            }
+            Err(e) => tracing::error!("Erro ao aplicar a nova configuração na fila: {}", e),
+        }
+    }
+
+    async fn process_due(&self, shutdown: &Shutdown) {
+        let mut jobs = match self.spool.load_metadata().await {
+            Ok(jobs) => jobs,
+            Err(e) => {
+                tracing::error!("Erro ao ler o spool: {}", e);
+                return;
+            }
+        };
+
+        record_depth(&self.router, &jobs);
+        jobs.sort_by(|a, b| {
+            b.priority
+                .cmp(&a.priority)
+                .then(a.next_attempt_at.cmp(&b.next_attempt_at))
+        });
+
+        // Jobs da mesma mensagem com o mesmo transporte vão juntos, para que
+        // o LMTP entregue todos os destinatários em uma transação
+        let now = Utc::now();
+        let mut batches: Vec<(String, Vec<JobMetadata>)> = Vec::new();
+        for job in jobs
+            .into_iter()
+            .filter(|j| is_due(j.held, j.next_attempt_at, now))
+        {
+            let route = self.router.route(&job.to_addr, &job.domain).to_string();
+            match batches
+                .iter_mut()
+                .find(|(r, batch)| *r == route && batch[0].email_id == job.email_id)
+            {
+                Some((_, batch)) => batch.push(job),
+                None => batches.push((route, vec![job])),
+            }
+        }
+
+        // Até queue.max_concurrent_deliveries lotes ao mesmo tempo, na ordem
+        // de prioridade
+        stream::iter(batches)
+            .for_each_concurrent(
+                self.config.queue.max_concurrent_deliveries,
+                |(route, batch)| async move {
+                    if !shutdown.is_triggered() {
+                        self.deliver(&route, batch, now).await;
+                    }
+                },
+            )
+            .await;
+    }
+
+    // Os jobs do lote só são reservados e lidos por inteiro na hora da
+    // entrega. Podem ter sido alterados pela administração depois da leitura.
+    async fn deliver(&self, route: &str, batch: Vec<JobMetadata>, now: DateTime<Utc>) {
+        let mut claims = Vec::with_capacity(batch.len());
+        let mut jobs = Vec::with_capacity(batch.len());
+        for metadata in batch {
+            let Ok(claim) = self.spool.claim(&metadata.id) else {
+                continue;
+            };
+            match self.spool.load(&metadata.id).await {
+                Ok(job) if is_due(job.held, job.next_attempt_at, now) => {
+                    claims.push(claim);
+                    jobs.push(job);
+                }
+                Ok(_) | Err(QueueError::NotFoundError(_)) => {}
+                Err(e) => tracing::error!("[{}] Erro ao ler job do spool: {}", metadata.id, e),
+            }
+        }
+
+        if !jobs.is_empty() {
+            self.attempt(route, jobs).await;
+        }
+    }
+
+    async fn attempt(&self, route: &str, mut jobs: Vec<DeliveryJob>) {
+        for job in &mut jobs {
+            job.attempt += 1;
+            tracing::info!(
+                "[{}] Entregando para {} via {} (tentativa {}/{})",
+                job.id,
+                job.to_addr,
+                route,
+                job.attempt,
+                job.max_attemps
+            );
+        }
+
+        let results = match self.router.transport(route) {
+            Some(transport) => transport.deliver_batch(&jobs).await,
+            None => jobs
+                .iter()
+                .map(|_| Err(anyhow::anyhow!("Transporte {} não configurado", route)))
+                .collect(),
+        };
+        for (job, result) in jobs.into_iter().zip(results) {
+            self.finish(job, result).await;
+        }
+    }
+
+    async fn finish(&self, mut job: DeliveryJob, result: anyhow::Result<DeliveryResult>) {
+        let kind = match &result {
+            Ok(result) => result.kind(),
+            Err(_) => "error",
+        };
+        metrics().delivery_attempts.with_label_values(&[kind]).inc();
+
+        let (smtp_code, error) = match result {
+            Ok(DeliveryResult::Delivered { smtp_code, message }) => {
+                tracing::info!("[{}] Entregue: {} {}", job.id, smtp_code, message);
+                record_attempt(&mut job, "delivered", Some(smtp_code), &message);
+                self.remove(&job).await;
+                return;
+            }
+            Ok(DeliveryResult::Permanent { smtp_code, message }) => {
+                tracing::warn!("[{}] Falha permanente: {} {}", job.id, smtp_code, message);
+                record_attempt(&mut job, "bounced", Some(smtp_code), &message);
+                job.last_error = Some(format!("{} {}", smtp_code, message));
+                self.bounce(&job, "The recipient's mail server rejected the message.")
+                    .await;
+                self.remove(&job).await;
+                return;
+            }
+            Ok(DeliveryResult::Transient { smtp_code, message }) => {
+                (Some(smtp_code), format!("{} {}", smtp_code, message))
+            }
+            Err(e) => (None, e.to_string()),
+        };
+
+        if job.attempt >= job.max_attemps {
+            tracing::warn!("[{}] Tentativas esgotadas: {}", job.id, error);
+            record_attempt(&mut job, "expired", smtp_code, &error);
+            job.last_error = Some(error);
+            let reason = format!(
+                "Delivery failed after {} attempts and was abandoned.",
+                job.attempt
+            );
+            self.bounce(&job, &reason).await;
+            self.remove(&job).await;
+            return;
+        }
+
+        let delay = self.config.queue.retry_interval_secs * 2u64.pow(job.attempt.min(6) - 1);
+        job.next_attempt_at = Utc::now() + chrono::Duration::seconds(delay as i64);
+        tracing::info!(
+            "[{}] Falha temporária, nova tentativa em {}s: {}",
+            job.id,
+            delay,
+            error
+        );
+        record_attempt(&mut job, "deferred", smtp_code, &error);
+        job.last_error = Some(error);
+
+        if let Err(e) = self.spool.store(&job).await {
+            tracing::error!("[{}] Erro ao gravar job no spool: {}", job.id, e);
+        }
+    }
+
+    // Notificação de não-entrega pela própria fila; o remetente nulo não
+    // recebe nenhuma
+    async fn bounce(&self, job: &DeliveryJob, reason: &str) {
+        let Some(notice) = bounce::job(job, reason, &self.config) else {
+            return;
+        };
+        match self.spool.store(&notice).await {
+            Ok(()) => {
+                tracing::info!(
+                    "[{}] Bounce {} enviado para {}",
+                    job.id,
+                    notice.id,
+                    job.from_addr
+                );
+                self.spool.wake();
+            }
+            Err(e) => tracing::error!("[{}] Erro ao gravar o bounce no spool: {}", job.id, e),
+        }
+    }
+
+    async fn remove(&self, job: &DeliveryJob) {
+        if let Err(e) = self.spool.remove(&job.id).await {
+            tracing::error!("[{}] Erro ao remover job do spool: {}", job.id, e);
+        }
+    }
+}
+
+fn is_due(held: bool, next_attempt_at: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+    !held && next_attempt_at <= now
+}
+
+// Por prioridade e destino: remote para o transporte smtp, local para os
+// demais. Todas as combinações são gravadas a cada ciclo, inclusive as
+// vazias, para que as séries não sumam entre as leituras.
+fn record_depth(router: &Router, jobs: &[JobMetadata]) {
+    const PRIORITIES: [JobPriority; 3] = [JobPriority::Low, JobPriority::Normal, JobPriority::High];
+    let mut counts = [[0i64; 2]; PRIORITIES.len()];
+    for job in jobs {
+        let priority = PRIORITIES
+            .iter()
+            .position(|p| *p == job.priority)
+            .unwrap_or_default();
+        let remote = router.route(&job.to_addr, &job.domain) == "smtp";
+        counts[priority][remote as usize] += 1;
+    }
+
+    for (priority, counts) in PRIORITIES.iter().zip(counts) {
+        for (destination, count) in ["local", "remote"].into_iter().zip(counts) {
+            metrics()
+                .queue_depth
+                .with_label_values(&[&priority.to_string(), destination])
+                .set(count);
+        }
+    }
+}
+
+fn record_attempt(job: &mut DeliveryJob, result: &str, smtp_code: Option<u16>, detail: &str) {
+    job.history.push(AttemptRecord {
+        at: Utc::now(),
+        result: result.to_string(),
+        smtp_code,
+        detail: detail.to_string(),
+    });
+    mail_log::delivery(&mail_log::DeliveryRecord {
+        message_id: &job.email_id,
+        job_id: &job.id,
+        from: &job.from_addr,
+        recipient: &job.to_addr,
+        attempt: job.attempt,
+        result,
+        smtp_code,
+        detail,
+        next_attempt_at: (result == "deferred").then_some(job.next_attempt_at),
+    });
+}
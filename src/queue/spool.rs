@@ -0,0 +1,165 @@
+use std::{
+    fs::File,
+    io::ErrorKind,
+    os::fd::AsRawFd,
+    path::{Path, PathBuf},
+};
+
+use serde::de::DeserializeOwned;
+use tokio::{fs, io::AsyncWriteExt, sync::Notify};
+
+use crate::queue::{
+    models::{DeliveryJob, JobMetadata},
+    queue_error::QueueError,
+};
+
+const JOB_EXTENSION: &str = "json";
+
+pub struct Spool {
+    dir: PathBuf,
+    wake: Notify,
+}
+
+// Reserva exclusiva de um job, liberada ao sair de escopo. É um flock em
+// .<id>.lock, para valer também entre processos: a CLI e, durante a troca
+// de processo do SIGUSR2, a fila do processo anterior.
+pub struct Claim<'a> {
+    spool: &'a Spool,
+    id: String,
+    _lock: File,
+}
+
+impl Drop for Claim<'_> {
+    fn drop(&mut self) {
+        // Ainda com a trava: quem abrir o arquivo depois disso cria outro e
+        // já não encontra o job
+        if !self.spool.job_path(&self.id).exists() {
+            let _ = std::fs::remove_file(self.spool.lock_path(&self.id));
+        }
+    }
+}
+
+impl Spool {
+    pub async fn open(dir: &Path) -> Result<Self, QueueError> {
+        fs::create_dir_all(dir).await?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            wake: Notify::new(),
+        })
+    }
+
+    pub fn claim(&self, id: &str) -> Result<Claim<'_>, QueueError> {
+        if !is_valid_id(id) {
+            return Err(QueueError::NotFoundError(id.to_string()));
+        }
+        let lock = std::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.lock_path(id))?;
+        // flock é por descrição de arquivo: também impede uma segunda
+        // reserva no mesmo processo
+        if unsafe { libc::flock(lock.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } != 0 {
+            let e = std::io::Error::last_os_error();
+            return match e.raw_os_error() {
+                Some(libc::EWOULDBLOCK) => Err(QueueError::BusyError(id.to_string())),
+                _ => Err(e.into()),
+            };
+        }
+        Ok(Claim {
+            spool: self,
+            id: id.to_string(),
+            _lock: lock,
+        })
+    }
+
+    // Avisa a fila de que há jobs para entregar antes do próximo ciclo
+    pub fn wake(&self) {
+        self.wake.notify_one();
+    }
+
+    pub async fn woken(&self) {
+        self.wake.notified().await;
+    }
+
+    // Grava em um arquivo temporário e renomeia, para que um job nunca seja
+    // lido pela metade. Só retorna depois que os dados estão no disco.
+    pub async fn store(&self, job: &DeliveryJob) -> Result<(), QueueError> {
+        let data = serde_json::to_vec_pretty(job)?;
+        let tmp_path = self.dir.join(format!(".{}.tmp", job.id));
+
+        let mut file = fs::File::create(&tmp_path).await?;
+        file.write_all(&data).await?;
+        file.sync_all().await?;
+        drop(file);
+
+        fs::rename(&tmp_path, self.job_path(&job.id)).await?;
+        fs::File::open(&self.dir).await?.sync_all().await?;
+
+        Ok(())
+    }
+
+    pub async fn load(&self, id: &str) -> Result<DeliveryJob, QueueError> {
+        if !is_valid_id(id) {
+            return Err(QueueError::NotFoundError(id.to_string()));
+        }
+
+        match fs::read(self.job_path(id)).await {
+            Ok(data) => Ok(serde_json::from_slice(&data)?),
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                Err(QueueError::NotFoundError(id.to_string()))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub async fn load_all(&self) -> Result<Vec<DeliveryJob>, QueueError> {
+        self.read_all().await
+    }
+
+    // Só os metadados de cada job, sem manter as mensagens na memória
+    pub async fn load_metadata(&self) -> Result<Vec<JobMetadata>, QueueError> {
+        self.read_all().await
+    }
+
+    async fn read_all<T: DeserializeOwned>(&self) -> Result<Vec<T>, QueueError> {
+        let mut jobs = Vec::new();
+        let mut entries = fs::read_dir(&self.dir).await?;
+
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(JOB_EXTENSION) {
+                continue;
+            }
+
+            match fs::read(&path)
+                .await
+                .map(|data| serde_json::from_slice(&data))
+            {
+                Ok(Ok(job)) => jobs.push(job),
+                Ok(Err(e)) => tracing::error!("Job inválido no spool {}: {}", path.display(), e),
+                Err(e) => tracing::error!("Erro ao ler job {}: {}", path.display(), e),
+            }
+        }
+
+        Ok(jobs)
+    }
+
+    pub async fn remove(&self, id: &str) -> Result<(), QueueError> {
+        fs::remove_file(self.job_path(id)).await?;
+        Ok(())
+    }
+
+    fn job_path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.{}", id, JOB_EXTENSION))
+    }
+
+    fn lock_path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!(".{}.lock", id))
+    }
+}
+
+// O id vem de fora (API e linha de comando) e vira nome de arquivo
+fn is_valid_id(id: &str) -> bool {
+    !id.is_empty() && !id.contains(['/', '\\']) && !id.starts_with('.')
+}
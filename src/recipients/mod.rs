@@ -0,0 +1,327 @@
+pub mod recipient_error;
+pub mod table;
+
+#[cfg(test)]
+mod tests;
+
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    config::{Config, shared_config::SharedConfig},
+    helpers::email_helper,
+    local_delivery::{self, maildir},
+    recipients::{
+        recipient_error::RecipientError,
+        table::{Format, Table},
+    },
+    shutdown::Shutdown,
+    srs,
+};
+
+// Níveis de alias antes de considerar a expansão um loop
+const MAX_DEPTH: usize = 20;
+
+// Intervalo entre as verificações das tabelas
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+// Resolução dos destinatários: aliases, mapas virtuais e caixas locais. Uma
+// por geração da configuração, compartilhada pelo SharedConfig.
+pub struct Recipients {
+    config: Arc<Config>,
+    aliases: Option<Arc<Table>>,
+    virtual_aliases: Option<Arc<Table>>,
+    mailboxes: Option<Arc<Table>>,
+    // Domínios das chaves dos mapas virtuais
+    virtual_domains: HashSet<String>,
+    mailbox_domains: HashSet<String>,
+    // Data de modificação de cada tabela quando foi lida
+    modified: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+impl Recipients {
+    pub fn load(config: Arc<Config>) -> Result<Self, RecipientError> {
+        let paths = &config.recipients;
+        // Antes da leitura: uma alteração no meio dela é vista na próxima
+        let modified = [
+            &paths.aliases_path,
+            &paths.virtual_alias_path,
+            &paths.virtual_mailbox_path,
+        ]
+        .into_iter()
+        .flatten()
+        .map(|path| (path.clone(), modified(path)))
+        .collect();
+
+        let load = |path: &Option<PathBuf>, format| match path {
+            Some(path) => table::load(path, format).map(Some),
+            None => Ok(None),
+        };
+        let aliases = load(&paths.aliases_path, Format::Aliases)?;
+        let virtual_aliases = load(&paths.virtual_alias_path, Format::Map)?;
+        let mailboxes = load(&paths.virtual_mailbox_path, Format::Map)?;
+
+        let mailbox_domains = key_domains(mailboxes.as_deref());
+        let mut virtual_domains = key_domains(virtual_aliases.as_deref());
+        virtual_domains.extend(mailbox_domains.iter().cloned());
+
+        Ok(Self {
+            config,
+            aliases,
+            virtual_aliases,
+            mailboxes,
+            virtual_domains,
+            mailbox_domains,
+            modified,
+        })
+    }
+
+    pub fn config(&self) -> &Arc<Config> {
+        &self.config
+    }
+
+    // Alguma tabela mudou desde a leitura
+    pub fn is_stale(&self) -> bool {
+        self.modified.iter().any(|(path, at)| modified(path) != *at)
+    }
+
+    // Domínios cujo destino final é este servidor, incluindo o dos endereços
+    // SRS
+    pub fn is_local_domain(&self, domain: &str) -> bool {
+        let srs = &self.config.srs;
+        self.config.local_delivery.is_local(domain)
+            || self.virtual_domains.contains(&domain.to_ascii_lowercase())
+            || (srs.enabled
+                && domain.eq_ignore_ascii_case(srs.domain(&self.config.server.hostname)))
+    }
+
+    // Domínios entregues nas Maildirs quando não há rota
+    pub fn is_mailbox_domain(&self, domain: &str) -> bool {
+        self.config.local_delivery.is_local(domain)
+            || self.mailbox_domains.contains(&domain.to_ascii_lowercase())
+    }
+
+    // Maildir de uma caixa do mapa virtual; vazio usa local_delivery.maildir
+    pub fn mailbox_path(&self, address: &str) -> Option<String> {
+        self.mailbox_entry(address)
+            .map(|values| values.first().cloned().unwrap_or_default())
+    }
+
+    // Endereços finais de um destinatário, sem repetições. Endereços de
+    // outros domínios ficam como estão (encaminhamento).
+    pub fn resolve(&self, address: &str) -> Result<Vec<String>, RecipientError> {
+        let mut resolved = Vec::new();
+        self.expand(address, &mut Vec::new(), &mut resolved)?;
+        Ok(resolved)
+    }
+
+    // resolve() numa thread de bloqueio: a procura das Maildirs consulta o
+    // sistema de arquivos e não pode parar as sessões
+    pub async fn resolve_blocking(
+        self: &Arc<Self>,
+        address: &str,
+    ) -> Result<Vec<String>, RecipientError> {
+        let recipients = self.clone();
+        let address = address.to_string();
+        tokio::task::spawn_blocking(move || recipients.resolve(&address))
+            .await
+            .unwrap_or_else(|e| Err(RecipientError::TableError(e.to_string())))
+    }
+
+    fn expand(
+        &self,
+        address: &str,
+        path: &mut Vec<String>,
+        resolved: &mut Vec<String>,
+    ) -> Result<(), RecipientError> {
+        let lowercase = address.to_ascii_lowercase();
+        if path.contains(&lowercase) || path.len() >= MAX_DEPTH {
+            return Err(RecipientError::LoopError(address.to_string()));
+        }
+        // Bounce de uma mensagem encaminhada: volta para o remetente original
+        if let Some(sender) = srs::reverse(&self.config, address)? {
+            path.push(lowercase);
+            self.expand(&sender, path, resolved)?;
+            path.pop();
+            return Ok(());
+        }
+
+        let (local_part, domain) = address.rsplit_once('@').unwrap_or((address, ""));
+        if !self.is_local_domain(domain) {
+            push_unique(resolved, address);
+            return Ok(());
+        }
+        let delimiter = &self.config.local_delivery.recipient_delimiter;
+        let (user, _) = email_helper::split_detail(local_part, delimiter);
+        let base = format!("{}@{}", user, domain).to_ascii_lowercase();
+
+        // O catch-all só vale para quem não tem caixa
+        let targets = match self.aliases_for(&lowercase, &base, domain) {
+            Some(targets) => targets,
+            None if self.is_mailbox(address, &base, user, domain) => {
+                push_unique(resolved, address);
+                return Ok(());
+            }
+            None => self
+                .virtual_aliases
+                .as_ref()
+                .and_then(|t| t.get(&format!("@{}", domain.to_ascii_lowercase())))
+                .cloned()
+                .unwrap_or_default(),
+        };
+        let targets: Vec<String> = targets
+            .iter()
+            .filter(|target| supported(target))
+            .map(|target| qualify(target, domain))
+            .collect();
+        if targets.is_empty() {
+            return Err(RecipientError::UnknownError(address.to_string()));
+        }
+
+        path.push(lowercase.clone());
+        for target in targets {
+            // Um alias que inclui o próprio endereço entrega também na caixa,
+            // se ela existir
+            if target.eq_ignore_ascii_case(&lowercase) || target.eq_ignore_ascii_case(&base) {
+                if self.is_mailbox(address, &base, user, domain) {
+                    push_unique(resolved, address);
+                }
+                continue;
+            }
+            self.expand(&target, path, resolved)?;
+        }
+        path.pop();
+        Ok(())
+    }
+
+    // Mapa virtual pelo endereço e pelo endereço sem o subendereço; depois o
+    // /etc/aliases, só para os local_domains
+    fn aliases_for(&self, address: &str, base: &str, domain: &str) -> Option<Vec<String>> {
+        if let Some(virtual_aliases) = &self.virtual_aliases
+            && let Some(targets) = virtual_aliases
+                .get(address)
+                .or_else(|| virtual_aliases.get(base))
+        {
+            return Some(targets.clone());
+        }
+        if !self.config.local_delivery.is_local(domain) {
+            return None;
+        }
+        let aliases = self.aliases.as_ref()?;
+        let local_part = |a: &str| a.rsplit_once('@').map(|(l, _)| l.to_string());
+        local_part(address)
+            .and_then(|l| aliases.get(&l))
+            .or_else(|| local_part(base).and_then(|l| aliases.get(&l)))
+            .cloned()
+    }
+
+    fn mailbox_entry(&self, address: &str) -> Option<&Vec<String>> {
+        let mailboxes = self.mailboxes.as_ref()?;
+        let (local_part, domain) = address.rsplit_once('@')?;
+        let delimiter = &self.config.local_delivery.recipient_delimiter;
+        let (user, _) = email_helper::split_detail(local_part, delimiter);
+        mailboxes
+            .get(&address.to_ascii_lowercase())
+            .or_else(|| mailboxes.get(&format!("{}@{}", user, domain).to_ascii_lowercase()))
+    }
+
+    fn is_mailbox(&self, address: &str, base: &str, user: &str, domain: &str) -> bool {
+        if self.mailbox_entry(address).is_some() {
+            return true;
+        }
+        let local = &self.config.local_delivery;
+        if !local.is_local(domain) {
+            return false;
+        }
+
+        // Com outro transporte, quem decide é o destino (LMTP, mbox)
+        let delimiter = &local.recipient_delimiter;
+        if self
+            .config
+            .delivery
+            .route(base, delimiter)
+            .is_some_and(|transport| transport != "maildir")
+        {
+            return true;
+        }
+        let user = user.to_ascii_lowercase();
+        local.autocreate
+            || (local_delivery::is_safe_name(&user)
+                && maildir::exists(&local_delivery::expand_path(&local.maildir, &user, domain)))
+    }
+}
+
+// Relê as tabelas que mudaram, sem esperar um reload
+pub async fn watch(shared_config: Arc<SharedConfig>, mut shutdown: Shutdown) {
+    // Último erro registrado, para não repetir a cada verificação
+    let mut failed = None;
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(CHECK_INTERVAL) => {}
+            _ = shutdown.wait() => break,
+        }
+
+        let shared = shared_config.clone();
+        match tokio::task::spawn_blocking(move || shared.refresh_recipients()).await {
+            Ok(Ok(reloaded)) => {
+                if reloaded {
+                    tracing::info!("Tabelas de destinatários recarregadas");
+                }
+                failed = None;
+            }
+            Ok(Err(e)) => {
+                let message = e.to_string();
+                if failed.as_ref() != Some(&message) {
+                    tracing::error!("{}; mantendo as tabelas anteriores", message);
+                    failed = Some(message);
+                }
+            }
+            Err(e) => tracing::error!("Erro ao recarregar as tabelas: {}", e),
+        }
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn key_domains(table: Option<&Table>) -> HashSet<String> {
+    table
+        .map(|table| {
+            table
+                .keys()
+                .filter_map(|key| key.rsplit_once('@'))
+                .map(|(_, domain)| domain.to_string())
+                .filter(|domain| !domain.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// Comandos, arquivos e :include: do /etc/aliases não são suportados
+fn supported(target: &str) -> bool {
+    let supported = !target.starts_with(['|', '/', ':', '"']);
+    if !supported {
+        tracing::warn!("Destino de alias não suportado ignorado: {}", target);
+    }
+    supported
+}
+
+// Destinos sem domínio ficam no domínio do alias
+fn qualify(target: &str, domain: &str) -> String {
+    if target.contains('@') {
+        target.to_string()
+    } else {
+        format!("{}@{}", target, domain)
+    }
+}
+
+fn push_unique(resolved: &mut Vec<String>, address: &str) {
+    if !resolved.iter().any(|a| a.eq_ignore_ascii_case(address)) {
+        resolved.push(address.to_string());
+    }
+}
@@ -0,0 +1,37 @@
+use std::{error::Error, fmt};
+
+use crate::srs::srs_error::SrsError;
+
+#[derive(Debug)]
+pub enum RecipientError {
+    // Destinatário de um domínio local sem caixa nem alias
+    UnknownError(String),
+    // Expansão que volta a um endereço já visitado ou vai fundo demais
+    LoopError(String),
+    TableError(String),
+    // Bounce para um endereço SRS que não validou
+    SrsError(SrsError),
+}
+
+impl fmt::Display for RecipientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecipientError::UnknownError(address) => {
+                write!(f, "Destinatário desconhecido: {}", address)
+            }
+            RecipientError::LoopError(address) => {
+                write!(f, "Loop na expansão de aliases em {}", address)
+            }
+            RecipientError::TableError(e) => write!(f, "Erro na tabela de destinatários: {}", e),
+            RecipientError::SrsError(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for RecipientError {}
+
+impl From<SrsError> for RecipientError {
+    fn from(err: SrsError) -> Self {
+        RecipientError::SrsError(err)
+    }
+}
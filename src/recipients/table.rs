@@ -0,0 +1,114 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, OnceLock},
+    time::SystemTime,
+};
+
+use crate::recipients::recipient_error::RecipientError;
+
+// Chave (em minúsculas) e seus valores
+pub type Table = HashMap<String, Vec<String>>;
+
+// Separador entre a chave e os valores no formato do Postfix
+#[derive(Clone, Copy)]
+pub enum Format {
+    // /etc/aliases: "nome: destino, destino"
+    Aliases,
+    // virtual: "chave destino, destino"
+    Map,
+}
+
+// Tabelas já lidas, com a data de modificação do arquivo
+type Cache = HashMap<PathBuf, (SystemTime, Arc<Table>)>;
+
+static CACHE: OnceLock<Mutex<Cache>> = OnceLock::new();
+
+pub fn load(path: &Path, format: Format) -> Result<Arc<Table>, RecipientError> {
+    let error = |e: &dyn std::fmt::Display| {
+        RecipientError::TableError(format!("{}: {}", path.display(), e))
+    };
+    let modified = std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .map_err(|e| error(&e))?;
+
+    let cache = CACHE.get_or_init(|| Mutex::new(HashMap::new()));
+    if let Some((cached_at, table)) = cache.lock().unwrap_or_else(|e| e.into_inner()).get(path)
+        && *cached_at == modified
+    {
+        return Ok(table.clone());
+    }
+
+    let content = std::fs::read_to_string(path).map_err(|e| error(&e))?;
+    let table = if path.extension().is_some_and(|ext| ext == "toml") {
+        parse_toml(&content).map_err(|e| error(&e))?
+    } else {
+        parse(&content, format)
+    };
+    let table = Arc::new(table);
+    cache
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(path.to_path_buf(), (modified, table.clone()));
+    Ok(table)
+}
+
+// Chaves com uma string ou uma lista de strings
+fn parse_toml(content: &str) -> Result<Table, String> {
+    let value: toml::Table = content
+        .parse()
+        .map_err(|e: toml::de::Error| e.to_string())?;
+    let mut table = Table::new();
+    for (key, value) in value {
+        let values = match value {
+            toml::Value::String(s) => split_values(&s),
+            toml::Value::Array(items) => items
+                .iter()
+                .map(|item| item.as_str().map(str::to_string))
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| format!("{}: os valores devem ser strings", key))?,
+            toml::Value::Boolean(true) => Vec::new(),
+            _ => return Err(format!("{}: use uma string ou uma lista", key)),
+        };
+        table.insert(key.to_ascii_lowercase(), values);
+    }
+    Ok(table)
+}
+
+// Linhas "chave valores", com # para comentários e linhas iniciadas por
+// espaço continuando a anterior, como no Postfix
+fn parse(content: &str, format: Format) -> Table {
+    let mut entries: Vec<String> = Vec::new();
+    for line in content.lines() {
+        if line.trim_start().starts_with('#') || line.trim().is_empty() {
+            continue;
+        }
+        match entries.last_mut() {
+            Some(entry) if line.starts_with([' ', '\t']) => {
+                entry.push(' ');
+                entry.push_str(line.trim());
+            }
+            _ => entries.push(line.trim().to_string()),
+        }
+    }
+
+    let mut table = Table::new();
+    for entry in entries {
+        let split = match format {
+            Format::Aliases => entry.split_once(':'),
+            Format::Map => entry.split_once(char::is_whitespace),
+        };
+        let (key, values) = split.unwrap_or((&entry, ""));
+        table.insert(key.trim().to_ascii_lowercase(), split_values(values));
+    }
+    table
+}
+
+fn split_values(values: &str) -> Vec<String> {
+    values
+        .split([',', ' ', '\t'])
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+        .collect()
+}
@@ -0,0 +1,169 @@
+use std::{path::PathBuf, sync::Arc};
+
+use crate::{
+    config::Config,
+    local_delivery::maildir,
+    recipients::{Recipients, recipient_error::RecipientError},
+};
+
+const TESTDATA: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/recipients/testdata");
+
+// Maildirs de ana e proprio em um diretório temporário, removido no fim
+struct Fixture {
+    dir: PathBuf,
+    recipients: Arc<Recipients>,
+}
+
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn fixture(virtual_table: &str) -> Fixture {
+    let dir = std::env::temp_dir().join(format!("smtp-recipients-{}", uuid::Uuid::new_v4()));
+    for user in ["ana", "proprio"] {
+        maildir::create(&dir.join("example.com").join(user), false).unwrap();
+    }
+
+    let mut config: Config = toml::from_str("[server]\nhostname = \"mx.example.com\"").unwrap();
+    config.local_delivery.local_domains = vec!["example.com".to_string()];
+    config.local_delivery.maildir = format!("{}/{{domain}}/{{user}}", dir.display());
+    config.recipients.aliases_path = Some(format!("{}/aliases", TESTDATA).into());
+    config.recipients.virtual_alias_path = Some(format!("{}/{}", TESTDATA, virtual_table).into());
+    config.recipients.virtual_mailbox_path = Some(format!("{}/mailboxes.toml", TESTDATA).into());
+
+    let recipients = Arc::new(Recipients::load(Arc::new(config)).unwrap());
+    Fixture { dir, recipients }
+}
+
+fn resolve(recipients: &Recipients, address: &str) -> Vec<String> {
+    recipients
+        .resolve(address)
+        .unwrap_or_else(|e| panic!("{}: {}", address, e))
+}
+
+#[test]
+fn aliases() {
+    let fixture = fixture("virtual");
+    let recipients = &fixture.recipients;
+
+    assert_eq!(
+        resolve(recipients, "postmaster@example.com"),
+        ["ana@example.com"]
+    );
+    // Linha de continuação e destino de outro domínio
+    assert_eq!(
+        resolve(recipients, "Equipe@example.com"),
+        ["ana@example.com", "bia@example.org"]
+    );
+    // Um alias que inclui o próprio endereço entrega também na caixa
+    assert_eq!(
+        resolve(recipients, "proprio@example.com"),
+        ["proprio@example.com", "ana@example.com"]
+    );
+    // Comandos não são suportados: sem outro destino, o endereço não existe
+    assert!(matches!(
+        recipients.resolve("comando@example.com"),
+        Err(RecipientError::UnknownError(_))
+    ));
+    assert!(matches!(
+        recipients.resolve("ninguem@example.com"),
+        Err(RecipientError::UnknownError(_))
+    ));
+    // Domínios remotos ficam como estão
+    assert_eq!(resolve(recipients, "x@remote.org"), ["x@remote.org"]);
+}
+
+#[test]
+fn loop_detection() {
+    let fixture = fixture("virtual");
+    assert!(matches!(
+        fixture.recipients.resolve("laco-a@example.com"),
+        Err(RecipientError::LoopError(_))
+    ));
+}
+
+// O mapa virtual tem o mesmo resultado no formato do Postfix e em TOML
+#[test]
+fn virtual_map() {
+    for table in ["virtual", "virtual.toml"] {
+        let fixture = fixture(table);
+        let recipients = &fixture.recipients;
+
+        assert_eq!(
+            resolve(recipients, "info@vd.example"),
+            ["ana@example.com", "carl@vd.example"],
+            "{}",
+            table
+        );
+        // O mapa virtual vale antes do /etc/aliases nos domínios locais
+        assert_eq!(
+            resolve(recipients, "vendas@example.com"),
+            ["bia@example.org"],
+            "{}",
+            table
+        );
+        assert!(recipients.is_local_domain("vd.example"));
+        assert!(recipients.is_mailbox_domain("vd.example"));
+        assert!(!recipients.is_local_domain("remote.org"));
+    }
+}
+
+#[test]
+fn catch_all() {
+    let fixture = fixture("virtual");
+    let recipients = &fixture.recipients;
+
+    assert_eq!(
+        resolve(recipients, "qualquer@vd.example"),
+        ["ana@example.com"]
+    );
+    // O catch-all só vale para quem não tem caixa
+    assert_eq!(resolve(recipients, "carl@vd.example"), ["carl@vd.example"]);
+    assert_eq!(
+        recipients.mailbox_path("dora@vd.example").as_deref(),
+        Some("/srv/mail/dora")
+    );
+    assert_eq!(
+        recipients.mailbox_path("carl@vd.example").as_deref(),
+        Some("")
+    );
+}
+
+#[test]
+fn subaddress() {
+    let fixture = fixture("virtual");
+    let recipients = &fixture.recipients;
+
+    // A caixa mantém o +tag; os destinos de um alias não o recebem
+    assert_eq!(
+        resolve(recipients, "ana+news@example.com"),
+        ["ana+news@example.com"]
+    );
+    assert_eq!(
+        resolve(recipients, "equipe+x@example.com"),
+        ["ana@example.com", "bia@example.org"]
+    );
+    assert_eq!(
+        resolve(recipients, "carl+x@vd.example"),
+        ["carl+x@vd.example"]
+    );
+    assert!(matches!(
+        recipients.resolve("ninguem+x@example.com"),
+        Err(RecipientError::UnknownError(_))
+    ));
+}
+
+#[tokio::test]
+async fn resolve_blocking() {
+    let fixture = fixture("virtual");
+    assert_eq!(
+        fixture
+            .recipients
+            .resolve_blocking("postmaster@example.com")
+            .await
+            .unwrap(),
+        ["ana@example.com"]
+    );
+}
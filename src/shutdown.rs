@@ -0,0 +1,73 @@
+use tokio::{
+    signal::unix::{Signal, SignalKind, signal},
+    sync::watch,
+};
+
+// Sinal de encerramento compartilhado entre o listener, as sessões e a fila.
+#[derive(Clone)]
+pub struct Shutdown {
+    rx: watch::Receiver<bool>,
+}
+
+pub struct ShutdownTrigger {
+    tx: watch::Sender<bool>,
+}
+
+pub fn channel() -> (ShutdownTrigger, Shutdown) {
+    let (tx, rx) = watch::channel(false);
+    (ShutdownTrigger { tx }, Shutdown { rx })
+}
+
+impl ShutdownTrigger {
+    pub fn trigger(&self) {
+        let _ = self.tx.send(true);
+    }
+}
+
+impl Shutdown {
+    pub fn is_triggered(&self) -> bool {
+        *self.rx.borrow()
+    }
+
+    // Retorna imediatamente se o encerramento já foi solicitado
+    pub async fn wait(&mut self) {
+        let _ = self.rx.wait_for(|triggered| *triggered).await;
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ServerSignal {
+    // SIGTERM / SIGINT: encerra de forma graciosa
+    Terminate,
+    // SIGUSR2: passa o listener para um novo processo e encerra
+    Upgrade,
+    // SIGHUP: recarrega a configuração
+    Reload,
+}
+
+pub struct Signals {
+    term: Signal,
+    int: Signal,
+    usr2: Signal,
+    hup: Signal,
+}
+
+impl Signals {
+    pub fn new() -> std::io::Result<Self> {
+        Ok(Self {
+            term: signal(SignalKind::terminate())?,
+            int: signal(SignalKind::interrupt())?,
+            usr2: signal(SignalKind::user_defined2())?,
+            hup: signal(SignalKind::hangup())?,
+        })
+    }
+
+    pub async fn recv(&mut self) -> ServerSignal {
+        tokio::select! {
+            _ = self.term.recv() => ServerSignal::Terminate,
+            _ = self.int.recv() => ServerSignal::Terminate,
+            _ = self.usr2.recv() => ServerSignal::Upgrade,
+            _ = self.hup.recv() => ServerSignal::Reload,
+        }
+    }
+}
@@ -6,6 +6,15 @@ pub enum DeliveryResult {
 }
 
 impl DeliveryResult {
+    // Nome da variante, usado como rótulo nas métricas
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Delivered { .. } => "delivered",
+            Self::Transient { .. } => "transient",
+            Self::Permanent { .. } => "permanent",
+        }
+    }
+
     pub fn from_smtp_code(code: u16, message: String) -> Self {
         match code {
             200..=299 => Self::Delivered {
@@ -1,28 +1,285 @@
 pub mod delivery_result;
 
-use crate::{config::Config, dkim::DkimSigner};
+use crate::{
+    arc::sealer, config::Config, dkim::DkimSigner, metrics::metrics, queue::models::DeliveryJob,
+};
 use anyhow::Result;
-use std::sync::Arc;
+use delivery_result::DeliveryResult;
+use hickory_resolver::{TokioResolver, proto::rr::RData};
+use std::{sync::Arc, time::Duration};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::TcpStream,
+};
+
+const SMTP_PORT: u16 = 25;
+pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(300);
+// Limite de linhas numa resposta multilinha, contra servidores que nunca a terminam
+const MAX_REPLY_LINES: usize = 100;
 
 pub struct SmtpClient {
     config: Arc<Config>,
     dkim_signer: Option<DkimSigner>,
+    resolver: TokioResolver,
+}
+
+enum MxLookup {
+    Hosts(Vec<String>),
+    // MX nulo (RFC 7505): o domínio não aceita emails
+    NullMx,
+    NoDomain,
 }
+
 impl SmtpClient {
     pub fn new(config: Arc<Config>) -> Result<Self> {
-        let dkim_signer = if config.dkim.enabled {
+        // As chaves de [dkim] também selam o ARC
+        let dkim_signer = if config.dkim.enabled || config.arc.seal {
             Some(DkimSigner::from_config(&config.dkim)?)
         } else {
             None
         };
 
+        let resolver = TokioResolver::builder_tokio()?.build()?;
+
         Ok(Self {
             config,
             dkim_signer,
+            resolver,
         })
     }
 
-    pub async fn deliver(&self) -> Result<delivery_result::DeliveryResult> {
-        let mx_hosts = resolve_mx();
+    pub async fn deliver(&self, job: &DeliveryJob) -> Result<DeliveryResult> {
+        let mx_hosts = match resolve_mx(&self.resolver, &job.domain).await? {
+            MxLookup::Hosts(hosts) => hosts,
+            MxLookup::NullMx => {
+                return Ok(DeliveryResult::Permanent {
+                    smtp_code: 556,
+                    message: format!("Domínio {} não aceita emails (MX nulo)", job.domain),
+                });
+            }
+            MxLookup::NoDomain => {
+                return Ok(DeliveryResult::Permanent {
+                    smtp_code: 550,
+                    message: format!("Domínio {} não existe", job.domain),
+                });
+            }
+        };
+
+        let message = self.sign(job);
+        let mut last_error = None;
+
+        for host in mx_hosts {
+            match self.deliver_to_host(&host, job, &message).await {
+                Ok(result @ DeliveryResult::Transient { .. }) => {
+                    tracing::debug!(
+                        "[{}] {} recusou temporariamente: {:?}",
+                        job.id,
+                        host,
+                        result
+                    );
+                    last_error = Some(result);
+                }
+                Ok(result) => return Ok(result),
+                Err(e) => {
+                    tracing::debug!("[{}] Falha ao entregar para {}: {}", job.id, host, e);
+                    last_error = Some(DeliveryResult::Transient {
+                        smtp_code: 421,
+                        message: format!("{}: {}", host, e),
+                    });
+                }
+            }
+        }
+
+        match last_error {
+            Some(result) => Ok(result),
+            None => anyhow::bail!("Nenhum servidor MX para {}", job.domain),
+        }
+    }
+
+    // Assina com DKIM e depois sela com ARC, conforme a configuração
+    fn sign(&self, job: &DeliveryJob) -> String {
+        let Some(signer) = &self.dkim_signer else {
+            return job.raw_message.clone();
+        };
+
+        let message = if self.config.dkim.enabled {
+            self.sign_dkim(signer, job)
+        } else {
+            job.raw_message.clone()
+        };
+        if !self.config.arc.seal {
+            return message;
+        }
+
+        let arc = &self.config.arc;
+        match sealer::seal(signer, &arc.domain, &self.config.server.hostname, &message) {
+            Ok(headers) => prepend(headers, &message),
+            Err(e) => {
+                tracing::warn!("[{}] Erro ao selar com ARC: {}", job.id, e);
+                message
+            }
+        }
+    }
+
+    fn sign_dkim(&self, signer: &DkimSigner, job: &DeliveryJob) -> String {
+        // Sem chave para o domínio do From, usa o domínio do usuário autenticado
+        let auth_domain = job
+            .auth_user
+            .as_deref()
+            .and_then(|user| user.rsplit_once('@'))
+            .map(|(_, domain)| domain);
+
+        let timer = metrics().dkim_sign_seconds.start_timer();
+        let signed = signer.sign(&job.raw_message, auth_domain);
+        timer.observe_duration();
+
+        match signed {
+            Ok(headers) => prepend(headers, &job.raw_message),
+            Err(e) => {
+                tracing::warn!("[{}] Erro ao assinar com DKIM: {}", job.id, e);
+                job.raw_message.clone()
+            }
+        }
+    }
+
+    async fn deliver_to_host(
+        &self,
+        host: &str,
+        job: &DeliveryJob,
+        message: &str,
+    ) -> Result<DeliveryResult> {
+        let stream =
+            tokio::time::timeout(CONNECT_TIMEOUT, TcpStream::connect((host, SMTP_PORT))).await??;
+        let (reader, mut writer) = stream.into_split();
+        let mut reader = BufReader::new(reader);
+
+        let (code, text) = read_reply(&mut reader).await?;
+        if code != 220 {
+            return Ok(DeliveryResult::from_smtp_code(code, text));
+        }
+
+        let commands = [
+            (format!("EHLO {}", self.config.server.hostname), 250),
+            (format!("MAIL FROM:<{}>", job.from_addr), 250),
+            (format!("RCPT TO:<{}>", job.to_addr), 250),
+            ("DATA".to_string(), 354),
+        ];
+
+        for (command, expected) in commands {
+            let (code, text) = send_command(&mut reader, &mut writer, &command).await?;
+            if code != expected {
+                let _ = send_command(&mut reader, &mut writer, "QUIT").await;
+                return Ok(DeliveryResult::from_smtp_code(code, text));
+            }
+        }
+
+        writer.write_all(dot_stuff(message).as_bytes()).await?;
+        writer.write_all(b".\r\n").await?;
+        let (code, text) = read_reply(&mut reader).await?;
+
+        let _ = send_command(&mut reader, &mut writer, "QUIT").await;
+
+        Ok(DeliveryResult::from_smtp_code(code, text))
+    }
+}
+
+async fn resolve_mx(resolver: &TokioResolver, domain: &str) -> Result<MxLookup> {
+    let lookup = match resolver.mx_lookup(format!("{}.", domain)).await {
+        Ok(lookup) => lookup,
+        Err(e) if e.is_nx_domain() => return Ok(MxLookup::NoDomain),
+        // Sem registros MX: usa o próprio domínio (RFC 5321 §5.1)
+        Err(e) if e.is_no_records_found() => return Ok(MxLookup::Hosts(vec![domain.to_string()])),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut records: Vec<_> = lookup
+        .answers()
+        .iter()
+        .filter_map(|r| match &r.data {
+            RData::MX(mx) => Some((mx.preference, mx.exchange.clone())),
+            _ => None,
+        })
+        .collect();
+    records.sort_by_key(|(preference, _)| *preference);
+
+    if records.iter().any(|(_, exchange)| exchange.is_root()) {
+        return Ok(MxLookup::NullMx);
+    }
+
+    Ok(MxLookup::Hosts(
+        records
+            .into_iter()
+            .map(|(_, exchange)| exchange.to_ascii().trim_end_matches('.').to_string())
+            .collect(),
+    ))
+}
+
+pub async fn send_command<R, W>(
+    reader: &mut BufReader<R>,
+    writer: &mut W,
+    command: &str,
+) -> Result<(u16, String)>
+where
+    R: tokio::io::AsyncRead + Unpin,
+    W: tokio::io::AsyncWrite + Unpin,
+{
+    writer
+        .write_all(format!("{}\r\n", command).as_bytes())
+        .await?;
+    read_reply(reader).await
+}
+
+// Lê uma resposta completa, incluindo as linhas de continuação ("250-...")
+pub async fn read_reply<R>(reader: &mut BufReader<R>) -> Result<(u16, String)>
+where
+    R: tokio::io::AsyncRead + Unpin,
+{
+    let mut lines = Vec::new();
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+        let n = tokio::time::timeout(COMMAND_TIMEOUT, reader.read_line(&mut line)).await??;
+        if n == 0 {
+            anyhow::bail!("Conexão fechada pelo servidor remoto");
+        }
+
+        let trimmed = line.trim_end_matches(['\r', '\n']);
+        let Some(code) = trimmed.get(..3).and_then(|code| code.parse::<u16>().ok()) else {
+            anyhow::bail!("Resposta SMTP inválida: {}", trimmed);
+        };
+
+        if lines.len() >= MAX_REPLY_LINES {
+            anyhow::bail!("Resposta SMTP com linhas demais");
+        }
+        lines.push(trimmed.get(4..).unwrap_or_default().to_string());
+
+        if trimmed.as_bytes().get(3) != Some(&b'-') {
+            return Ok((code, lines.join(" ")));
+        }
+    }
+}
+
+// Normaliza as quebras de linha para CRLF e aplica o "dot-stuffing" (RFC 5321 §4.5.2)
+pub fn dot_stuff(message: &str) -> String {
+    let mut out = String::with_capacity(message.len() + 64);
+    for line in message.lines() {
+        if line.starts_with('.') {
+            out.push('.');
+        }
+        out.push_str(line);
+        out.push_str("\r\n");
+    }
+    out
+}
+
+fn prepend(headers: Vec<String>, message: &str) -> String {
+    let mut out = String::new();
+    for header in headers {
+        out.push_str(&header);
+        out.push_str("\r\n");
     }
+    out.push_str(message);
+    out
 }
@@ -0,0 +1,82 @@
+use std::{
+    os::fd::{AsRawFd, FromRawFd, RawFd},
+    process::{Child, Command},
+};
+use tokio::net::TcpListener;
+
+// Descritores herdados do processo anterior durante uma atualização, um
+// por listener
+pub const LISTEN_FD_ENV: &str = "SMTP_LISTEN_FD";
+pub const SUBMISSION_FD_ENV: &str = "SMTP_SUBMISSION_FD";
+
+// Papel do listener: recepção de outros MTAs ou submissão de clientes
+// (RFC 6409), que passa pelas correções de MSA
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListenerRole {
+    Mta,
+    Submission,
+}
+
+pub fn bind_or_inherit(addr: &str, fd_env: &str) -> std::io::Result<TcpListener> {
+    let listener = match std::env::var(fd_env)
+        .ok()
+        .and_then(|v| v.parse::<RawFd>().ok())
+    {
+        Some(fd) => {
+            tracing::info!("Reutilizando listener herdado (fd {})", fd);
+            // SAFETY: o descritor foi aberto pelo processo anterior e passado
+            // explicitamente via fd_env; nenhum outro código o utiliza.
+            unsafe { std::net::TcpListener::from_raw_fd(fd) }
+        }
+        None => std::net::TcpListener::bind(addr)?,
+    };
+
+    set_cloexec(listener.as_raw_fd(), true)?;
+    listener.set_nonblocking(true)?;
+    TcpListener::from_std(listener)
+}
+
+// Inicia um novo processo com o mesmo executável e argumentos, repassando os
+// sockets em escuta. Conexões que chegam durante a troca ficam no backlog do
+// kernel até que o novo processo as aceite.
+pub fn spawn_successor(listeners: &[(&str, &TcpListener)]) -> std::io::Result<Child> {
+    let exe = std::env::current_exe()?;
+    let mut command = Command::new(exe);
+    command
+        .args(std::env::args_os().skip(1))
+        .env_remove(SUBMISSION_FD_ENV);
+
+    for (fd_env, listener) in listeners {
+        let fd = listener.as_raw_fd();
+        set_cloexec(fd, false)?;
+        command.env(fd_env, fd.to_string());
+    }
+    let child = command.spawn();
+    for (_, listener) in listeners {
+        set_cloexec(listener.as_raw_fd(), true)?;
+    }
+
+    child
+}
+
+fn set_cloexec(fd: RawFd, enabled: bool) -> std::io::Result<()> {
+    // SAFETY: fcntl com F_GETFD/F_SETFD apenas altera as flags do descritor.
+    unsafe {
+        let flags = libc::fcntl(fd, libc::F_GETFD);
+        if flags < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        let flags = if enabled {
+            flags | libc::FD_CLOEXEC
+        } else {
+            flags & !libc::FD_CLOEXEC
+        };
+
+        if libc::fcntl(fd, libc::F_SETFD, flags) < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+
+    Ok(())
+}
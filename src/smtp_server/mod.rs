@@ -1,17 +1,29 @@
 mod error;
-mod response_builder;
+pub mod listener;
+pub mod pipeline;
+pub mod response_builder;
+mod sasl;
+mod submission;
 
-use std::sync::Arc;
-use tokio::{io::AsyncBufReadExt, io::AsyncWriteExt, io::BufReader, net::TcpStream};
-use uuid::Uuid;
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+use tokio::{io::AsyncBufReadExt, io::AsyncRead, io::AsyncWrite, io::AsyncWriteExt, io::BufReader};
 
 use crate::{
+    auth,
     config::Config,
     helpers::email_helper::{self, extract_from_angle_brackets},
+    metrics::{self, metrics},
     plugins::EmailContext,
-    smtp_server::error::SmtpError,
+    queue::spool::Spool,
+    recipients::{Recipients, recipient_error::RecipientError},
+    shutdown::Shutdown,
+    smtp_server::{error::SmtpError, listener::ListenerRole, sasl::Exchange},
 };
 
+// Falhas de AUTH antes de encerrar a sessão, e a espera depois de cada uma
+const MAX_AUTH_FAILURES: u32 = 3;
+const AUTH_FAILURE_DELAY: Duration = Duration::from_secs(2);
+
 #[derive(Debug, PartialEq)]
 enum SessionState {
     Greeting,
@@ -24,82 +36,171 @@ enum SessionState {
 
 pub struct SmtpSession {
     config: Arc<Config>,
+    recipients: Arc<Recipients>,
     state: SessionState,
     peer_addr: String,
+    role: ListenerRole,
     helo_domain: Option<String>,
+    // A sessão começou com EHLO
+    esmtp: bool,
+    // STARTTLS ainda não é suportado
+    tls: bool,
+    // Login depois de um AUTH bem-sucedido
+    auth_user: Option<String>,
+    auth_failures: u32,
+    // AUTH em andamento, esperando a próxima resposta do cliente
+    sasl: Option<Exchange>,
     ctx: Option<EmailContext>,
+    spool: Arc<Spool>,
+    shutdown: Shutdown,
 }
 
 impl SmtpSession {
-    pub fn new(config: Arc<Config>, peer_addr: String) -> Self {
+    pub fn new(
+        config: Arc<Config>,
+        recipients: Arc<Recipients>,
+        peer_addr: String,
+        role: ListenerRole,
+        spool: Arc<Spool>,
+        shutdown: Shutdown,
+    ) -> Self {
         Self {
             config,
+            recipients,
             state: SessionState::Greeting,
             peer_addr,
+            role,
             helo_domain: None,
+            esmtp: false,
+            tls: false,
+            auth_user: None,
+            auth_failures: 0,
+            sasl: None,
             ctx: None,
+            spool,
+            shutdown,
         }
     }
 
-    pub async fn run(&mut self, stream: TcpStream) -> Result<(), SmtpError> {
-        let (reader, mut writer) = stream.into_split();
+    // Uma conexão TCP ou, no `sendmail -bs`, a entrada e a saída padrão
+    pub async fn run<S>(&mut self, stream: S) -> Result<(), SmtpError>
+    where
+        S: AsyncRead + AsyncWrite,
+    {
+        let (reader, mut writer) = tokio::io::split(stream);
         let mut reader = BufReader::new(reader);
+        let _active = metrics::ActiveSession::open();
 
-        writer
-            .write_all(
-                response_builder::service_ready_response(
-                    &self.config.server.hostname,
-                    &self.config.server.banner,
-                )
-                .as_bytes(),
-            )
-            .await?;
+        let greeting = response_builder::service_ready_response(
+            &self.config.server.hostname,
+            &self.config.server.banner,
+        );
+        self.send(&mut writer, &greeting).await?;
         self.state = SessionState::Ehlo;
 
         let mut line = String::new();
+        let mut shutdown = self.shutdown.clone();
 
         loop {
             line.clear();
-            let n = reader.read_line(&mut line).await?;
+            let n = tokio::select! {
+                n = reader.read_line(&mut line) => n?,
+                _ = shutdown.wait() => 0,
+            };
+            if self.shutdown.is_triggered() {
+                tracing::debug!(
+                    "[{}] Encerrando sessão: servidor em desligamento",
+                    self.peer_addr
+                );
+                let response =
+                    response_builder::shutting_down_response(&self.config.server.hostname);
+                self.send(&mut writer, &response).await?;
+                break;
+            }
             if n == 0 {
                 tracing::debug!("Conexão fechada por {}", self.peer_addr);
                 break;
             }
+            metrics().bytes_received.inc_by(n as u64);
 
             let cmd = line.trim_end_matches(['\r', '\n']).to_string();
-            tracing::debug!("[{}] C: {}", self.peer_addr, cmd);
+            tracing::debug!("[{}] C: {}", self.peer_addr, self.redact(&cmd));
 
             let response = self.handle_command(&cmd).await;
-
-            tracing::debug!("[{}] S: {}", self.peer_addr, response.trim());
-            writer.write_all(response.as_bytes()).await?;
+            self.send(&mut writer, &response).await?;
 
             if self.state == SessionState::Quit {
                 break;
             }
 
             if self.state == SessionState::Data {
-                let body = self.read_data(&mut reader).await?;
+                // Durante o desligamento, uma transação em DATA ainda tem
+                // shutdown_timeout_secs para terminar
+                let deadline = Duration::from_secs(self.config.server.shutdown_timeout_secs);
+                let body = tokio::select! {
+                    body = self.read_data(&mut reader) => body?,
+                    _ = async { shutdown.wait().await; tokio::time::sleep(deadline).await } => {
+                        tracing::warn!("[{}] Prazo de desligamento esgotado durante DATA", self.peer_addr);
+                        let response = response_builder::shutting_down_response(&self.config.server.hostname);
+                        self.send(&mut writer, &response).await?;
+                        break;
+                    }
+                };
                 let resp = self.handle_data_complete(body).await;
-                writer.write_all(resp.as_bytes()).await?;
+                self.send(&mut writer, &resp).await?;
             }
         }
 
         Ok(())
     }
+
+    async fn send<W>(&self, writer: &mut W, response: &str) -> Result<(), SmtpError>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        tracing::debug!("[{}] S: {}", self.peer_addr, response.trim());
+        metrics::record_reply(response);
+        writer.write_all(response.as_bytes()).await?;
+        writer.flush().await?;
+        Ok(())
+    }
+
+    // Credenciais do AUTH não vão para o log
+    fn redact(&self, cmd: &str) -> String {
+        if self.sasl.is_some() {
+            return "<credenciais>".to_string();
+        }
+        let mut words = cmd.split_whitespace();
+        match (words.next(), words.next(), words.next()) {
+            (Some(verb), Some(mechanism), Some(_)) if verb.eq_ignore_ascii_case("AUTH") => {
+                format!("{} {} <credenciais>", verb, mechanism)
+            }
+            _ => cmd.to_string(),
+        }
+    }
+
     async fn handle_command(&mut self, cmd: &str) -> String {
+        if let Some(exchange) = self.sasl.take() {
+            return self.continue_auth(exchange, cmd).await;
+        }
+
         let upper = cmd.to_uppercase();
+        metrics::record_command(&upper);
 
         if upper.starts_with("EHLO") || upper.starts_with("HELO") {
             return self.cmd_ehlo(cmd);
         }
 
+        if upper == "AUTH" || upper.starts_with("AUTH ") {
+            return self.cmd_auth(cmd).await;
+        }
+
         if upper.starts_with("MAIL FROM") {
-            return self.cmd_mail_from(cmd);
+            return self.cmd_mail_from(cmd).await;
         }
 
         if upper.starts_with("RCPT TO") {
-            return self.cmd_rcpt_to(cmd);
+            return self.cmd_rcpt_to(cmd).await;
         }
 
         if upper == "DATA" {
@@ -117,32 +218,180 @@ impl SmtpSession {
     fn cmd_ehlo(&mut self, cmd: &str) -> String {
         let parts: Vec<&str> = cmd.splitn(2, ' ').collect();
         self.helo_domain = parts.get(1).map(|s| s.to_string());
+        self.esmtp = cmd.to_uppercase().starts_with("EHLO");
         self.state = SessionState::MailFrom;
 
         let hostname = &self.config.server.hostname;
         let max_size = &self.config.server.max_message_size_mb * 1024 * 1024;
 
-        response_builder::ehlo_response(hostname, &self.peer_addr, max_size)
+        let auth = &self.config.auth;
+        let mechanisms =
+            (auth.enabled && self.auth_allowed()).then_some(auth.mechanisms.as_slice());
+
+        response_builder::ehlo_response(hostname, &self.peer_addr, max_size, mechanisms)
+    }
+
+    // AUTH (RFC 4954), com ou sem a resposta inicial
+    async fn cmd_auth(&mut self, cmd: &str) -> String {
+        if !self.config.auth.enabled {
+            return response_builder::command_not_implemented_response();
+        }
+        if !self.auth_allowed() {
+            return response_builder::encryption_required_response();
+        }
+        // Só depois do EHLO, fora de uma transação e uma vez por sessão
+        if !self.esmtp || self.state != SessionState::MailFrom || self.auth_user.is_some() {
+            return response_builder::bad_sequence_response();
+        }
+
+        let mut words = cmd.split_whitespace().skip(1);
+        let mechanism = words.next().unwrap_or_default().to_ascii_uppercase();
+        let initial = words.next();
+        if !self
+            .config
+            .auth
+            .mechanisms
+            .iter()
+            .any(|m| m.eq_ignore_ascii_case(&mechanism))
+        {
+            return response_builder::auth_mechanism_response();
+        }
+
+        match (mechanism.as_str(), initial) {
+            ("PLAIN", Some(response)) => self.continue_auth(Exchange::Plain, response).await,
+            ("PLAIN", None) => {
+                self.sasl = Some(Exchange::Plain);
+                response_builder::auth_challenge_response("")
+            }
+            ("LOGIN", Some(response)) => {
+                self.continue_auth(Exchange::LoginUsername, response).await
+            }
+            _ => {
+                self.sasl = Some(Exchange::LoginUsername);
+                response_builder::auth_challenge_response(sasl::LOGIN_USERNAME)
+            }
+        }
     }
 
-    fn cmd_mail_from(&mut self, cmd: &str) -> String {
+    // A senha só trafega sob TLS, a menos que auth.allow_insecure permita
+    fn auth_allowed(&self) -> bool {
+        self.tls || self.config.auth.allow_insecure
+    }
+
+    async fn continue_auth(&mut self, exchange: Exchange, response: &str) -> String {
+        if response.trim() == "*" {
+            return response_builder::auth_cancelled_response();
+        }
+
+        let (mechanism, username, password) = match exchange {
+            Exchange::Plain => match sasl::plain_credentials(response) {
+                // Não permitimos agir em nome de outro usuário
+                Some(c) if !c.authzid.is_empty() && c.authzid != c.authcid => {
+                    return self.auth_failed("PLAIN").await;
+                }
+                Some(c) => ("PLAIN", c.authcid, c.password),
+                None => return response_builder::auth_malformed_response(),
+            },
+            Exchange::LoginUsername => match sasl::decode(response) {
+                Some(username) => {
+                    self.sasl = Some(Exchange::LoginPassword(username));
+                    return response_builder::auth_challenge_response(sasl::LOGIN_PASSWORD);
+                }
+                None => return response_builder::auth_malformed_response(),
+            },
+            Exchange::LoginPassword(username) => match sasl::decode(response) {
+                Some(password) => ("LOGIN", username, password),
+                None => return response_builder::auth_malformed_response(),
+            },
+        };
+
+        // O Argon2 é lento de propósito: fora das threads do runtime
+        let result =
+            auth::authenticate_blocking(self.config.clone(), username.clone(), password).await;
+        match result {
+            Ok(true) => {
+                tracing::info!("[{}] Autenticado como {}", self.peer_addr, username);
+                metrics::record_auth(mechanism, "success");
+                self.auth_user = Some(username);
+                response_builder::auth_succeeded_response()
+            }
+            Ok(false) => {
+                tracing::warn!("[{}] Falha no AUTH de {}", self.peer_addr, username);
+                self.auth_failed(mechanism).await
+            }
+            Err(e) => {
+                tracing::error!("[{}] Erro ao consultar os usuários: {}", self.peer_addr, e);
+                metrics::record_auth(mechanism, "error");
+                response_builder::auth_temporary_failure_response()
+            }
+        }
+    }
+
+    // Cada falha atrasa a resposta; depois de MAX_AUTH_FAILURES a sessão é encerrada
+    async fn auth_failed(&mut self, mechanism: &str) -> String {
+        metrics::record_auth(mechanism, "failure");
+        self.auth_failures += 1;
+        tokio::time::sleep(AUTH_FAILURE_DELAY).await;
+
+        if self.auth_failures >= MAX_AUTH_FAILURES {
+            tracing::warn!(
+                "[{}] {} falhas no AUTH, encerrando a sessão",
+                self.peer_addr,
+                self.auth_failures
+            );
+            self.state = SessionState::Quit;
+            return response_builder::too_many_auth_failures_response(&self.config.server.hostname);
+        }
+        response_builder::auth_failed_response()
+    }
+
+    async fn cmd_mail_from(&mut self, cmd: &str) -> String {
         if self.state == SessionState::Greeting {
             return response_builder::bad_sequence_response();
         }
+        // Na porta de submissão só clientes autenticados enviam (RFC 6409 §4.3)
+        if self.role == ListenerRole::Submission && self.auth_user.is_none() {
+            return response_builder::auth_required_response();
+        }
 
         let from = extract_from_angle_brackets(cmd)
             .unwrap_or_default()
             .to_string();
 
-        let id = Uuid::new_v4().to_string();
-        let ctx = EmailContext {
-            id: id.clone(),
-            from: from.clone(),
-            rcpt_to: vec![],
-            raw_headers: String::new(),
-            raw_body: String::new(),
-            metadata: Default::default(),
+        let mut ctx = EmailContext::new(&from);
+        ctx.auth_user = self.auth_user.clone();
+        // O remetente vazio (avisos, respostas automáticas) não tem dono
+        if let Some(user) = &self.auth_user
+            && !from.is_empty()
+        {
+            match auth::may_send_as(&self.config.auth, user, &from) {
+                Ok(true) => {}
+                Ok(false) => {
+                    tracing::warn!(
+                        "[{}] {} não pode enviar como {}",
+                        self.peer_addr,
+                        user,
+                        from
+                    );
+                    return response_builder::sender_not_allowed_response(&from);
+                }
+                Err(e) => {
+                    tracing::error!("[{}] Erro ao consultar os usuários: {}", self.peer_addr, e);
+                    return response_builder::local_error_response();
+                }
+            }
+        }
+
+        let source = pipeline::Source {
+            peer: &self.peer_addr,
+            helo: self.helo_domain.as_deref(),
+            esmtp: self.esmtp,
+            tls: self.tls,
+            submission: self.role == ListenerRole::Submission,
         };
+        if let Some(response) = pipeline::check_sender(&self.config, &source, &mut ctx).await {
+            return response;
+        }
 
         self.ctx = Some(ctx);
         self.state = SessionState::RcptTo;
@@ -150,14 +399,57 @@ impl SmtpSession {
         response_builder::ok_response(None)
     }
 
-    fn cmd_rcpt_to(&mut self, cmd: &str) -> String {
+    async fn cmd_rcpt_to(&mut self, cmd: &str) -> String {
         if self.state != SessionState::RcptTo {
             return response_builder::bad_sequence_response();
         }
 
-        let rcpt = email_helper::extract_from_angle_brackets(cmd)
-            .unwrap_or_default()
-            .to_string();
+        let Some(rcpt) = email_helper::extract_from_angle_brackets(cmd) else {
+            return response_builder::syntax_error_response();
+        };
+        // <postmaster> sem domínio vai para o primeiro domínio local
+        // (RFC 5321 §4.5.1)
+        let rcpt = if rcpt.eq_ignore_ascii_case("postmaster") {
+            let domain = self.config.local_delivery.local_domains.first();
+            format!(
+                "postmaster@{}",
+                domain.unwrap_or(&self.config.server.hostname)
+            )
+        } else {
+            rcpt.to_string()
+        };
+        if !email_helper::is_valid_address(&rcpt) {
+            return response_builder::bad_recipient_response(&rcpt);
+        }
+
+        // Sem AUTH, só a rede confiável envia para domínios de fora
+        let (_, domain) = rcpt.rsplit_once('@').unwrap_or_default();
+        if !self.recipients.is_local_domain(domain) && !self.may_relay() {
+            tracing::warn!("[{}] Relay negado para {}", self.peer_addr, rcpt);
+            return response_builder::relay_denied_response(&rcpt);
+        }
+
+        // Destinatários dos domínios locais precisam existir agora, em vez de
+        // virar um bounce depois
+        match self.recipients.resolve_blocking(&rcpt).await {
+            Ok(_) => {}
+            Err(RecipientError::UnknownError(_)) => {
+                tracing::info!("[{}] Destinatário desconhecido: {}", self.peer_addr, rcpt);
+                return response_builder::unknown_recipient_response(&rcpt);
+            }
+            Err(RecipientError::LoopError(address)) => {
+                tracing::warn!("[{}] Loop de aliases em {}", self.peer_addr, address);
+                return response_builder::alias_loop_response(&rcpt);
+            }
+            Err(RecipientError::SrsError(e)) => {
+                tracing::info!("[{}] {}", self.peer_addr, e);
+                return response_builder::invalid_srs_response(&rcpt);
+            }
+            Err(e @ RecipientError::TableError(_)) => {
+                tracing::error!("[{}] {}", self.peer_addr, e);
+                return response_builder::lookup_failed_response();
+            }
+        }
 
         if let Some(ctx) = &mut self.ctx {
             ctx.rcpt_to.push(rcpt);
@@ -166,15 +458,25 @@ impl SmtpSession {
         response_builder::ok_response(None)
     }
 
+    // Clientes autenticados, a rede confiável e a injeção local (sendmail)
+    fn may_relay(&self) -> bool {
+        if self.auth_user.is_some() || self.peer_addr == "local" {
+            return true;
+        }
+        self.peer_addr
+            .parse::<SocketAddr>()
+            .is_ok_and(|peer| self.config.relay.is_trusted(peer.ip()))
+    }
+
     fn cmd_data(&mut self) -> String {
         if self.state != SessionState::RcptTo {
             return response_builder::bad_sequence_response();
         }
 
-        if let Some(ctx) = &self.ctx {
-            if ctx.rcpt_to.is_empty() {
-                return response_builder::no_recipients_response();
-            }
+        if let Some(ctx) = &self.ctx
+            && ctx.rcpt_to.is_empty()
+        {
+            return response_builder::no_recipients_response();
         }
 
         self.state = SessionState::Data;
@@ -183,14 +485,18 @@ impl SmtpSession {
 
     async fn read_data<R>(&mut self, reader: &mut BufReader<R>) -> Result<String, SmtpError>
     where
-        R: tokio::io::AsyncRead + Unpin,
+        R: AsyncRead + Unpin,
     {
         let mut body = String::new();
         let mut line = String::new();
         let max = self.config.server.max_message_size_mb * 1024 * 1024;
         loop {
             line.clear();
-            reader.read_line(&mut line).await?;
+            let n = reader.read_line(&mut line).await?;
+            if n == 0 {
+                return Err(SmtpError::IoError(std::io::ErrorKind::UnexpectedEof.into()));
+            }
+            metrics().bytes_received.inc_by(n as u64);
             if line.trim_end_matches(['\r', '\n']) == "." {
                 break;
             }
@@ -212,25 +518,27 @@ impl SmtpSession {
     }
 
     async fn handle_data_complete(&mut self, raw: String) -> String {
-        let ctx = match self.ctx.as_mut() {
+        let ctx = match self.ctx.take() {
             Some(c) => c,
             None => return response_builder::transaction_failed_response(),
         };
-
-        // Separa os headers do body
-        let (headers, body) = if let Some(pos) = raw.find("\r\n\r\n") {
-            (&raw[..pos + 4], &raw[pos + 4..])
-        } else {
-            (raw.as_str(), "")
-        };
-
-        ctx.raw_headers = headers.to_string();
-        ctx.raw_body = body.to_string();
-
-        let id = ctx.id.clone();
-        self.ctx = None;
         self.state = SessionState::MailFrom;
 
-        response_builder::ok_response(Some(id.as_str()))
+        let source = pipeline::Source {
+            peer: &self.peer_addr,
+            helo: self.helo_domain.as_deref(),
+            esmtp: self.esmtp,
+            tls: self.tls,
+            submission: self.role == ListenerRole::Submission,
+        };
+        pipeline::accept_message(
+            &self.config,
+            &self.recipients,
+            &self.spool,
+            &source,
+            ctx,
+            &raw,
+        )
+        .await
     }
 }
@@ -0,0 +1,539 @@
+use std::{net::SocketAddr, sync::Arc};
+
+use crate::{
+    arc::{ArcOutcome, ArcResult, validator::ArcValidator},
+    auth,
+    config::Config,
+    dkim::{
+        message,
+        verifier::{DkimResult, DkimVerifier, SignatureResult},
+    },
+    dmarc::{DmarcEvaluator, DmarcOutcome, DmarcResult, record::Policy, report},
+    dns,
+    helpers::{auth_results_helper, email_helper, trace_helper},
+    logging::mail_log,
+    metrics::metrics,
+    plugins::EmailContext,
+    queue::{models::DeliveryJob, spool::Spool},
+    recipients::Recipients,
+    smtp_server::{response_builder, submission},
+    spf::{SpfCheck, SpfEvaluator, SpfOutcome, SpfResult},
+    srs,
+};
+
+// Origem de uma mensagem: uma sessão SMTP ou a injeção local (CLI/sendmail)
+pub struct Source<'a> {
+    pub peer: &'a str,
+    pub helo: Option<&'a str>,
+    // EHLO em vez de HELO
+    pub esmtp: bool,
+    // STARTTLS ainda não é suportado
+    pub tls: bool,
+    // Recebida na porta de submissão
+    pub submission: bool,
+}
+
+// Caminho comum de toda mensagem recebida, depois do DATA. Retorna a resposta
+// SMTP final, que a injeção local também usa para reportar o resultado.
+pub async fn accept_message(
+    config: &Config,
+    recipients: &Arc<Recipients>,
+    spool: &Spool,
+    source: &Source<'_>,
+    mut ctx: EmailContext,
+    raw: &str,
+) -> String {
+    if trace_helper::count_received(raw) > config.server.max_hops {
+        tracing::warn!("[{}] Mensagem em loop: {}", source.peer, ctx.id);
+        let response = response_builder::too_many_hops_response();
+        log_transaction(source, &ctx, raw.len(), &response);
+        return response;
+    }
+    if let Some(response) = check_from(config, &ctx, raw) {
+        log_transaction(source, &ctx, raw.len(), &response);
+        return response;
+    }
+    let raw = if source.submission && ctx.auth_user.is_some() {
+        submission::prepare(config, raw)
+    } else {
+        raw.to_string()
+    };
+    let raw = format!("{}\r\n{}", received(config, source, &ctx), raw);
+
+    let (raw, dmarc) = authenticate(config, source, &mut ctx, &raw).await;
+    let raw = raw.as_str();
+
+    // Separa os headers do body
+    let (headers, body) = if let Some(pos) = raw.find("\r\n\r\n") {
+        (&raw[..pos + 4], &raw[pos + 4..])
+    } else {
+        (raw, "")
+    };
+
+    ctx.raw_headers = headers.to_string();
+    ctx.raw_body = body.to_string();
+
+    let action = dmarc
+        .as_ref()
+        .map(|outcome| dmarc_action(config, source, outcome))
+        .unwrap_or("accept");
+    let response = match action {
+        "reject" => response_builder::dmarc_rejected_response(
+            dmarc
+                .as_ref()
+                .map(|o| o.header_from.as_str())
+                .unwrap_or_default(),
+        ),
+        _ => {
+            enqueue(
+                config,
+                recipients,
+                spool,
+                source,
+                &ctx,
+                raw,
+                action == "hold",
+            )
+            .await
+        }
+    };
+    log_transaction(source, &ctx, raw.len(), &response);
+
+    response
+}
+
+// Usuários autenticados só enviam com um From que seja uma de suas
+// identidades, como no MAIL FROM
+fn check_from(config: &Config, ctx: &EmailContext, raw: &str) -> Option<String> {
+    let user = ctx.auth_user.as_ref()?;
+    let (headers, _) = message::split(raw);
+    let addresses = message::header_fields(headers)
+        .iter()
+        .filter(|f| f.name.eq_ignore_ascii_case("from"))
+        .filter_map(|f| f.raw.split_once(':'))
+        .flat_map(|(_, value)| email_helper::parse_address_list(value))
+        .collect::<Vec<_>>();
+
+    for address in addresses {
+        match auth::may_send_as(&config.auth, user, &address) {
+            Ok(true) => {}
+            Ok(false) => {
+                tracing::warn!("[{}] {} não pode enviar como {}", ctx.id, user, address);
+                return Some(response_builder::from_not_allowed_response(&address));
+            }
+            Err(e) => {
+                tracing::error!("[{}] Erro ao consultar os usuários: {}", ctx.id, e);
+                return Some(response_builder::local_error_response());
+            }
+        }
+    }
+    None
+}
+
+// Nosso Received, acima dos que vieram com a mensagem e abaixo do
+// Authentication-Results
+fn received(config: &Config, source: &Source<'_>, ctx: &EmailContext) -> String {
+    let hide_client = source.submission && config.submission.hide_client;
+    let ip = source
+        .peer
+        .parse::<SocketAddr>()
+        .ok()
+        .map(|peer| peer.ip())
+        .filter(|_| !hide_client);
+    // A injeção local pelo socket de controle não passa por SMTP
+    let protocol = (ip.is_some() || source.helo.is_some())
+        .then(|| trace_helper::protocol(source.esmtp, source.tls, ctx.auth_user.is_some()));
+    let recipient = match ctx.rcpt_to.as_slice() {
+        [recipient] => Some(recipient.as_str()),
+        _ => None,
+    };
+
+    trace_helper::Received {
+        helo: source.helo.filter(|_| !hide_client),
+        ip,
+        by: &config.server.hostname,
+        protocol,
+        id: &ctx.id,
+        recipient,
+    }
+    .header()
+}
+
+// Verificações no MAIL FROM. Retorna a resposta quando o remetente é recusado.
+pub async fn check_sender(
+    config: &Config,
+    source: &Source<'_>,
+    ctx: &mut EmailContext,
+) -> Option<String> {
+    let spf = &config.spf;
+    // O DMARC usa o resultado do MAIL FROM mesmo sem as ações de SPF
+    if !spf.enabled && !config.dmarc.enabled {
+        return None;
+    }
+    // Clientes autenticados e a submissão não passam pelo SPF: o IP é do usuário, não do domínio
+    if source.submission || ctx.auth_user.is_some() {
+        return None;
+    }
+    // A injeção local não tem um IP para verificar
+    let Ok(peer) = source.peer.parse::<SocketAddr>() else {
+        return None;
+    };
+    let helo = source.helo.unwrap_or_default();
+
+    let evaluator = match dns::resolver() {
+        Ok(resolver) => SpfEvaluator::new(resolver, &config.server.hostname),
+        Err(e) => {
+            tracing::warn!("[{}] SPF não verificado: {}", source.peer, e);
+            return None;
+        }
+    };
+
+    if spf.enabled && spf.helo && !helo.is_empty() {
+        let sender = format!("postmaster@{}", helo);
+        let outcome = evaluator.check_host(peer.ip(), helo, &sender, helo).await;
+        record_spf(ctx, "spf.helo", ("smtp.helo", helo), &outcome);
+        if let Some(response) = spf_response(config, source, helo, &outcome) {
+            return Some(response);
+        }
+    }
+
+    // Remetente vazio (bounces): usa a identidade do HELO (RFC 7208 §2.4)
+    let sender = match ctx.from.as_str() {
+        "" => format!("postmaster@{}", helo),
+        from => from.to_string(),
+    };
+    let domain = sender.rsplit_once('@').map(|(_, d)| d).unwrap_or_default();
+    if domain.is_empty() {
+        return None;
+    }
+
+    let outcome = evaluator.check_host(peer.ip(), domain, &sender, helo).await;
+    record_spf(ctx, "spf", ("smtp.mailfrom", &sender), &outcome);
+    ctx.spf = Some(SpfCheck {
+        domain: domain.to_string(),
+        scope: if ctx.from.is_empty() { "helo" } else { "mfrom" },
+        result: outcome.result,
+    });
+    if !spf.enabled {
+        return None;
+    }
+    spf_response(config, source, domain, &outcome)
+}
+
+fn record_spf(ctx: &mut EmailContext, key: &str, identity: (&str, &str), outcome: &SpfOutcome) {
+    let result = outcome.result.to_string();
+    ctx.auth_results
+        .add("spf", &result, outcome.reason.as_deref(), &[identity]);
+    ctx.metadata.insert(key.to_string(), result);
+}
+
+fn spf_response(
+    config: &Config,
+    source: &Source<'_>,
+    domain: &str,
+    outcome: &SpfOutcome,
+) -> Option<String> {
+    let action = config.spf.action(outcome.result);
+    if action == "accept" {
+        return None;
+    }
+
+    tracing::info!(
+        "[{}] SPF {} para {} ({}): {}",
+        source.peer,
+        outcome.result,
+        domain,
+        action,
+        outcome.reason.as_deref().unwrap_or("-")
+    );
+    let text = match (&outcome.explanation, &outcome.reason) {
+        (Some(explanation), _) => explanation.clone(),
+        (None, Some(reason)) => format!("SPF {} for {}: {}", outcome.result, domain, reason),
+        (None, None) => format!("SPF {} for {}", outcome.result, domain),
+    };
+    match action {
+        "defer" => Some(response_builder::spf_deferred_response(&text)),
+        _ => Some(response_builder::spf_rejected_response(
+            outcome.result == SpfResult::PermError,
+            &text,
+        )),
+    }
+}
+
+// Verificações depois do DATA. Os resultados, junto com os do MAIL FROM,
+// ficam nos metadados e em um Authentication-Results no topo da mensagem.
+async fn authenticate(
+    config: &Config,
+    source: &Source<'_>,
+    ctx: &mut EmailContext,
+    raw: &str,
+) -> (String, Option<DmarcOutcome>) {
+    // Mensagens locais ou de usuários autenticados não passam pelo DMARC
+    let dmarc_enabled = config.dmarc.enabled
+        && ctx.auth_user.is_none()
+        && source.peer.parse::<SocketAddr>().is_ok();
+
+    let arc_enabled = config.arc.verify || config.arc.seal;
+    let dkim_enabled = config.dkim.verify || dmarc_enabled;
+
+    let mut dmarc = None;
+    if dkim_enabled || arc_enabled {
+        match dns::resolver() {
+            Ok(resolver) => {
+                let mut signatures = Vec::new();
+                if dkim_enabled {
+                    signatures = DkimVerifier::new(resolver.clone()).verify(raw).await;
+                    record_dkim(ctx, &signatures);
+                }
+
+                let mut arc = None;
+                if arc_enabled {
+                    let outcome = ArcValidator::new(resolver.clone()).validate(raw).await;
+                    record_arc(ctx, source, &outcome);
+                    arc = Some(outcome);
+                }
+
+                if dmarc_enabled {
+                    let mut outcome = DmarcEvaluator::new(resolver, &config.dmarc)
+                        .evaluate(raw, &signatures, ctx.spf.as_ref())
+                        .await;
+                    if let Some(arc) = &arc {
+                        trust_forwarder(config, arc, &mut outcome);
+                    }
+                    record_dmarc(ctx, &outcome);
+                    dmarc = Some(outcome);
+                }
+            }
+            Err(e) => {
+                // Sem DNS, o ARC fica sem resultado e a mensagem não é selada
+                tracing::warn!("[{}] Autenticação não verificada: {}", ctx.id, e);
+                if dkim_enabled {
+                    ctx.auth_results
+                        .add("dkim", "temperror", Some("DNS unavailable"), &[]);
+                    ctx.metadata
+                        .insert("dkim".to_string(), "temperror".to_string());
+                }
+                if dmarc_enabled {
+                    ctx.auth_results
+                        .add("dmarc", "temperror", Some("DNS unavailable"), &[]);
+                    ctx.metadata
+                        .insert("dmarc".to_string(), "temperror".to_string());
+                }
+            }
+        }
+    }
+
+    if ctx.auth_results.is_empty() {
+        return (raw.to_string(), dmarc);
+    }
+
+    let hostname = &config.server.hostname;
+    let raw = format!(
+        "{}\r\n{}",
+        ctx.auth_results.header(hostname),
+        auth_results_helper::strip_forged(raw, hostname)
+    );
+    (raw, dmarc)
+}
+
+fn record_dkim(ctx: &mut EmailContext, signatures: &[SignatureResult]) {
+    if signatures.is_empty() {
+        ctx.auth_results.add("dkim", "none", None, &[]);
+        ctx.metadata.insert("dkim".to_string(), "none".to_string());
+        return;
+    }
+
+    let mut details = Vec::new();
+    for signature in signatures {
+        let result = signature.result.to_string();
+        ctx.auth_results.add(
+            "dkim",
+            &result,
+            signature.reason.as_deref(),
+            &[
+                ("header.d", &signature.domain),
+                ("header.i", &signature.identity),
+                ("header.s", &signature.selector),
+                ("header.a", &signature.algorithm),
+                ("header.b", &signature.signature_prefix),
+            ],
+        );
+        details.push(match &signature.reason {
+            Some(reason) => format!("{} d={} ({})", result, signature.domain, reason),
+            None => format!("{} d={}", result, signature.domain),
+        });
+    }
+
+    // Basta uma assinatura válida; sem nenhuma, vale o resultado da primeira
+    let overall = signatures
+        .iter()
+        .find(|s| s.result == DkimResult::Pass)
+        .unwrap_or(&signatures[0]);
+    ctx.metadata
+        .insert("dkim".to_string(), overall.result.to_string());
+    ctx.metadata
+        .insert("dkim.details".to_string(), details.join("; "));
+}
+
+fn record_arc(ctx: &mut EmailContext, source: &Source<'_>, outcome: &ArcOutcome) {
+    let result = outcome.result.to_string();
+    let ip = source
+        .peer
+        .parse::<SocketAddr>()
+        .map(|peer| peer.ip().to_string());
+    let properties: Vec<(&str, &str)> = match &ip {
+        Ok(ip) => vec![("smtp.remote-ip", ip)],
+        Err(_) => Vec::new(),
+    };
+    ctx.auth_results
+        .add("arc", &result, outcome.reason.as_deref(), &properties);
+    ctx.metadata.insert("arc".to_string(), result);
+    if !outcome.sealers.is_empty() {
+        ctx.metadata
+            .insert("arc.sealers".to_string(), outcome.sealers.join(","));
+    }
+}
+
+// Uma cadeia ARC válida com o selo de um intermediário confiável desfaz a
+// falha de DMARC causada pelo encaminhamento (RFC 8617 §7.2.1)
+fn trust_forwarder(config: &Config, arc: &ArcOutcome, outcome: &mut DmarcOutcome) {
+    if outcome.result != DmarcResult::Fail || arc.result != ArcResult::Pass {
+        return;
+    }
+    let trusted = arc.sealers.iter().any(|sealer| {
+        config
+            .arc
+            .trusted_sealers
+            .iter()
+            .any(|t| t.eq_ignore_ascii_case(sealer))
+    });
+    if trusted {
+        outcome.disposition = Policy::None;
+        outcome.trusted_forwarder = true;
+    }
+}
+
+fn record_dmarc(ctx: &mut EmailContext, outcome: &DmarcOutcome) {
+    let result = outcome.result.to_string();
+    let mut properties = Vec::new();
+    if !outcome.header_from.is_empty() {
+        properties.push(("header.from", outcome.header_from.as_str()));
+    }
+    ctx.auth_results
+        .add("dmarc", &result, outcome.reason.as_deref(), &properties);
+    ctx.metadata.insert("dmarc".to_string(), result);
+    if let Some(policy) = &outcome.policy {
+        ctx.metadata
+            .insert("dmarc.policy".to_string(), policy.record.p.to_string());
+    }
+    if outcome.trusted_forwarder {
+        ctx.metadata
+            .insert("dmarc.override".to_string(), "arc".to_string());
+    }
+}
+
+// Ação local para a política pedida pelo domínio. O que foi de fato feito
+// entra no relatório agregado.
+fn dmarc_action<'a>(config: &'a Config, source: &Source<'_>, outcome: &DmarcOutcome) -> &'a str {
+    let action = config.dmarc.action(outcome.disposition);
+    let applied = match action {
+        "reject" => Policy::Reject,
+        "hold" => Policy::Quarantine,
+        _ => Policy::None,
+    };
+
+    if outcome.disposition != Policy::None {
+        tracing::info!(
+            "[{}] DMARC {} para {}: política {} ({})",
+            source.peer,
+            outcome.result,
+            outcome.header_from,
+            outcome.disposition,
+            action
+        );
+    }
+    if let Ok(peer) = source.peer.parse::<SocketAddr>() {
+        report::record(config, peer.ip(), outcome, applied);
+    }
+    action
+}
+
+// Com `held`, os jobs ficam retidos até serem liberados pela administração
+async fn enqueue(
+    config: &Config,
+    recipients: &Arc<Recipients>,
+    spool: &Spool,
+    source: &Source<'_>,
+    ctx: &EmailContext,
+    raw: &str,
+    held: bool,
+) -> String {
+    // Um job por endereço final, depois da expansão dos aliases
+    let mut jobs: Vec<DeliveryJob> = Vec::new();
+    for rcpt in &ctx.rcpt_to {
+        let resolved = match recipients.resolve_blocking(rcpt).await {
+            Ok(resolved) => resolved,
+            Err(e) => {
+                tracing::warn!("[{}] {}; entregando para {}", ctx.id, e, rcpt);
+                vec![rcpt.clone()]
+            }
+        };
+        for address in resolved {
+            if jobs
+                .iter()
+                .any(|j| j.to_addr.eq_ignore_ascii_case(&address))
+            {
+                continue;
+            }
+            let mut job =
+                DeliveryJob::new(&ctx.id, &ctx.from, &address, raw, config.queue.max_attempts);
+            job.auth_user = ctx.auth_user.clone();
+            job.held = held;
+            if !address.eq_ignore_ascii_case(rcpt) {
+                job.original_recipient = Some(rcpt.clone());
+                // Encaminhada para fora: o remetente passa a ser nosso, para
+                // passar no SPF do destino
+                if !recipients.is_local_domain(&job.domain) {
+                    job.from_addr = srs::forward(config, &ctx.from);
+                }
+            }
+            jobs.push(job);
+        }
+    }
+
+    for (i, job) in jobs.iter().enumerate() {
+        if let Err(e) = spool.store(job).await {
+            tracing::error!("[{}] Erro ao gravar no spool: {}", source.peer, e);
+            // Evita entregas duplicadas quando o cliente tentar novamente
+            for stored in &jobs[..i] {
+                let _ = spool.remove(&stored.id).await;
+            }
+            return response_builder::local_error_response();
+        }
+    }
+
+    response_builder::ok_response(Some(ctx.id.as_str()))
+}
+
+fn log_transaction(source: &Source<'_>, ctx: &EmailContext, size: usize, response: &str) {
+    let smtp_code = response.get(..3).and_then(|c| c.parse().ok()).unwrap_or(0);
+    let result = if (200..300).contains(&smtp_code) {
+        "accepted"
+    } else {
+        "rejected"
+    };
+
+    metrics().messages.with_label_values(&[result]).inc();
+    mail_log::transaction(&mail_log::TransactionRecord {
+        message_id: &ctx.id,
+        peer: source.peer,
+        helo: source.helo,
+        from: &ctx.from,
+        recipients: &ctx.rcpt_to,
+        size,
+        tls: None,
+        verdicts: &ctx.metadata,
+        result,
+        smtp_code,
+    });
+}
@@ -2,15 +2,23 @@ pub fn service_ready_response(hostname: &str, banner: &str) -> String {
     format!("220 {} {}\r\n", hostname, banner)
 }
 
-pub fn ehlo_response(hostname: &str, remote_addr: &str, max_size: usize) -> String {
-    let capabilities = vec![
+// Com auth, a lista de mecanismos anunciados em AUTH. STARTTLS não é anunciado
+// enquanto o servidor não tiver suporte a TLS.
+pub fn ehlo_response(
+    hostname: &str,
+    remote_addr: &str,
+    max_size: usize,
+    auth: Option<&[String]>,
+) -> String {
+    let mut capabilities = vec![
         format!("{} at your service, [{}]", hostname, remote_addr),
         format!("SIZE {}", max_size),
         "8BITMIME".to_string(),
-        "STARTTLS".to_string(),
-        "AUTH PLAIN LOGIN".to_string(),
-        "SMTPUTF8".to_string(),
     ];
+    if let Some(mechanisms) = auth {
+        capabilities.push(format!("AUTH {}", mechanisms.join(" ").to_uppercase()));
+    }
+    capabilities.push("SMTPUTF8".to_string());
 
     let mut response = String::new();
     for (i, capability) in capabilities.iter().enumerate() {
@@ -53,3 +61,128 @@ pub fn transaction_failed_response() -> String {
 pub fn quit_response(hostname: &str) -> String {
     format!("221 {} Service closing\r\n", hostname)
 }
+
+pub fn local_error_response() -> String {
+    "451 Requested action aborted: local error in processing\r\n".to_string()
+}
+
+pub fn shutting_down_response(hostname: &str) -> String {
+    format!(
+        "421 {} Service not available, closing transmission channel\r\n",
+        hostname
+    )
+}
+
+// RFC 7372: 5.7.23 para falha de SPF, x.7.24 para erros na avaliação
+pub fn too_many_connections_response(hostname: &str) -> String {
+    format!(
+        "421 4.7.0 {} Too many connections, try again later\r\n",
+        hostname
+    )
+}
+
+pub fn spf_rejected_response(error: bool, text: &str) -> String {
+    let code = if error { "5.7.24" } else { "5.7.23" };
+    format!("550 {} {}\r\n", code, text)
+}
+
+pub fn spf_deferred_response(text: &str) -> String {
+    format!("451 4.7.24 {}\r\n", text)
+}
+
+pub fn too_many_hops_response() -> String {
+    "554 5.4.6 Too many hops, possible mail loop\r\n".to_string()
+}
+
+// RFC 4954 §6
+pub fn auth_challenge_response(challenge: &str) -> String {
+    format!("334 {}\r\n", challenge)
+}
+
+pub fn auth_succeeded_response() -> String {
+    "235 2.7.0 Authentication successful\r\n".to_string()
+}
+
+pub fn auth_failed_response() -> String {
+    "535 5.7.8 Authentication credentials invalid\r\n".to_string()
+}
+
+pub fn too_many_auth_failures_response(hostname: &str) -> String {
+    format!(
+        "421 4.7.0 {} Too many authentication failures, closing connection\r\n",
+        hostname
+    )
+}
+
+pub fn auth_temporary_failure_response() -> String {
+    "454 4.7.0 Temporary authentication failure\r\n".to_string()
+}
+
+pub fn auth_cancelled_response() -> String {
+    "501 5.0.0 Authentication cancelled\r\n".to_string()
+}
+
+pub fn auth_malformed_response() -> String {
+    "501 5.5.2 Cannot decode response\r\n".to_string()
+}
+
+pub fn auth_mechanism_response() -> String {
+    "504 5.5.4 Unrecognized authentication type\r\n".to_string()
+}
+
+pub fn encryption_required_response() -> String {
+    "538 5.7.11 Encryption required for requested authentication mechanism\r\n".to_string()
+}
+
+pub fn auth_required_response() -> String {
+    "530 5.7.0 Authentication required\r\n".to_string()
+}
+
+pub fn sender_not_allowed_response(address: &str) -> String {
+    format!("553 5.7.1 Not authorized to send as <{}>\r\n", address)
+}
+
+pub fn from_not_allowed_response(address: &str) -> String {
+    format!("550 5.7.1 Not authorized to send as {}\r\n", address)
+}
+
+pub fn syntax_error_response() -> String {
+    "501 5.5.4 Syntax error in parameters or arguments\r\n".to_string()
+}
+
+pub fn bad_recipient_response(address: &str) -> String {
+    format!("553 5.1.3 <{}>: Bad recipient address syntax\r\n", address)
+}
+
+pub fn relay_denied_response(address: &str) -> String {
+    format!("554 5.7.1 <{}>: Relay access denied\r\n", address)
+}
+
+pub fn unknown_recipient_response(address: &str) -> String {
+    format!(
+        "550 5.1.1 <{}>: Recipient address rejected: User unknown\r\n",
+        address
+    )
+}
+
+pub fn alias_loop_response(address: &str) -> String {
+    format!("550 5.4.6 <{}>: Alias expansion loop\r\n", address)
+}
+
+pub fn invalid_srs_response(address: &str) -> String {
+    format!(
+        "550 5.7.1 <{}>: Invalid or expired SRS address\r\n",
+        address
+    )
+}
+
+pub fn lookup_failed_response() -> String {
+    "451 4.3.0 Temporary lookup failure\r\n".to_string()
+}
+
+pub fn dmarc_rejected_response(domain: &str) -> String {
+    format!(
+        "550 5.7.1 Email from {} rejected due to its DMARC policy\r\n",
+        domain
+    )
+}
@@ -0,0 +1,43 @@
+use base64::{Engine, engine::general_purpose::STANDARD};
+
+// Próxima resposta esperada do cliente durante um AUTH
+pub enum Exchange {
+    Plain,
+    LoginUsername,
+    LoginPassword(String),
+}
+
+// "VXNlcm5hbWU6" e "UGFzc3dvcmQ6" (draft-murchison-sasl-login)
+pub const LOGIN_USERNAME: &str = "VXNlcm5hbWU6";
+pub const LOGIN_PASSWORD: &str = "UGFzc3dvcmQ6";
+
+// "=" é a resposta vazia (RFC 4954 §4)
+pub fn decode(response: &str) -> Option<String> {
+    let response = response.trim();
+    if response == "=" {
+        return Some(String::new());
+    }
+    let bytes = STANDARD.decode(response).ok()?;
+    String::from_utf8(bytes).ok()
+}
+
+pub struct PlainCredentials {
+    pub authzid: String,
+    pub authcid: String,
+    pub password: String,
+}
+
+// PLAIN (RFC 4616): authzid NUL authcid NUL senha
+pub fn plain_credentials(response: &str) -> Option<PlainCredentials> {
+    let decoded = decode(response)?;
+    let mut parts = decoded.split('\0');
+    let (authzid, authcid, password) = (parts.next()?, parts.next()?, parts.next()?);
+    if parts.next().is_some() || authcid.is_empty() {
+        return None;
+    }
+    Some(PlainCredentials {
+        authzid: authzid.to_string(),
+        authcid: authcid.to_string(),
+        password: password.to_string(),
+    })
+}
@@ -0,0 +1,32 @@
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::{config::Config, dkim::message, helpers::email_helper};
+
+// Correções de MSA (RFC 6409 §8) nas mensagens da porta de submissão. Como
+// acontecem antes da fila, a assinatura DKIM na entrega já cobre o
+// resultado.
+pub fn prepare(config: &Config, raw: &str) -> String {
+    let submission = &config.submission;
+    let (headers, body) = message::split(raw);
+    let fields = message::header_fields(headers);
+
+    let mut headers = headers.to_string();
+    if submission.hide_client {
+        headers = email_helper::strip_header(&headers, "received");
+    }
+
+    let present = |name: &str| fields.iter().any(|f| f.name.eq_ignore_ascii_case(name));
+    if !present("date") {
+        headers.push_str(&format!("Date: {}\r\n", Utc::now().to_rfc2822()));
+    }
+    if !present("message-id") {
+        headers.push_str(&format!(
+            "Message-ID: <{}@{}>\r\n",
+            Uuid::new_v4(),
+            config.server.hostname
+        ));
+    }
+
+    format!("{}\r\n{}", headers, body)
+}
@@ -0,0 +1,149 @@
+use std::net::IpAddr;
+
+// Valores das macros de RFC 7208 §7.3
+pub struct MacroContext<'a> {
+    // Remetente completo (local@domínio); postmaster@helo quando vazio
+    pub sender: &'a str,
+    // Domínio do registro sendo avaliado
+    pub domain: &'a str,
+    pub ip: IpAddr,
+    pub helo: &'a str,
+    // %{p}: nome validado do IP, consultado só quando a macro aparece
+    pub validated: Option<&'a str>,
+    // %{r}: nosso hostname, só em explicações
+    pub receiver: &'a str,
+}
+
+// Expande um domain-spec (ou, com `explanation`, o texto de exp=)
+pub fn expand(spec: &str, ctx: &MacroContext, explanation: bool) -> Result<String, String> {
+    let mut out = String::new();
+    let mut chars = spec.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('%') => out.push('%'),
+            Some('_') => out.push(' '),
+            Some('-') => out.push_str("%20"),
+            Some('{') => {
+                let mut body = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => body.push(c),
+                        None => return Err(format!("unterminated macro in {}", spec)),
+                    }
+                }
+                out.push_str(&expand_macro(&body, ctx, explanation)?);
+            }
+            _ => return Err(format!("invalid macro in {}", spec)),
+        }
+    }
+
+    Ok(out)
+}
+
+// %{p} exige consultas extras, feitas só quando a macro aparece
+pub fn uses_validated_name(spec: &str) -> bool {
+    spec.to_ascii_lowercase().contains("%{p")
+}
+
+fn expand_macro(body: &str, ctx: &MacroContext, explanation: bool) -> Result<String, String> {
+    let invalid = || format!("invalid macro %{{{}}}", body);
+    let mut chars = body.chars();
+    let letter = chars.next().ok_or_else(invalid)?;
+
+    let value = match letter.to_ascii_lowercase() {
+        's' => ctx.sender.to_string(),
+        'l' => ctx
+            .sender
+            .rsplit_once('@')
+            .map(|(local, _)| local)
+            .unwrap_or("postmaster")
+            .to_string(),
+        'o' => ctx
+            .sender
+            .rsplit_once('@')
+            .map(|(_, domain)| domain)
+            .unwrap_or(ctx.sender)
+            .to_string(),
+        'd' => ctx.domain.to_string(),
+        'i' => dotted_ip(ctx.ip),
+        'p' => ctx.validated.unwrap_or("unknown").to_string(),
+        'v' => match ctx.ip {
+            IpAddr::V4(_) => "in-addr".to_string(),
+            IpAddr::V6(_) => "ip6".to_string(),
+        },
+        'h' => ctx.helo.to_string(),
+        'c' if explanation => ctx.ip.to_string(),
+        'r' if explanation => ctx.receiver.to_string(),
+        't' if explanation => chrono::Utc::now().timestamp().to_string(),
+        _ => return Err(invalid()),
+    };
+
+    // Transformadores: quantidade de partes à direita, "r" para inverter e
+    // os delimitadores usados para separar as partes
+    let rest: String = chars.collect();
+    let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+    let rest = &rest[digits.len()..];
+    let (reverse, delimiters) = match rest.strip_prefix(['r', 'R']) {
+        Some(delimiters) => (true, delimiters),
+        None => (false, rest),
+    };
+    if !delimiters.chars().all(|c| ".-+,/_=".contains(c)) {
+        return Err(invalid());
+    }
+
+    let delimiters: Vec<char> = match delimiters {
+        "" => vec!['.'],
+        _ => delimiters.chars().collect(),
+    };
+    let mut parts: Vec<&str> = value.split(delimiters.as_slice()).collect();
+    if reverse {
+        parts.reverse();
+    }
+    if !digits.is_empty() {
+        let keep: usize = digits.parse().map_err(|_| invalid())?;
+        if keep == 0 {
+            return Err(invalid());
+        }
+        parts.drain(..parts.len().saturating_sub(keep));
+    }
+    let value = parts.join(".");
+
+    // Letra maiúscula: o valor é codificado como em uma URL
+    if letter.is_ascii_uppercase() {
+        Ok(url_escape(&value))
+    } else {
+        Ok(value)
+    }
+}
+
+// %{i}: IPv4 como de costume, IPv6 com cada nibble separado por ponto
+fn dotted_ip(ip: IpAddr) -> String {
+    match ip {
+        IpAddr::V4(ip) => ip.to_string(),
+        IpAddr::V6(ip) => ip
+            .octets()
+            .iter()
+            .flat_map(|b| [b >> 4, b & 0xf])
+            .map(|n| format!("{:x}", n))
+            .collect::<Vec<_>>()
+            .join("."),
+    }
+}
+
+fn url_escape(value: &str) -> String {
+    let mut out = String::new();
+    for b in value.bytes() {
+        if b.is_ascii_alphanumeric() || b"-._~".contains(&b) {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+    out
+}
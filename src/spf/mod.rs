@@ -0,0 +1,432 @@
+pub mod macros;
+pub mod record;
+
+#[cfg(test)]
+mod tests;
+
+use std::{
+    fmt,
+    future::Future,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    pin::Pin,
+    sync::Arc,
+    time::Duration,
+};
+
+use crate::{
+    dns::{Lookup, Resolver, dns_error::DnsError},
+    spf::{
+        macros::MacroContext,
+        record::{Mechanism, Record},
+    },
+};
+
+// Limites de RFC 7208 §4.6.4
+const MAX_LOOKUPS: u32 = 10;
+const MAX_VOID_LOOKUPS: u32 = 2;
+const MAX_NAMES: usize = 10;
+// Tempo máximo de uma avaliação completa (RFC 7208 §4.6.4 sugere 20s)
+const TIMEOUT: Duration = Duration::from_secs(20);
+// Tamanho máximo de um nome de domínio
+const MAX_DOMAIN_LEN: usize = 253;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpfResult {
+    None,
+    Neutral,
+    Pass,
+    Fail,
+    SoftFail,
+    TempError,
+    PermError,
+}
+
+impl fmt::Display for SpfResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpfResult::None => write!(f, "none"),
+            SpfResult::Neutral => write!(f, "neutral"),
+            SpfResult::Pass => write!(f, "pass"),
+            SpfResult::Fail => write!(f, "fail"),
+            SpfResult::SoftFail => write!(f, "softfail"),
+            SpfResult::TempError => write!(f, "temperror"),
+            SpfResult::PermError => write!(f, "permerror"),
+        }
+    }
+}
+
+pub struct SpfOutcome {
+    pub result: SpfResult,
+    // Motivo de temperror e permerror
+    pub reason: Option<String>,
+    // Texto de exp= do domínio, quando o resultado é fail
+    pub explanation: Option<String>,
+}
+
+// Resultado do MAIL FROM guardado para o DMARC. Com o remetente vazio, o
+// domínio é o do HELO.
+pub struct SpfCheck {
+    pub domain: String,
+    // "mfrom" ou "helo", como nos relatórios DMARC
+    pub scope: &'static str,
+    pub result: SpfResult,
+}
+
+pub struct SpfEvaluator {
+    resolver: Arc<dyn Resolver>,
+    // Nosso hostname, para %{r} nas explicações
+    receiver: String,
+}
+
+impl SpfEvaluator {
+    pub fn new(resolver: Arc<dyn Resolver>, receiver: &str) -> Self {
+        Self {
+            resolver,
+            receiver: receiver.to_string(),
+        }
+    }
+
+    // check_host() (RFC 7208 §4). `sender` é o MAIL FROM ou, para a
+    // identidade do HELO e remetentes vazios, postmaster@helo.
+    pub async fn check_host(
+        &self,
+        ip: IpAddr,
+        domain: &str,
+        sender: &str,
+        helo: &str,
+    ) -> SpfOutcome {
+        // Clientes IPv4 em sockets IPv6 aparecem como ::ffff:a.b.c.d
+        let ip = match ip {
+            IpAddr::V6(v6) => v6
+                .to_ipv4_mapped()
+                .map(IpAddr::V4)
+                .unwrap_or(IpAddr::V6(v6)),
+            v4 => v4,
+        };
+
+        let mut evaluation = Evaluation {
+            resolver: self.resolver.as_ref(),
+            ip,
+            sender,
+            helo,
+            receiver: &self.receiver,
+            lookups: 0,
+            void_lookups: 0,
+        };
+
+        let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+        match tokio::time::timeout(TIMEOUT, evaluation.check_host(domain)).await {
+            Ok(Ok((result, explanation))) => SpfOutcome {
+                result,
+                reason: None,
+                explanation,
+            },
+            Ok(Err(Abort(result, reason))) => SpfOutcome {
+                result,
+                reason: Some(reason),
+                explanation: None,
+            },
+            Err(_) => SpfOutcome {
+                result: SpfResult::TempError,
+                reason: Some("DNS timeout".to_string()),
+                explanation: None,
+            },
+        }
+    }
+}
+
+// Interrompe a avaliação com temperror ou permerror
+struct Abort(SpfResult, String);
+
+impl Abort {
+    fn temp(reason: impl Into<String>) -> Self {
+        Abort(SpfResult::TempError, reason.into())
+    }
+
+    fn perm(reason: impl Into<String>) -> Self {
+        Abort(SpfResult::PermError, reason.into())
+    }
+}
+
+type Step<T> = Result<T, Abort>;
+type HostResult = (SpfResult, Option<String>);
+
+// Estado de uma avaliação, compartilhado pelos include e redirect
+struct Evaluation<'a> {
+    resolver: &'a dyn Resolver,
+    ip: IpAddr,
+    sender: &'a str,
+    helo: &'a str,
+    receiver: &'a str,
+    lookups: u32,
+    void_lookups: u32,
+}
+
+impl<'a> Evaluation<'a> {
+    fn check_host(
+        &mut self,
+        domain: String,
+    ) -> Pin<Box<dyn Future<Output = Step<HostResult>> + Send + '_>> {
+        Box::pin(async move {
+            if !valid_domain(&domain) {
+                return Ok((SpfResult::None, None));
+            }
+
+            let records = match self.resolver.txt(&domain).await {
+                Ok(records) => records,
+                Err(DnsError::NotFoundError(_)) => return Ok((SpfResult::None, None)),
+                Err(e) => return Err(Abort::temp(e.to_string())),
+            };
+            let spf: Vec<&String> = records.iter().filter(|r| record::is_spf(r)).collect();
+            let text = match spf.as_slice() {
+                [] => return Ok((SpfResult::None, None)),
+                [text] => text,
+                _ => return Err(Abort::perm(format!("multiple SPF records for {}", domain))),
+            };
+            let record = record::parse(text).map_err(Abort::perm)?;
+
+            self.evaluate(&record, &domain).await
+        })
+    }
+
+    async fn evaluate(&mut self, record: &Record, domain: &str) -> Step<HostResult> {
+        for directive in &record.mechanisms {
+            if directive.mechanism.needs_lookup() {
+                self.count_lookup()?;
+            }
+            if self.matches(&directive.mechanism, domain).await? {
+                let explanation = match (directive.qualifier, &record.exp) {
+                    (SpfResult::Fail, Some(exp)) => self.explain(exp, domain).await,
+                    _ => None,
+                };
+                return Ok((directive.qualifier, explanation));
+            }
+        }
+
+        if let Some(redirect) = &record.redirect {
+            self.count_lookup()?;
+            let target = self.expand_domain(redirect, domain).await?;
+            return match self.check_host(target).await? {
+                (SpfResult::None, _) => Err(Abort::perm("redirect to a domain without SPF")),
+                outcome => Ok(outcome),
+            };
+        }
+
+        Ok((SpfResult::Neutral, None))
+    }
+
+    async fn matches(&mut self, mechanism: &Mechanism, domain: &str) -> Step<bool> {
+        match mechanism {
+            Mechanism::All => Ok(true),
+            Mechanism::Include(spec) => {
+                let target = self.expand_domain(spec, domain).await?;
+                // Só pass no domínio incluído conta como correspondência
+                match self.check_host(target).await? {
+                    (SpfResult::Pass, _) => Ok(true),
+                    (SpfResult::None, _) => Err(Abort::perm("include of a domain without SPF")),
+                    _ => Ok(false),
+                }
+            }
+            Mechanism::A(spec, v4, v6) => {
+                let target = self.target(spec.as_deref(), domain).await?;
+                self.host_matches(&target, *v4, *v6, true).await
+            }
+            Mechanism::Mx(spec, v4, v6) => {
+                let target = self.target(spec.as_deref(), domain).await?;
+                let resolver = self.resolver;
+                let hosts = self.query(resolver.mx(&target), true).await?;
+                if hosts.len() > MAX_NAMES {
+                    return Err(Abort::perm(format!("too many MX records for {}", target)));
+                }
+                for host in hosts {
+                    if self.host_matches(&host, *v4, *v6, false).await? {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+            Mechanism::Ptr(spec) => {
+                let target = self.target(spec.as_deref(), domain).await?;
+                Ok(self
+                    .validated_names()
+                    .await
+                    .iter()
+                    .any(|name| within_domain(name, &target)))
+            }
+            Mechanism::Ip4(network, len) => Ok(match self.ip {
+                IpAddr::V4(ip) => in_network_v4(ip, *network, *len),
+                IpAddr::V6(_) => false,
+            }),
+            Mechanism::Ip6(network, len) => Ok(match self.ip {
+                IpAddr::V6(ip) => in_network_v6(ip, *network, *len),
+                IpAddr::V4(_) => false,
+            }),
+            Mechanism::Exists(spec) => {
+                let target = self.expand_domain(spec, domain).await?;
+                // Sempre A, mesmo para clientes IPv6 (RFC 7208 §5.7)
+                let resolver = self.resolver;
+                Ok(!self.query(resolver.a(&target), true).await?.is_empty())
+            }
+        }
+    }
+
+    // A ou AAAA de `host`, conforme a família do IP do cliente
+    async fn host_matches(&mut self, host: &str, v4: u8, v6: u8, count_void: bool) -> Step<bool> {
+        let resolver = self.resolver;
+        match self.ip {
+            IpAddr::V4(ip) => Ok(self
+                .query(resolver.a(host), count_void)
+                .await?
+                .into_iter()
+                .any(|addr| in_network_v4(ip, addr, v4))),
+            IpAddr::V6(ip) => Ok(self
+                .query(resolver.aaaa(host), count_void)
+                .await?
+                .into_iter()
+                .any(|addr| in_network_v6(ip, addr, v6))),
+        }
+    }
+
+    // Nomes do PTR do cliente que resolvem de volta para o mesmo IP (RFC 7208 §5.5).
+    // Falhas de DNS aqui não são erro: o nome só não é validado.
+    async fn validated_names(&mut self) -> Vec<String> {
+        let Ok(names) = self.resolver.ptr(self.ip).await else {
+            return Vec::new();
+        };
+
+        let mut validated = Vec::new();
+        for name in names.into_iter().take(MAX_NAMES) {
+            let found = match self.ip {
+                IpAddr::V4(ip) => self
+                    .resolver
+                    .a(&name)
+                    .await
+                    .is_ok_and(|addrs| addrs.contains(&ip)),
+                IpAddr::V6(ip) => self
+                    .resolver
+                    .aaaa(&name)
+                    .await
+                    .is_ok_and(|addrs| addrs.contains(&ip)),
+            };
+            if found {
+                validated.push(name);
+            }
+        }
+        validated
+    }
+
+    // Consulta de um mecanismo. NXDOMAIN e respostas vazias contam como
+    // "void lookups"; outras falhas são temperror.
+    async fn query<T>(&mut self, lookup: Lookup<'_, Vec<T>>, count_void: bool) -> Step<Vec<T>> {
+        let records = match lookup.await {
+            Ok(records) => records,
+            Err(DnsError::NotFoundError(_)) => Vec::new(),
+            Err(e) => return Err(Abort::temp(e.to_string())),
+        };
+        if records.is_empty() && count_void {
+            self.void_lookups += 1;
+            if self.void_lookups > MAX_VOID_LOOKUPS {
+                return Err(Abort::perm("too many void DNS lookups"));
+            }
+        }
+        Ok(records)
+    }
+
+    fn count_lookup(&mut self) -> Step<()> {
+        self.lookups += 1;
+        if self.lookups > MAX_LOOKUPS {
+            return Err(Abort::perm("too many DNS lookups"));
+        }
+        Ok(())
+    }
+
+    async fn target(&mut self, spec: Option<&str>, domain: &str) -> Step<String> {
+        match spec {
+            Some(spec) => self.expand_domain(spec, domain).await,
+            None => Ok(domain.to_string()),
+        }
+    }
+
+    async fn expand_domain(&mut self, spec: &str, domain: &str) -> Step<String> {
+        let expanded = self
+            .expand(spec, domain, false)
+            .await
+            .map_err(Abort::perm)?;
+        let mut expanded = expanded.trim_end_matches('.').to_ascii_lowercase();
+
+        // Nomes longos demais perdem os rótulos da esquerda (RFC 7208 §7.3)
+        while expanded.len() > MAX_DOMAIN_LEN {
+            match expanded.split_once('.') {
+                Some((_, rest)) => expanded = rest.to_string(),
+                None => break,
+            }
+        }
+        Ok(expanded)
+    }
+
+    // Texto de exp= (RFC 7208 §6.2). Qualquer problema só descarta a explicação.
+    async fn explain(&mut self, exp: &str, domain: &str) -> Option<String> {
+        let target = self.expand_domain(exp, domain).await.ok()?;
+        let records = self.resolver.txt(&target).await.ok()?;
+        let [text] = records.as_slice() else {
+            return None;
+        };
+        let explanation = self.expand(text, domain, true).await.ok()?;
+        // Vai para a resposta SMTP: só ASCII imprimível, sem CR/LF nem controles
+        let explanation: String = explanation
+            .chars()
+            .filter(|c| matches!(c, ' '..='~'))
+            .collect();
+        (!explanation.trim().is_empty()).then_some(explanation)
+    }
+
+    async fn expand(
+        &mut self,
+        spec: &str,
+        domain: &str,
+        explanation: bool,
+    ) -> Result<String, String> {
+        let validated = if macros::uses_validated_name(spec) {
+            let names = self.validated_names().await;
+            names
+                .iter()
+                .find(|name| within_domain(name, domain))
+                .or(names.first())
+                .cloned()
+        } else {
+            None
+        };
+
+        let ctx = MacroContext {
+            sender: self.sender,
+            domain,
+            ip: self.ip,
+            helo: self.helo,
+            validated: validated.as_deref(),
+            receiver: self.receiver,
+        };
+        macros::expand(spec, &ctx, explanation)
+    }
+}
+
+// Domínios malformados ou de um só rótulo resultam em none (RFC 7208 §4.3)
+fn valid_domain(domain: &str) -> bool {
+    let labels: Vec<&str> = domain.split('.').collect();
+    domain.len() <= MAX_DOMAIN_LEN
+        && labels.len() > 1
+        && labels.iter().all(|l| !l.is_empty() && l.len() <= 63)
+}
+
+fn within_domain(name: &str, domain: &str) -> bool {
+    name == domain || name.ends_with(&format!(".{}", domain))
+}
+
+fn in_network_v4(ip: Ipv4Addr, network: Ipv4Addr, len: u8) -> bool {
+    let mask = u32::MAX.checked_shl(32 - len as u32).unwrap_or(0);
+    u32::from(ip) & mask == u32::from(network) & mask
+}
+
+fn in_network_v6(ip: Ipv6Addr, network: Ipv6Addr, len: u8) -> bool {
+    let mask = u128::MAX.checked_shl(128 - len as u32).unwrap_or(0);
+    u128::from(ip) & mask == u128::from(network) & mask
+}
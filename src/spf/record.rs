@@ -0,0 +1,182 @@
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use crate::spf::SpfResult;
+
+// Um registro SPF já interpretado (RFC 7208 §4.6.1)
+pub struct Record {
+    pub mechanisms: Vec<Directive>,
+    pub redirect: Option<String>,
+    pub exp: Option<String>,
+}
+
+pub struct Directive {
+    pub qualifier: SpfResult,
+    pub mechanism: Mechanism,
+}
+
+// Os domain-spec ficam sem expandir: as macros dependem da consulta
+pub enum Mechanism {
+    All,
+    Include(String),
+    A(Option<String>, u8, u8),
+    Mx(Option<String>, u8, u8),
+    Ptr(Option<String>),
+    Ip4(Ipv4Addr, u8),
+    Ip6(Ipv6Addr, u8),
+    Exists(String),
+}
+
+impl Mechanism {
+    // Mecanismos que consultam o DNS e contam no limite de 10 (RFC 7208 §4.6.4)
+    pub fn needs_lookup(&self) -> bool {
+        !matches!(
+            self,
+            Mechanism::All | Mechanism::Ip4(..) | Mechanism::Ip6(..)
+        )
+    }
+}
+
+// Registros começam com "v=spf1" seguido de espaço ou do fim do texto
+pub fn is_spf(text: &str) -> bool {
+    let prefix = "v=spf1";
+    text.get(..prefix.len())
+        .is_some_and(|p| p.eq_ignore_ascii_case(prefix))
+        && text[prefix.len()..].chars().next().is_none_or(|c| c == ' ')
+}
+
+// Qualquer erro de sintaxe invalida o registro inteiro (permerror)
+pub fn parse(text: &str) -> Result<Record, String> {
+    let mut record = Record {
+        mechanisms: Vec::new(),
+        redirect: None,
+        exp: None,
+    };
+
+    for term in text.split(' ').skip(1).filter(|t| !t.is_empty()) {
+        // Modificadores: nome=valor, com o nome começando por letra
+        if let Some((name, value)) = term.split_once('=')
+            && name.starts_with(|c: char| c.is_ascii_alphabetic())
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        {
+            let slot = match name.to_ascii_lowercase().as_str() {
+                "redirect" => &mut record.redirect,
+                "exp" => &mut record.exp,
+                // Modificadores desconhecidos são ignorados
+                _ => continue,
+            };
+            if slot.is_some() {
+                return Err(format!("duplicate {} modifier", name));
+            }
+            *slot = Some(domain_spec(value)?);
+            continue;
+        }
+
+        record.mechanisms.push(directive(term)?);
+    }
+
+    Ok(record)
+}
+
+fn directive(term: &str) -> Result<Directive, String> {
+    let (qualifier, rest) = match term.chars().next() {
+        Some('+') => (SpfResult::Pass, &term[1..]),
+        Some('-') => (SpfResult::Fail, &term[1..]),
+        Some('~') => (SpfResult::SoftFail, &term[1..]),
+        Some('?') => (SpfResult::Neutral, &term[1..]),
+        _ => (SpfResult::Pass, term),
+    };
+
+    let split = rest.find([':', '/']).unwrap_or(rest.len());
+    let (name, arg) = rest.split_at(split);
+    let invalid = || format!("invalid mechanism {}", term);
+
+    let mechanism = match name.to_ascii_lowercase().as_str() {
+        "all" if arg.is_empty() => Mechanism::All,
+        "include" => Mechanism::Include(required_spec(arg).ok_or_else(invalid)?),
+        "exists" => Mechanism::Exists(required_spec(arg).ok_or_else(invalid)?),
+        "a" => {
+            let (spec, v4, v6) = spec_with_cidr(arg).ok_or_else(invalid)?;
+            Mechanism::A(spec, v4, v6)
+        }
+        "mx" => {
+            let (spec, v4, v6) = spec_with_cidr(arg).ok_or_else(invalid)?;
+            Mechanism::Mx(spec, v4, v6)
+        }
+        "ptr" => match arg {
+            "" => Mechanism::Ptr(None),
+            _ => Mechanism::Ptr(Some(required_spec(arg).ok_or_else(invalid)?)),
+        },
+        "ip4" => {
+            let value = arg.strip_prefix(':').ok_or_else(invalid)?;
+            let (ip, len) = network(value, 32).ok_or_else(invalid)?;
+            Mechanism::Ip4(ip.parse().map_err(|_| invalid())?, len)
+        }
+        "ip6" => {
+            let value = arg.strip_prefix(':').ok_or_else(invalid)?;
+            let (ip, len) = network(value, 128).ok_or_else(invalid)?;
+            Mechanism::Ip6(ip.parse().map_err(|_| invalid())?, len)
+        }
+        _ => return Err(format!("unknown mechanism {}", term)),
+    };
+
+    Ok(Directive {
+        qualifier,
+        mechanism,
+    })
+}
+
+// ":domain-spec" obrigatório
+fn required_spec(arg: &str) -> Option<String> {
+    domain_spec(arg.strip_prefix(':')?).ok()
+}
+
+// [":domain-spec"] ["/cidr4"] ["//cidr6"] dos mecanismos a e mx
+fn spec_with_cidr(arg: &str) -> Option<(Option<String>, u8, u8)> {
+    let (spec, cidr) = match arg.find('/') {
+        Some(pos) => arg.split_at(pos),
+        None => (arg, ""),
+    };
+
+    let spec = match spec {
+        "" => None,
+        _ => Some(domain_spec(spec.strip_prefix(':')?).ok()?),
+    };
+
+    let (v4, v6) = match cidr.strip_prefix('/') {
+        None => (32, 128),
+        Some(rest) => match rest.split_once("//") {
+            Some(("", v6)) => (32, prefix_len(v6, 128)?),
+            Some((v4, v6)) => (prefix_len(v4, 32)?, prefix_len(v6, 128)?),
+            None => match rest.strip_prefix('/') {
+                Some(v6) => (32, prefix_len(v6, 128)?),
+                None => (prefix_len(rest, 32)?, 128),
+            },
+        },
+    };
+
+    Some((spec, v4, v6))
+}
+
+fn network(value: &str, max: u8) -> Option<(&str, u8)> {
+    match value.split_once('/') {
+        Some((ip, len)) => Some((ip, prefix_len(len, max)?)),
+        None => Some((value, max)),
+    }
+}
+
+// Sem zeros à esquerda (RFC 7208 §5.6)
+fn prefix_len(text: &str, max: u8) -> Option<u8> {
+    if text.is_empty() || (text.len() > 1 && text.starts_with('0')) {
+        return None;
+    }
+    text.parse().ok().filter(|len| *len <= max)
+}
+
+fn domain_spec(spec: &str) -> Result<String, String> {
+    if spec.is_empty() || spec.contains(char::is_whitespace) {
+        return Err(format!("invalid domain-spec {}", spec));
+    }
+    Ok(spec.to_string())
+}
@@ -0,0 +1,366 @@
+use std::{
+    collections::BTreeMap,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    sync::Arc,
+};
+
+use crate::{
+    dns::{Lookup, Resolver, dns_error::DnsError, static_resolver::StaticResolver},
+    spf::{
+        SpfEvaluator, SpfOutcome, SpfResult,
+        macros::{self, MacroContext},
+        record,
+    },
+};
+
+// A, AAAA, MX e PTR fixos, usados como apoio do StaticResolver (que só responde TXT)
+struct Hosts;
+
+impl Resolver for Hosts {
+    fn txt<'a>(&'a self, name: &'a str) -> Lookup<'a, Vec<String>> {
+        answer(name, Vec::new())
+    }
+
+    fn a<'a>(&'a self, name: &'a str) -> Lookup<'a, Vec<Ipv4Addr>> {
+        let addrs = match name {
+            "example.com" => vec!["192.0.2.1"],
+            "mail.example.com" => vec!["192.0.2.10"],
+            "mx1.example.com" => vec!["192.0.2.20"],
+            "host.example.org" => vec!["192.0.2.40"],
+            "30.2.0.192.in-addr._spf.example.com" => vec!["127.0.0.2"],
+            _ => Vec::new(),
+        };
+        answer(name, addrs.iter().map(|a| a.parse().unwrap()).collect())
+    }
+
+    fn aaaa<'a>(&'a self, name: &'a str) -> Lookup<'a, Vec<Ipv6Addr>> {
+        let addrs = match name {
+            "example.com" => vec!["2001:db8::1".parse().unwrap()],
+            _ => Vec::new(),
+        };
+        answer(name, addrs)
+    }
+
+    fn mx<'a>(&'a self, name: &'a str) -> Lookup<'a, Vec<String>> {
+        let hosts = match name {
+            "example.com" => vec!["mx1.example.com".to_string()],
+            _ => Vec::new(),
+        };
+        answer(name, hosts)
+    }
+
+    fn ptr<'a>(&'a self, ip: IpAddr) -> Lookup<'a, Vec<String>> {
+        let names = match ip.to_string().as_str() {
+            "192.0.2.40" => vec!["host.example.org".to_string()],
+            _ => Vec::new(),
+        };
+        answer(&ip.to_string(), names)
+    }
+}
+
+fn answer<'a, T: Send + 'a>(name: &str, records: Vec<T>) -> Lookup<'a, Vec<T>> {
+    let name = name.to_string();
+    Box::pin(async move {
+        match records.is_empty() {
+            true => Err(DnsError::NotFoundError(name)),
+            false => Ok(records),
+        }
+    })
+}
+
+fn evaluator(records: &[(&str, &str)]) -> SpfEvaluator {
+    let mut txt: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (name, record) in records {
+        txt.entry(name.to_string())
+            .or_default()
+            .push(record.to_string());
+    }
+    let resolver = StaticResolver::new(&txt).with_fallback(Arc::new(Hosts));
+    SpfEvaluator::new(Arc::new(resolver), "mx.example.net")
+}
+
+async fn check(records: &[(&str, &str)], ip: &str) -> SpfOutcome {
+    evaluator(records)
+        .check_host(
+            ip.parse().unwrap(),
+            "example.com",
+            "ana@example.com",
+            "mail.example.com",
+        )
+        .await
+}
+
+async fn result(record: &str, ip: &str) -> SpfResult {
+    check(&[("example.com", record)], ip).await.result
+}
+
+#[test]
+fn is_spf_prefix() {
+    assert!(record::is_spf("v=spf1"));
+    assert!(record::is_spf("V=SPF1 -all"));
+    assert!(!record::is_spf("v=spf10 -all"));
+    assert!(!record::is_spf("v=spf"));
+    // O sexto byte cai no meio de um caractere
+    assert!(!record::is_spf("v=sp€ -all"));
+}
+
+#[tokio::test]
+async fn mechanisms() {
+    let cases = [
+        ("v=spf1 -all", "192.0.2.99", SpfResult::Fail),
+        ("v=spf1 ~all", "192.0.2.99", SpfResult::SoftFail),
+        ("v=spf1 ?all", "192.0.2.99", SpfResult::Neutral),
+        ("v=spf1", "192.0.2.99", SpfResult::Neutral),
+        (
+            "v=spf1 ip4:192.0.2.0/24 -all",
+            "192.0.2.99",
+            SpfResult::Pass,
+        ),
+        (
+            "v=spf1 ip4:192.0.2.0/24 -all",
+            "198.51.100.1",
+            SpfResult::Fail,
+        ),
+        (
+            "v=spf1 ip6:2001:db8::/32 -all",
+            "2001:db8::5",
+            SpfResult::Pass,
+        ),
+        (
+            "v=spf1 ip6:2001:db8::/32 -all",
+            "192.0.2.99",
+            SpfResult::Fail,
+        ),
+        ("v=spf1 a -all", "192.0.2.1", SpfResult::Pass),
+        ("v=spf1 a -all", "2001:db8::1", SpfResult::Pass),
+        ("v=spf1 a -all", "::ffff:192.0.2.1", SpfResult::Pass),
+        ("v=spf1 a -all", "192.0.2.2", SpfResult::Fail),
+        (
+            "v=spf1 a:mail.example.com/24 -all",
+            "192.0.2.77",
+            SpfResult::Pass,
+        ),
+        ("v=spf1 mx -all", "192.0.2.20", SpfResult::Pass),
+        ("v=spf1 mx -all", "192.0.2.10", SpfResult::Fail),
+        ("v=spf1 ptr:example.org -all", "192.0.2.40", SpfResult::Pass),
+        ("v=spf1 ptr -all", "192.0.2.40", SpfResult::Fail),
+        (
+            "v=spf1 exists:%{ir}.%{v}._spf.%{d} -all",
+            "192.0.2.30",
+            SpfResult::Pass,
+        ),
+        (
+            "v=spf1 exists:%{ir}.%{v}._spf.%{d} -all",
+            "192.0.2.31",
+            SpfResult::Fail,
+        ),
+        ("v=spf1 foo -all", "192.0.2.99", SpfResult::PermError),
+        (
+            "v=spf1 ip4:192.0.2.0/033 -all",
+            "192.0.2.99",
+            SpfResult::PermError,
+        ),
+        ("spf2.0/pra -all", "192.0.2.99", SpfResult::None),
+    ];
+
+    for (record, ip, expected) in cases {
+        assert_eq!(result(record, ip).await, expected, "{} de {}", record, ip);
+    }
+}
+
+#[tokio::test]
+async fn missing_and_duplicate_records() {
+    assert_eq!(check(&[], "192.0.2.99").await.result, SpfResult::None);
+
+    let outcome = check(
+        &[
+            ("example.com", "v=spf1 -all"),
+            ("example.com", "v=spf1 +all"),
+        ],
+        "192.0.2.99",
+    )
+    .await;
+    assert_eq!(outcome.result, SpfResult::PermError);
+}
+
+// Registro com `count` includes, cada um para um domínio que termina em -all
+fn includes(count: usize) -> Vec<(String, String)> {
+    let mut records: Vec<(String, String)> = (1..=count)
+        .map(|n| (format!("i{}.example.com", n), "v=spf1 -all".to_string()))
+        .collect();
+    let terms: Vec<String> = (1..=count)
+        .map(|n| format!("include:i{}.example.com", n))
+        .collect();
+    records.push((
+        "example.com".to_string(),
+        format!("v=spf1 {} ?all", terms.join(" ")),
+    ));
+    records
+}
+
+#[tokio::test]
+async fn lookup_limit() {
+    for (count, expected) in [(10, SpfResult::Neutral), (11, SpfResult::PermError)] {
+        let records = includes(count);
+        let records: Vec<(&str, &str)> = records
+            .iter()
+            .map(|(name, record)| (name.as_str(), record.as_str()))
+            .collect();
+        let outcome = check(&records, "192.0.2.99").await;
+        assert_eq!(outcome.result, expected, "{} includes", count);
+    }
+}
+
+#[tokio::test]
+async fn void_lookup_limit() {
+    assert_eq!(
+        result(
+            "v=spf1 a:v1.example.com a:v2.example.com -all",
+            "192.0.2.99"
+        )
+        .await,
+        SpfResult::Fail
+    );
+
+    let outcome = check(
+        &[(
+            "example.com",
+            "v=spf1 a:v1.example.com a:v2.example.com a:v3.example.com -all",
+        )],
+        "192.0.2.99",
+    )
+    .await;
+    assert_eq!(outcome.result, SpfResult::PermError);
+    assert_eq!(outcome.reason.as_deref(), Some("too many void DNS lookups"));
+}
+
+#[tokio::test]
+async fn redirect() {
+    let records = [
+        ("example.com", "v=spf1 redirect=_spf.%{d}"),
+        ("_spf.example.com", "v=spf1 ip4:192.0.2.0/24 -all"),
+    ];
+    assert_eq!(check(&records, "192.0.2.5").await.result, SpfResult::Pass);
+    assert_eq!(
+        check(&records, "198.51.100.1").await.result,
+        SpfResult::Fail
+    );
+
+    // Os mecanismos têm prioridade sobre o redirect
+    let records = [
+        ("example.com", "v=spf1 ?all redirect=_spf.example.com"),
+        ("_spf.example.com", "v=spf1 -all"),
+    ];
+    assert_eq!(
+        check(&records, "192.0.2.5").await.result,
+        SpfResult::Neutral
+    );
+
+    assert_eq!(
+        result("v=spf1 redirect=_spf.example.com", "192.0.2.5").await,
+        SpfResult::PermError
+    );
+}
+
+#[tokio::test]
+async fn include() {
+    let records = [
+        ("example.com", "v=spf1 include:_spf.example.net -all"),
+        ("_spf.example.net", "v=spf1 ip4:198.51.100.0/24 ~all"),
+    ];
+    assert_eq!(
+        check(&records, "198.51.100.7").await.result,
+        SpfResult::Pass
+    );
+    // O softfail do domínio incluído não é correspondência
+    assert_eq!(check(&records, "203.0.113.1").await.result, SpfResult::Fail);
+
+    assert_eq!(
+        result("v=spf1 include:_spf.example.net -all", "192.0.2.5").await,
+        SpfResult::PermError
+    );
+}
+
+#[tokio::test]
+async fn explanation_keeps_printable_ascii() {
+    let records = [
+        ("example.com", "v=spf1 -all exp=explain.%{d}"),
+        (
+            "explain.example.com",
+            "%{i} is not one of %{d}'s servers\r\n250 ok\u{7}é",
+        ),
+    ];
+    let outcome = check(&records, "192.0.2.99").await;
+    assert_eq!(outcome.result, SpfResult::Fail);
+    assert_eq!(
+        outcome.explanation.as_deref(),
+        Some("192.0.2.99 is not one of example.com's servers250 ok")
+    );
+}
+
+fn expand(spec: &str, ip: &str) -> Result<String, String> {
+    let ctx = MacroContext {
+        sender: "strong-bad@email.example.com",
+        domain: "email.example.com",
+        ip: ip.parse().unwrap(),
+        helo: "mx.example.org",
+        validated: None,
+        receiver: "mx.example.net",
+    };
+    macros::expand(spec, &ctx, false)
+}
+
+// Exemplos de RFC 7208 §7.4
+#[test]
+fn macro_expansion() {
+    let cases = [
+        ("%{s}", "strong-bad@email.example.com"),
+        ("%{o}", "email.example.com"),
+        ("%{d}", "email.example.com"),
+        ("%{d4}", "email.example.com"),
+        ("%{d3}", "email.example.com"),
+        ("%{d2}", "example.com"),
+        ("%{d1}", "com"),
+        ("%{dr}", "com.example.email"),
+        ("%{d2r}", "example.email"),
+        ("%{l}", "strong-bad"),
+        ("%{l-}", "strong.bad"),
+        ("%{lr}", "strong-bad"),
+        ("%{lr-}", "bad.strong"),
+        ("%{l1r-}", "strong"),
+        (
+            "%{ir}.%{v}._spf.%{d2}",
+            "3.2.0.192.in-addr._spf.example.com",
+        ),
+        ("%{lr-}.lp._spf.%{d2}", "bad.strong.lp._spf.example.com"),
+        (
+            "%{lr-}.lp.%{ir}.%{v}._spf.%{d2}",
+            "bad.strong.lp.3.2.0.192.in-addr._spf.example.com",
+        ),
+        (
+            "%{ir}.%{v}.%{l1r-}.lp._spf.%{d2}",
+            "3.2.0.192.in-addr.strong.lp._spf.example.com",
+        ),
+        (
+            "%{d2}.trusted-domains.example.net",
+            "example.com.trusted-domains.example.net",
+        ),
+        ("%%%_%-", "% %20"),
+        ("%{S}", "strong-bad%40email.example.com"),
+    ];
+    for (spec, expected) in cases {
+        assert_eq!(expand(spec, "192.0.2.3").unwrap(), expected, "{}", spec);
+    }
+
+    assert_eq!(
+        expand("%{ir}.%{v}._spf.%{d2}", "2001:db8::cb01").unwrap(),
+        concat!(
+            "1.0.b.c.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.8.b.d.0.1.0.0.2",
+            ".ip6._spf.example.com"
+        )
+    );
+
+    for invalid in ["%{x}", "%{d0}", "%{d", "%a", "%{c}", "%{dr!}"] {
+        assert!(expand(invalid, "192.0.2.3").is_err(), "{}", invalid);
+    }
+}
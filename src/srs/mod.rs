@@ -0,0 +1,195 @@
+pub mod srs_error;
+
+#[cfg(test)]
+mod tests;
+
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::{config::Config, srs::srs_error::SrsError};
+
+// Sender Rewriting Scheme, no formato do libsrs2:
+//   SRS0=HHHH=TT=domínio=usuário@nosso.domínio
+//   SRS1=HHHH=domínio-srs0==HHHH=TT=domínio=usuário@nosso.domínio
+// O hash é um HMAC-SHA256 truncado e o timestamp conta dias, em base32.
+
+const HASH_LENGTH: usize = 4;
+const TIMESTAMP_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+// Dois caracteres de base32: o timestamp dá a volta a cada 1024 dias
+const TIMESTAMP_SLOTS: i64 = 1024;
+
+// Remetente reescrito para um encaminhamento. O remetente vazio e os
+// endereços que já são do nosso domínio SRS ficam como estão.
+pub fn forward(config: &Config, sender: &str) -> String {
+    let srs = &config.srs;
+    if !srs.enabled || srs.secret.is_empty() {
+        return sender.to_string();
+    }
+    let Some((local_part, domain)) = sender.rsplit_once('@') else {
+        return sender.to_string();
+    };
+    let srs_domain = srs.domain(&config.server.hostname);
+    if domain.eq_ignore_ascii_case(srs_domain) {
+        return sender.to_string();
+    }
+
+    // Um SRS0 de outro encaminhador vira SRS1, apontando para quem o criou
+    if let Some(rest) = strip_tag(local_part, "SRS0") {
+        let hash = hash(&srs.secret, &[domain, rest]);
+        return format!("SRS1={}={}=={}@{}", hash, domain, rest, srs_domain);
+    }
+    // Um SRS1 só troca o hash; o destino do bounce continua o mesmo
+    if let Some((host, srs0)) = strip_tag(local_part, "SRS1").and_then(parse_srs1) {
+        let hash = hash(&srs.secret, &[host, srs0]);
+        return format!("SRS1={}={}=={}@{}", hash, host, srs0, srs_domain);
+    }
+
+    let timestamp = timestamp(today());
+    let hash = hash(&srs.secret, &[&timestamp, domain, local_part]);
+    format!(
+        "SRS0={}={}={}={}@{}",
+        hash, timestamp, domain, local_part, srs_domain
+    )
+}
+
+// Endereço original de um bounce para um endereço SRS nosso; None quando o
+// endereço não é SRS
+pub fn reverse(config: &Config, address: &str) -> Result<Option<String>, SrsError> {
+    let srs = &config.srs;
+    if !srs.enabled {
+        return Ok(None);
+    }
+    let Some((local_part, domain)) = address.rsplit_once('@') else {
+        return Ok(None);
+    };
+    if !domain.eq_ignore_ascii_case(srs.domain(&config.server.hostname)) {
+        return Ok(None);
+    }
+
+    if let Some(rest) = strip_tag(local_part, "SRS0") {
+        let mut fields = rest.splitn(4, '=');
+        let (Some(hash), Some(timestamp), Some(domain), Some(user)) =
+            (fields.next(), fields.next(), fields.next(), fields.next())
+        else {
+            return Err(SrsError::FormatError(address.to_string()));
+        };
+        if domain.is_empty() || user.is_empty() {
+            return Err(SrsError::FormatError(address.to_string()));
+        }
+        if !verify(config, hash, &[timestamp, domain, user]) {
+            return Err(SrsError::HashError(address.to_string()));
+        }
+        let age = decode_timestamp(timestamp)
+            .map(|stamp| (today() - stamp).rem_euclid(TIMESTAMP_SLOTS))
+            .ok_or_else(|| SrsError::FormatError(address.to_string()))?;
+        if age > srs.max_age_days as i64 {
+            return Err(SrsError::ExpiredError(address.to_string()));
+        }
+        return Ok(Some(format!("{}@{}", user, domain)));
+    }
+
+    if let Some(rest) = strip_tag(local_part, "SRS1") {
+        let (hash, tail) = rest
+            .split_once('=')
+            .ok_or_else(|| SrsError::FormatError(address.to_string()))?;
+        let (host, srs0) = tail
+            .split_once("==")
+            .filter(|(host, srs0)| !host.is_empty() && !srs0.is_empty())
+            .ok_or_else(|| SrsError::FormatError(address.to_string()))?;
+        if !verify(config, hash, &[host, srs0]) {
+            return Err(SrsError::HashError(address.to_string()));
+        }
+        // Volta para o encaminhador que criou o SRS0, que o valida
+        return Ok(Some(format!("SRS0={}@{}", srs0, host)));
+    }
+
+    Ok(None)
+}
+
+// Conteúdo depois de SRS0= ou SRS1= (também com + ou - como separador)
+fn strip_tag<'a>(local_part: &'a str, tag: &str) -> Option<&'a str> {
+    let prefix = local_part.get(..tag.len())?;
+    if !prefix.eq_ignore_ascii_case(tag) {
+        return None;
+    }
+    local_part[tag.len()..].strip_prefix(['=', '+', '-'])
+}
+
+// HHHH=domínio==resto de um SRS1, sem o hash
+fn parse_srs1(rest: &str) -> Option<(&str, &str)> {
+    let (_, tail) = rest.split_once('=')?;
+    tail.split_once("==")
+        .filter(|(host, srs0)| !host.is_empty() && !srs0.is_empty())
+}
+
+// O segredo atual e os anteriores, para que a rotação não invalide os
+// bounces de mensagens já encaminhadas. Os hashes antigos, sem separador
+// entre as partes, só valem com os segredos anteriores.
+fn verify(config: &Config, expected: &str, parts: &[&str]) -> bool {
+    let srs = &config.srs;
+    // Alguns servidores mudam a caixa da parte local
+    let matches = |hash: String| hash.eq_ignore_ascii_case(expected);
+
+    if !srs.secret.is_empty() && matches(hash(&srs.secret, parts)) {
+        return true;
+    }
+    srs.previous_secrets
+        .iter()
+        .filter(|secret| !secret.is_empty())
+        .any(|secret| matches(hash(secret, parts)) || matches(legacy_hash(secret, parts)))
+}
+
+// As partes são separadas por um byte nulo, que não aparece em endereços:
+// sem ele, "a" + "bc" e "ab" + "c" teriam o mesmo hash
+fn hash(secret: &str, parts: &[&str]) -> String {
+    mac(secret, parts, Some(b"\0"))
+}
+
+// Esquema anterior, com as partes concatenadas
+fn legacy_hash(secret: &str, parts: &[&str]) -> String {
+    mac(secret, parts, None)
+}
+
+fn mac(secret: &str, parts: &[&str], separator: Option<&[u8]>) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC aceita qualquer chave");
+    for (i, part) in parts.iter().enumerate() {
+        if let Some(separator) = separator
+            && i > 0
+        {
+            mac.update(separator);
+        }
+        mac.update(part.to_ascii_lowercase().as_bytes());
+    }
+    let mut encoded = URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes());
+    encoded.truncate(HASH_LENGTH);
+    encoded
+}
+
+fn today() -> i64 {
+    Utc::now().timestamp().div_euclid(86400)
+}
+
+fn timestamp(day: i64) -> String {
+    let day = day.rem_euclid(TIMESTAMP_SLOTS) as usize;
+    [TIMESTAMP_ALPHABET[day >> 5], TIMESTAMP_ALPHABET[day & 31]]
+        .iter()
+        .map(|&c| c as char)
+        .collect()
+}
+
+fn decode_timestamp(timestamp: &str) -> Option<i64> {
+    if timestamp.len() != 2 {
+        return None;
+    }
+    let mut day = 0;
+    for c in timestamp.bytes() {
+        let value = TIMESTAMP_ALPHABET
+            .iter()
+            .position(|&a| a == c.to_ascii_uppercase())?;
+        day = (day << 5) | value as i64;
+    }
+    Some(day)
+}
@@ -0,0 +1,23 @@
+use std::{error::Error, fmt};
+
+#[derive(Debug)]
+pub enum SrsError {
+    // Endereço com o prefixo SRS0/SRS1 mas sem todos os campos
+    FormatError(String),
+    // Hash que não confere com nenhum dos segredos
+    HashError(String),
+    // Timestamp mais antigo que srs.max_age_days
+    ExpiredError(String),
+}
+
+impl fmt::Display for SrsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SrsError::FormatError(address) => write!(f, "Endereço SRS malformado: {}", address),
+            SrsError::HashError(address) => write!(f, "Hash SRS inválido em {}", address),
+            SrsError::ExpiredError(address) => write!(f, "Endereço SRS expirado: {}", address),
+        }
+    }
+}
+
+impl Error for SrsError {}
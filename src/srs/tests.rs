@@ -0,0 +1,187 @@
+use crate::{
+    config::{Config, srs_config::SrsConfig},
+    srs::{self, srs_error::SrsError},
+};
+
+fn config(domain: &str, secret: &str, previous_secrets: &[&str]) -> Config {
+    let mut config: Config = toml::from_str("[server]\nhostname = \"mx.example.net\"").unwrap();
+    config.srs = SrsConfig {
+        enabled: true,
+        domain: domain.to_string(),
+        secret: secret.to_string(),
+        previous_secrets: previous_secrets.iter().map(|s| s.to_string()).collect(),
+        max_age_days: 21,
+    };
+    config
+}
+
+// SRS0 com `age` dias, assinado por `hash`
+fn srs0(hash: fn(&str, &[&str]) -> String, secret: &str, age: i64) -> String {
+    let timestamp = srs::timestamp(srs::today() - age);
+    let hash = hash(secret, &[&timestamp, "origem.com", "ana"]);
+    format!("SRS0={}={}=origem.com=ana@srs.example.net", hash, timestamp)
+}
+
+#[test]
+fn forward_reverse_round_trip() {
+    let config = config("srs.example.net", "segredo", &[]);
+
+    let forwarded = srs::forward(&config, "ana@origem.com");
+    assert!(forwarded.starts_with("SRS0="), "{}", forwarded);
+    assert!(forwarded.ends_with("=origem.com=ana@srs.example.net"));
+    assert_eq!(
+        srs::reverse(&config, &forwarded).unwrap().as_deref(),
+        Some("ana@origem.com")
+    );
+    // Alguns servidores mudam a caixa da parte local
+    assert_eq!(
+        srs::reverse(&config, &forwarded.to_uppercase())
+            .unwrap()
+            .map(|a| a.to_lowercase())
+            .as_deref(),
+        Some("ana@origem.com")
+    );
+
+    // Remetente vazio, endereços do próprio domínio SRS e endereços comuns
+    assert_eq!(srs::forward(&config, ""), "");
+    assert_eq!(srs::forward(&config, &forwarded), forwarded);
+    assert!(
+        srs::reverse(&config, "ana@srs.example.net")
+            .unwrap()
+            .is_none()
+    );
+    assert!(
+        srs::reverse(&config, &forwarded.replace("srs.example.net", "outro.net"))
+            .unwrap()
+            .is_none()
+    );
+
+    let mut disabled = config.clone();
+    disabled.srs.enabled = false;
+    assert_eq!(srs::forward(&disabled, "ana@origem.com"), "ana@origem.com");
+}
+
+#[test]
+fn invalid_addresses() {
+    let config = config("srs.example.net", "segredo", &[]);
+    let forwarded = srs::forward(&config, "ana@origem.com");
+
+    let tampered = forwarded.replace("=ana@", "=bia@");
+    assert!(matches!(
+        srs::reverse(&config, &tampered),
+        Err(SrsError::HashError(_))
+    ));
+    assert!(matches!(
+        srs::reverse(&config, "SRS0=abcd=AA=origem.com@srs.example.net"),
+        Err(SrsError::FormatError(_))
+    ));
+    assert!(matches!(
+        srs::reverse(&config, "SRS1=abcd=origem.com@srs.example.net"),
+        Err(SrsError::FormatError(_))
+    ));
+}
+
+// Um SRS0 de outro encaminhador vira SRS1 e o bounce volta por ele
+#[test]
+fn srs0_to_srs1_and_back() {
+    let first = config("srs.first.net", "primeiro", &[]);
+    let second = config("srs.second.net", "segundo", &[]);
+    let third = config("srs.third.net", "terceiro", &[]);
+
+    let srs0 = srs::forward(&first, "ana@origem.com");
+    let srs1 = srs::forward(&second, &srs0);
+    let (_, srs0_tail) = srs0.split_once('=').unwrap();
+    let (srs0_tail, _) = srs0_tail.rsplit_once('@').unwrap();
+    assert!(srs1.starts_with("SRS1="), "{}", srs1);
+    assert!(
+        srs1.ends_with(&format!("=srs.first.net=={}@srs.second.net", srs0_tail)),
+        "{}",
+        srs1
+    );
+
+    // Um SRS1 reencaminhado só troca o hash e o domínio
+    let again = srs::forward(&third, &srs1);
+    assert!(
+        again.ends_with(&format!("=srs.first.net=={}@srs.third.net", srs0_tail)),
+        "{}",
+        again
+    );
+
+    assert_eq!(
+        srs::reverse(&third, &again).unwrap().as_deref(),
+        Some(srs0.as_str())
+    );
+    assert_eq!(
+        srs::reverse(&second, &srs1).unwrap().as_deref(),
+        Some(srs0.as_str())
+    );
+    assert_eq!(
+        srs::reverse(&first, &srs0).unwrap().as_deref(),
+        Some("ana@origem.com")
+    );
+    // O SRS1 de um encaminhador não vale no outro
+    assert!(matches!(
+        srs::reverse(&third, &srs1.replace("srs.second.net", "srs.third.net")),
+        Err(SrsError::HashError(_))
+    ));
+}
+
+#[test]
+fn expiry() {
+    let config = config("srs.example.net", "segredo", &[]);
+
+    assert!(srs::reverse(&config, &srs0(srs::hash, "segredo", 21)).is_ok());
+    assert!(matches!(
+        srs::reverse(&config, &srs0(srs::hash, "segredo", 22)),
+        Err(SrsError::ExpiredError(_))
+    ));
+    // O timestamp dá a volta a cada 1024 dias
+    assert!(matches!(
+        srs::reverse(&config, &srs0(srs::hash, "segredo", 1000)),
+        Err(SrsError::ExpiredError(_))
+    ));
+}
+
+#[test]
+fn secret_rotation() {
+    let old = config("srs.example.net", "antigo", &[]);
+    let rotated = config("srs.example.net", "novo", &["antigo"]);
+    let dropped = config("srs.example.net", "novo", &[]);
+
+    let forwarded = srs::forward(&old, "ana@origem.com");
+    assert_eq!(
+        srs::reverse(&rotated, &forwarded).unwrap().as_deref(),
+        Some("ana@origem.com")
+    );
+    assert!(matches!(
+        srs::reverse(&dropped, &forwarded),
+        Err(SrsError::HashError(_))
+    ));
+
+    // Os novos endereços usam o segredo atual
+    let forwarded = srs::forward(&rotated, "ana@origem.com");
+    assert!(srs::reverse(&dropped, &forwarded).is_ok());
+    assert!(matches!(
+        srs::reverse(&old, &forwarded),
+        Err(SrsError::HashError(_))
+    ));
+}
+
+// Hashes sem separador só valem com os segredos anteriores
+#[test]
+fn legacy_hash_only_with_previous_secrets() {
+    let legacy = srs0(srs::legacy_hash, "antigo", 1);
+    assert_ne!(legacy, srs0(srs::hash, "antigo", 1));
+
+    let current = config("srs.example.net", "antigo", &[]);
+    assert!(matches!(
+        srs::reverse(&current, &legacy),
+        Err(SrsError::HashError(_))
+    ));
+
+    let rotated = config("srs.example.net", "novo", &["antigo"]);
+    assert_eq!(
+        srs::reverse(&rotated, &legacy).unwrap().as_deref(),
+        Some("ana@origem.com")
+    );
+}
@@ -0,0 +1,120 @@
+use anyhow::{Result, anyhow};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, AsyncWriteExt, BufReader},
+    net::{TcpStream, UnixStream},
+};
+
+use crate::{
+    queue::models::DeliveryJob,
+    smtp_client::{self, CONNECT_TIMEOUT, delivery_result::DeliveryResult},
+    transport::{Deliveries, Transport},
+};
+
+// Entrega por LMTP (RFC 2033), por exemplo para o Dovecot
+pub struct LmtpTransport {
+    // "host:porta" ou o caminho de um socket Unix
+    address: String,
+    hostname: String,
+}
+
+impl LmtpTransport {
+    pub fn new(address: &str, hostname: &str) -> Self {
+        Self {
+            address: address.to_string(),
+            hostname: hostname.to_string(),
+        }
+    }
+
+    async fn deliver_all(&self, jobs: &[DeliveryJob]) -> Result<Vec<DeliveryResult>> {
+        if self.address.starts_with('/') {
+            let stream =
+                tokio::time::timeout(CONNECT_TIMEOUT, UnixStream::connect(&self.address)).await??;
+            self.session(stream, jobs).await
+        } else {
+            let stream =
+                tokio::time::timeout(CONNECT_TIMEOUT, TcpStream::connect(&self.address)).await??;
+            self.session(stream, jobs).await
+        }
+    }
+
+    async fn session<S>(&self, stream: S, jobs: &[DeliveryJob]) -> Result<Vec<DeliveryResult>>
+    where
+        S: AsyncRead + AsyncWrite,
+    {
+        let (reader, mut writer) = tokio::io::split(stream);
+        let mut reader = BufReader::new(reader);
+        // Resultado da mensagem inteira, repetido para cada job
+        let all = |code, text: String| {
+            jobs.iter()
+                .map(|_| DeliveryResult::from_smtp_code(code, text.clone()))
+                .collect()
+        };
+
+        let (code, text) = smtp_client::read_reply(&mut reader).await?;
+        if code != 220 {
+            return Ok(all(code, text));
+        }
+        let from = &jobs[0].from_addr;
+        for (command, expected) in [
+            (format!("LHLO {}", self.hostname), 250),
+            (format!("MAIL FROM:<{}>", from), 250),
+        ] {
+            let (code, text) =
+                smtp_client::send_command(&mut reader, &mut writer, &command).await?;
+            if code != expected {
+                let _ = smtp_client::send_command(&mut reader, &mut writer, "QUIT").await;
+                return Ok(all(code, text));
+            }
+        }
+
+        // Recusas no RCPT já são o resultado daquele destinatário
+        let mut results: Vec<Option<DeliveryResult>> = Vec::with_capacity(jobs.len());
+        for job in jobs {
+            let command = format!("RCPT TO:<{}>", job.to_addr);
+            let (code, text) =
+                smtp_client::send_command(&mut reader, &mut writer, &command).await?;
+            results.push((code != 250).then(|| DeliveryResult::from_smtp_code(code, text)));
+        }
+
+        let accepted = results.iter().filter(|r| r.is_none()).count();
+        if accepted > 0 {
+            let (code, text) = smtp_client::send_command(&mut reader, &mut writer, "DATA").await?;
+            if code == 354 {
+                writer
+                    .write_all(smtp_client::dot_stuff(&jobs[0].raw_message).as_bytes())
+                    .await?;
+                writer.write_all(b".\r\n").await?;
+                // Uma resposta por destinatário aceito, na ordem dos RCPT
+                for result in results.iter_mut().filter(|r| r.is_none()) {
+                    let (code, text) = smtp_client::read_reply(&mut reader).await?;
+                    *result = Some(DeliveryResult::from_smtp_code(code, text));
+                }
+            } else {
+                for result in results.iter_mut().filter(|r| r.is_none()) {
+                    *result = Some(DeliveryResult::from_smtp_code(code, text.clone()));
+                }
+            }
+        }
+
+        let _ = smtp_client::send_command(&mut reader, &mut writer, "QUIT").await;
+        Ok(results.into_iter().flatten().collect())
+    }
+}
+
+impl Transport for LmtpTransport {
+    fn deliver_batch<'a>(&'a self, jobs: &'a [DeliveryJob]) -> Deliveries<'a> {
+        Box::pin(async move {
+            if jobs.is_empty() {
+                return Vec::new();
+            }
+            match self.deliver_all(jobs).await {
+                Ok(results) => results.into_iter().map(Ok).collect(),
+                // Uma falha de conexão vale para todos os destinatários
+                Err(e) => {
+                    let error = format!("{}: {}", self.address, e);
+                    jobs.iter().map(|_| Err(anyhow!(error.clone()))).collect()
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,195 @@
+use std::{
+    fs::{self, File},
+    io::{self, Seek, SeekFrom, Write},
+    os::unix::{fs::OpenOptionsExt, io::AsRawFd},
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+
+use crate::{
+    helpers::email_helper,
+    local_delivery,
+    queue::models::DeliveryJob,
+    smtp_client::delivery_result::DeliveryResult,
+    transport::{Deliveries, Transport},
+};
+
+// Tempo máximo esperando as travas de outro processo (MUA ou MDA)
+const LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+// Um .lock mais velho que isso foi deixado por um processo que morreu
+const STALE_DOTLOCK: Duration = Duration::from_secs(300);
+
+// Acrescenta as mensagens a um mbox por usuário, com dotlock e fcntl como os
+// leitores tradicionais (mutt, Dovecot)
+pub struct MboxTransport {
+    path: String,
+    recipient_delimiter: String,
+}
+
+impl MboxTransport {
+    pub fn new(path: &str, recipient_delimiter: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            recipient_delimiter: recipient_delimiter.to_string(),
+        }
+    }
+
+    async fn deliver(&self, job: &DeliveryJob) -> Result<DeliveryResult> {
+        let (local_part, domain) = job.to_addr.rsplit_once('@').unwrap_or((&job.to_addr, ""));
+        let (user, _) = email_helper::split_detail(local_part, &self.recipient_delimiter);
+        let user = user.to_ascii_lowercase();
+        if !local_delivery::is_safe_name(&user) || !local_delivery::is_safe_name(domain) {
+            return Ok(DeliveryResult::Permanent {
+                smtp_code: 553,
+                message: format!("Endereço {} inválido para entrega local", job.to_addr),
+            });
+        }
+        if local_delivery::already_delivered(&job.raw_message, &job.to_addr) {
+            return Ok(DeliveryResult::Permanent {
+                smtp_code: 554,
+                message: format!("Loop detectado: {} já recebeu esta mensagem", job.to_addr),
+            });
+        }
+
+        let path = local_delivery::expand_path(&self.path, &user, domain);
+        let entry = entry(job);
+        let target = path.clone();
+        tokio::task::spawn_blocking(move || append(&target, entry.as_bytes()))
+            .await?
+            .with_context(|| format!("Erro ao gravar em {}", path.display()))?;
+
+        Ok(DeliveryResult::Delivered {
+            smtp_code: 250,
+            message: format!("Entregue em {}", path.display()),
+        })
+    }
+}
+
+impl Transport for MboxTransport {
+    fn deliver_batch<'a>(&'a self, jobs: &'a [DeliveryJob]) -> Deliveries<'a> {
+        Box::pin(async move {
+            let mut results = Vec::with_capacity(jobs.len());
+            for job in jobs {
+                results.push(self.deliver(job).await);
+            }
+            results
+        })
+    }
+}
+
+// Linha From_, a mensagem com as linhas "From " citadas (mboxrd) e uma linha
+// em branco separando da próxima
+fn entry(job: &DeliveryJob) -> String {
+    let sender = if job.from_addr.is_empty() {
+        "MAILER-DAEMON"
+    } else {
+        &job.from_addr
+    };
+    let mut out = format!(
+        "From {} {}\n",
+        sender,
+        Utc::now().format("%a %b %e %H:%M:%S %Y")
+    );
+    for line in local_delivery::local_copy(job).lines() {
+        if line.trim_start_matches('>').starts_with("From ") {
+            out.push('>');
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+    out.push('\n');
+    out
+}
+
+fn append(path: &Path, entry: &[u8]) -> io::Result<()> {
+    let _dotlock = DotLock::acquire(path)?;
+    let mut file = fs::OpenOptions::new()
+        .append(true)
+        .create(true)
+        .mode(0o600)
+        .open(path)?;
+    lock(&file)?;
+
+    // Uma gravação incompleta é desfeita para não corromper o mbox
+    let original = file.seek(SeekFrom::End(0))?;
+    if let Err(e) = file.write_all(entry).and_then(|_| file.sync_all()) {
+        let _ = file.set_len(original);
+        return Err(e);
+    }
+    Ok(())
+}
+
+// Trava fcntl de escrita no arquivo inteiro, liberada ao fechar
+fn lock(file: &File) -> io::Result<()> {
+    let deadline = Instant::now() + LOCK_TIMEOUT;
+    loop {
+        let mut flock: libc::flock = unsafe { std::mem::zeroed() };
+        flock.l_type = libc::F_WRLCK as libc::c_short;
+        flock.l_whence = libc::SEEK_SET as libc::c_short;
+        if unsafe { libc::fcntl(file.as_raw_fd(), libc::F_SETLK, &flock) } == 0 {
+            return Ok(());
+        }
+        let e = io::Error::last_os_error();
+        if !matches!(e.raw_os_error(), Some(libc::EAGAIN) | Some(libc::EACCES))
+            || Instant::now() >= deadline
+        {
+            return Err(e);
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+}
+
+// <mbox>.lock, criado de forma exclusiva e removido ao sair de escopo. Sem
+// permissão no diretório (como em /var/mail), fica só a trava fcntl.
+struct DotLock(Option<PathBuf>);
+
+impl DotLock {
+    fn acquire(path: &Path) -> io::Result<Self> {
+        let mut name = path.as_os_str().to_owned();
+        name.push(".lock");
+        let lock_path = PathBuf::from(name);
+        let deadline = Instant::now() + LOCK_TIMEOUT;
+
+        loop {
+            match fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&lock_path)
+            {
+                Ok(_) => return Ok(DotLock(Some(lock_path))),
+                Err(e) if e.kind() == io::ErrorKind::PermissionDenied => return Ok(DotLock(None)),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                    let stale = fs::metadata(&lock_path)
+                        .and_then(|m| m.modified())
+                        .ok()
+                        .and_then(|t| SystemTime::now().duration_since(t).ok())
+                        .is_some_and(|age| age > STALE_DOTLOCK);
+                    if stale {
+                        let _ = fs::remove_file(&lock_path);
+                        continue;
+                    }
+                    if Instant::now() >= deadline {
+                        return Err(io::Error::new(
+                            io::ErrorKind::WouldBlock,
+                            format!("{} travado por outro processo", path.display()),
+                        ));
+                    }
+                    thread::sleep(Duration::from_millis(100));
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl Drop for DotLock {
+    fn drop(&mut self) {
+        if let Some(path) = &self.0 {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
@@ -0,0 +1,106 @@
+pub mod lmtp;
+pub mod mbox;
+
+use std::{collections::HashMap, future::Future, pin::Pin, sync::Arc};
+
+use anyhow::Result;
+
+use crate::{
+    config::Config,
+    local_delivery::LocalDelivery,
+    queue::models::DeliveryJob,
+    recipients::Recipients,
+    smtp_client::{SmtpClient, delivery_result::DeliveryResult},
+    transport::{lmtp::LmtpTransport, mbox::MboxTransport},
+};
+
+pub type Deliveries<'a> = Pin<Box<dyn Future<Output = Vec<Result<DeliveryResult>>> + Send + 'a>>;
+
+// Destino dos jobs da fila. Recebe jobs da mesma mensagem (mesmo remetente e
+// conteúdo) e devolve um resultado por job, na mesma ordem, para que o LMTP
+// entregue todos em uma transação.
+pub trait Transport: Send + Sync {
+    fn deliver_batch<'a>(&'a self, jobs: &'a [DeliveryJob]) -> Deliveries<'a>;
+}
+
+impl Transport for SmtpClient {
+    fn deliver_batch<'a>(&'a self, jobs: &'a [DeliveryJob]) -> Deliveries<'a> {
+        Box::pin(async move {
+            let mut results = Vec::with_capacity(jobs.len());
+            for job in jobs {
+                results.push(self.deliver(job).await);
+            }
+            results
+        })
+    }
+}
+
+impl Transport for LocalDelivery {
+    fn deliver_batch<'a>(&'a self, jobs: &'a [DeliveryJob]) -> Deliveries<'a> {
+        Box::pin(async move {
+            let mut results = Vec::with_capacity(jobs.len());
+            for job in jobs {
+                results.push(self.deliver(job).await);
+            }
+            results
+        })
+    }
+}
+
+// Transportes da configuração e a escolha de cada job
+pub struct Router {
+    config: Arc<Config>,
+    recipients: Arc<Recipients>,
+    transports: HashMap<String, Box<dyn Transport>>,
+}
+
+impl Router {
+    pub fn new(config: Arc<Config>, recipients: Arc<Recipients>) -> Result<Self> {
+        let mut transports: HashMap<String, Box<dyn Transport>> = HashMap::new();
+        transports.insert(
+            "smtp".to_string(),
+            Box::new(SmtpClient::new(config.clone())?),
+        );
+        transports.insert(
+            "maildir".to_string(),
+            Box::new(LocalDelivery::new(config.clone(), recipients.clone())),
+        );
+        for transport in &config.delivery.transports {
+            let built: Box<dyn Transport> = match transport.kind.as_str() {
+                "lmtp" => Box::new(LmtpTransport::new(
+                    &transport.address,
+                    &config.server.hostname,
+                )),
+                _ => Box::new(MboxTransport::new(
+                    &transport.path,
+                    &config.local_delivery.recipient_delimiter,
+                )),
+            };
+            transports.insert(transport.name.clone(), built);
+        }
+
+        Ok(Self {
+            config,
+            recipients,
+            transports,
+        })
+    }
+
+    // Rotas primeiro; sem rota, maildir para os local_domains e os domínios
+    // do mapa de caixas virtuais, e smtp para o resto
+    pub fn route<'a>(&'a self, to_addr: &str, domain: &str) -> &'a str {
+        let delimiter = &self.config.local_delivery.recipient_delimiter;
+        if let Some(name) = self.config.delivery.route(to_addr, delimiter) {
+            return name;
+        }
+        if self.recipients.is_mailbox_domain(domain) {
+            "maildir"
+        } else {
+            "smtp"
+        }
+    }
+
+    pub fn transport(&self, name: &str) -> Option<&dyn Transport> {
+        self.transports.get(name).map(|t| t.as_ref())
+    }
+}